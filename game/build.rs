@@ -14,7 +14,7 @@ impl<'ast> Visit<'ast> for NmdlImportMacroFinder {
     // Visit all macro invocations
     fn visit_macro(&mut self, mac: &'ast Macro) {
         let macro_name = mac.path.segments.last().unwrap().ident.to_string();
-        let macro_names = ["nmdl_import", "nmdl_import_w_collider"];
+        let macro_names = ["nmdl_import", "nmdl_import_w_collider", "nmdl_import_hierarchy", "nmdl_load"];
 
         if macro_names.contains(&macro_name.as_str()) {
             // Attempt to extract the first argument
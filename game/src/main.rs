@@ -1,10 +1,13 @@
 use glengine::engine::drawable::manager::DRAWABLE_MANAGER;
+use glengine::engine::drawable::asset_server::ASSET_SERVER;
 use glengine::engine::drawable::shader::manager::IncludedShaderHandle;
 use glam::Vec3;
 use glengine::engine::drawable::base::BaseDrawable;
 use glengine::engine::drawable::importer::nmdl::import_w_collider;
 use glengine::engine::drawable::material::{Material, MaterialData};
+use glengine::engine::drawable::material::render_state::{BlendMode, RenderState};
 use glengine::engine::scene::camera::Camera;
+use glengine::engine::scene::gameobject::components::character::CharacterControllerComponent;
 use glengine::engine::scene::gameobject::components::collider::ColliderComponent;
 use glengine::engine::scene::gameobject::components::drawable::DrawableComponent;
 use glengine::engine::scene::gameobject::components::rigidbody::RigidBodyComponent;
@@ -20,7 +23,7 @@ use glengine::engine::Engine;
 use glengine::engine::GameData;
 use glengine::glam::{vec3, Mat4, Quat};
 use glengine::result::EngineRenderResult;
-use glengine::{gl, nmdl_import, nmdl_import_w_collider};
+use glengine::{gl, nmdl_import_w_collider, nmdl_load};
 use rand::Rng;
 use rapier3d::prelude::ColliderBuilder;
 use rapier3d::prelude::RigidBodyBuilder;
@@ -30,6 +33,10 @@ fn cube_rain(parent: Option<GameObject>, center: Vec3, size: Vec3, count: usize,
     let mut rng = rand::thread_rng();
     let (mut drawable, collider) = nmdl_import_w_collider!("monkeylp.obj", scale);
     drawable.draw_data[0].shader_handle = IncludedShaderHandle::LitColor.into();
+    if cfg!(debug_assertions) {
+        let source = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("models").join("monkeylp.obj");
+        ASSET_SERVER.with(|server| server.borrow_mut().watch_model(source, &drawable));
+    }
     for _ in 0..count {
         let x = rng.gen_range(center.x - size.x..center.x + size.x);
         let y = rng.gen_range(center.y - size.y..center.y + size.y);
@@ -56,18 +63,8 @@ fn new_simulated_cube(
     let mut drawable = drawable.clone();
     let mut collider = collider_component.clone();
     let data = GameObject::new(parent);
-    let mut rand_vec3 = || vec3(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0));
-    let material = Material {
-        data: MaterialData {
-            ambient: Some(rand_vec3()),
-            diffuse: Some(rand_vec3()),
-            specular: Some(rand_vec3()),
-            shininess: Some(0.02),
-        },
-        textures: Default::default(),
-    };
-    let material_handle = DRAWABLE_MANAGER.with(|dm| dm.borrow_mut().material.add(material));
-    drawable.draw_data[0].material_handle = Some(material_handle);
+    // the cubes keep the material of the model so they are drawn as one instanced batch
+    drawable.draw_data[0].color = vec3(rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0), rng.gen_range(0.0..1.0)).extend(1.0);
     {
         let mut data = data.base.borrow_mut();
         data.add_component(DrawableComponent::new(Box::new(drawable)));
@@ -119,6 +116,7 @@ fn new_simulated_cube(
                     diffuse: Some(vec3(1.0, 0.4, 0.6)),
                     specular: Some(vec3(1.0, 0.5, 0.7)),
                     shininess: Some(0.02),
+                    ..Default::default()
                 },
                 textures: Default::default(),
             };
@@ -157,6 +155,8 @@ fn new_simulated_cube(
                     diffuse: Some(vec3(1.0, 0.4, 0.6)),
                     specular: Some(vec3(1.0, 0.5, 0.7)),
                     shininess: Some(0.02),
+                    render_state: RenderState::default().with_blend(BlendMode::AlphaBlend),
+                    ..Default::default()
                 },
                 textures: Default::default(),
             };
@@ -164,6 +164,7 @@ fn new_simulated_cube(
             let mut drawable = BaseDrawable::default();
             drawable.draw_data[0].shader_handle = IncludedShaderHandle::LitColor.into();
             drawable.draw_data[0].material_handle = Some(material_handle);
+            drawable.draw_data[0].color.w = 0.5;
             let mut data = cube.base.borrow_mut();
             data.add_component(DrawableComponent::new(Box::new(drawable)));
             data.data.transform.scale *= 4.0;
@@ -177,27 +178,32 @@ fn new_simulated_cube(
         let rotator = GameObject::new(Some(empty.clone()));
         {
             let mut data = rotator.base.borrow_mut();
-            let drawable = nmdl_import!("bugatticlean.obj");
-            data.add_component(DrawableComponent::new(Box::new(drawable)));
+            data.add_component(DrawableComponent::loading(nmdl_load!("bugatticlean.obj")));
             data.add_component(RotatingComponent::new(vec3(0.0, 0.14, 0.0)));
             data.data.transform.scale *= 0.3;
             data.data.transform.position = vec3(0.0, 0.0, 0.0);
         }
 
-        let camera = Camera::new(
+        let player = GameObject::new_w_transform(
             None,
-            vec3(20.0, 20.0, 20.0),
-            vec3(0.0, 0.0, 0.0),
-            vec3(0.0, 1.0, 0.0),
+            Transform::default()
+                .with_position(vec3(20.0, 0.0, 20.0))
+                .with_rotation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_4)),
         );
+        let head = GameObject::new_w_transform(
+            Some(player.clone()),
+            Transform::default().with_position(vec3(0.0, 0.7, 0.0)),
+        );
+        let camera = Camera::new_w_gameobject(head.clone());
         {
-            let mut data = camera.game_object.base.borrow_mut();
-            data.add_component(RigidBodyComponent::from(RigidBodyBuilder::fixed().build()));
+            let mut data = player.base.borrow_mut();
+            data.add_component(RigidBodyComponent::from(
+                RigidBodyBuilder::kinematic_position_based().build(),
+            ));
             data.add_component(ColliderComponent::from(
-                ColliderBuilder::ball(1.0)
-                    .mass(20000000.0)
-                    .build(),
+                ColliderBuilder::capsule_y(0.6, 0.3).build(),
             ));
+            data.add_component(CharacterControllerComponent::new(Some(head.clone())));
         }
 
         let spot_light = SpotLight::new(
@@ -243,7 +249,7 @@ fn new_simulated_cube(
 
         scene.lights.directional = Some(directional_light);
 
-        scene.objects.push(camera.game_object.clone());
+        scene.objects.push(player.clone());
         scene.main_camera = Some(camera);
         Ok(())
     }
@@ -0,0 +1,264 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use glengine_mdl::error::{NmdlError, NmdlResult};
+use glengine_mdl::models::{
+    is_importable, FileStruct, Header, ImportOptions, NodeStruct, TextureCompression, TextureData, EXTENSION,
+};
+
+const USAGE: &str = "Usage:
+  nmdl convert <source file or directory> [destination] [options]
+  nmdl info <file.nmdl>
+  nmdl validate <file.nmdl>...
+  nmdl extract-textures <file.nmdl> [directory]
+
+convert options:
+  --png             store embedded textures as PNG instead of raw RGBA
+  --flip-uvs        flip the texture coordinates vertically
+  --smooth-normals  generate smooth instead of flat normals when the source has none
+  --optimize        reorder triangles and vertices for the vertex cache and overdraw
+  --quantize        store normals, tangents and texture coordinates in compact formats
+  --lods=<count>    generate up to count simplified levels of detail per mesh";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (flags, args): (Vec<&String>, Vec<&String>) = args.iter().partition(|arg| arg.starts_with("--"));
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+    let result = match args.as_slice() {
+        ["convert", source, rest @ ..] if rest.len() <= 1 => match import_options(&flags) {
+            Some(options) => convert(Path::new(source), rest.first().map(Path::new), &options),
+            None => return usage(),
+        },
+        ["info", file] => info(file),
+        ["validate", files @ ..] if !files.is_empty() => return validate(files),
+        ["extract-textures", file, rest @ ..] if rest.len() <= 1 => {
+            extract_textures(file, Path::new(rest.first().unwrap_or(&".")))
+        }
+        _ => return usage(),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{}", USAGE);
+    ExitCode::FAILURE
+}
+
+fn import_options(flags: &[&String]) -> Option<ImportOptions> {
+    let mut options = ImportOptions::default();
+    for flag in flags {
+        match flag.as_str() {
+            "--png" => options.texture_compression = TextureCompression::Png,
+            "--flip-uvs" => options.flip_uvs = true,
+            "--smooth-normals" => options.smooth_normals = true,
+            "--optimize" => options.optimize = true,
+            "--quantize" => options.quantize = true,
+            flag if flag.starts_with("--lods=") => match flag["--lods=".len()..].parse() {
+                Ok(levels) => options.lod_levels = levels,
+                Err(_) => {
+                    eprintln!("Invalid level count in {}", flag);
+                    return None;
+                }
+            },
+            _ => {
+                eprintln!("Unknown option {}", flag);
+                return None;
+            }
+        }
+    }
+    Some(options)
+}
+
+// model.obj becomes model.obj.nmdl, keeping the source extension
+fn nmdl_name(file: &Path) -> PathBuf {
+    let mut name = file.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(EXTENSION);
+    file.with_file_name(name)
+}
+
+fn source_files(dir: &Path, files: &mut Vec<PathBuf>) -> NmdlResult<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            source_files(&path, files)?;
+        } else if is_importable(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn convert_file(source: &Path, destination: &Path, options: &ImportOptions) -> NmdlResult<()> {
+    let file = FileStruct::import_with_options(source.to_str().unwrap(), options)?;
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    file.save(destination.to_str().unwrap())?;
+    println!("{} -> {}", source.display(), destination.display());
+    Ok(())
+}
+
+// A directory is converted recursively, mirroring its layout in the destination.
+fn convert(source: &Path, destination: Option<&Path>, options: &ImportOptions) -> NmdlResult<()> {
+    if !source.is_dir() {
+        let destination = destination.map(Path::to_path_buf).unwrap_or_else(|| nmdl_name(source));
+        return convert_file(source, &destination, options);
+    }
+    let mut files = vec![];
+    source_files(source, &mut files)?;
+    let mut failed = 0;
+    for file in &files {
+        let relative = file.strip_prefix(source).unwrap();
+        let output = nmdl_name(&destination.unwrap_or(source).join(relative));
+        if let Err(e) = convert_file(file, &output, options) {
+            eprintln!("{}: {}", file.display(), e);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(NmdlError::Invalid(format!("{} of {} models could not be converted", failed, files.len())));
+    }
+    Ok(())
+}
+
+fn format_bounds(bounds: Option<([f32; 3], [f32; 3])>) -> String {
+    match bounds {
+        Some((min, max)) => format!("{:?} to {:?}", min, max),
+        None => "empty".to_string(),
+    }
+}
+
+fn node_count(node: &NodeStruct) -> usize {
+    1 + node.children.iter().map(node_count).sum::<usize>()
+}
+
+fn info(path: &str) -> NmdlResult<()> {
+    let header = Header::read(path)?;
+    let file = FileStruct::load(path)?;
+    println!("{} version {}", header.magic, header.version);
+
+    println!("Meshes: {}", file.meshes.len());
+    let mut bounds: Option<([f32; 3], [f32; 3])> = None;
+    for (index, mesh) in file.meshes.iter().enumerate() {
+        let mesh_bounds = mesh.bounds.map(|bounds| (bounds.min, bounds.max));
+        println!(
+            "  {} {:?}: {} vertices, {} indices, material {}{}{}{}",
+            index,
+            mesh.name,
+            mesh.vertices.len() / 3,
+            mesh.indices.len(),
+            mesh.material_index,
+            if mesh.tangents.is_some() || mesh.packed.as_ref().is_some_and(|packed| packed.tangents.is_some()) { ", tangents" } else { "" },
+            if mesh.packed.is_some() { ", packed" } else { "" },
+            mesh.skin.as_ref().map(|skin| format!(", {} bones", skin.bones.len())).unwrap_or_default(),
+        );
+        for (level, lod) in mesh.lods.iter().enumerate() {
+            println!("     lod {}: {} indices, error {:.4}", level + 1, lod.indices.len(), lod.error);
+        }
+        match &mesh.bounds {
+            Some(bounds) => println!("     bounds {}, radius {:.4}", format_bounds(mesh_bounds), bounds.radius),
+            None => println!("     bounds {}", format_bounds(mesh_bounds)),
+        }
+        if let Some((min, max)) = mesh_bounds {
+            bounds = Some(match bounds {
+                Some((total_min, total_max)) => (
+                    [0, 1, 2].map(|axis| total_min[axis].min(min[axis])),
+                    [0, 1, 2].map(|axis| total_max[axis].max(max[axis])),
+                ),
+                None => (min, max),
+            });
+        }
+    }
+    // mesh space bounds, node transforms are not applied
+    println!("Bounds: {}", format_bounds(bounds));
+
+    println!("Materials: {}", file.materials.materials.len());
+    for (index, material) in file.materials.materials.iter().enumerate() {
+        let textures = &material.texture;
+        let names: Vec<String> = [
+            ("diffuse", &textures.diffuse),
+            ("specular", &textures.specular),
+            ("normal", &textures.normal),
+            ("base color", &textures.base_color),
+            ("metallic/roughness", &textures.metallic_roughness),
+            ("ambient occlusion", &textures.ambient_occlusion),
+            ("emissive", &textures.emissive),
+        ]
+        .iter()
+        .filter_map(|(kind, name)| name.as_ref().map(|name| format!("{} {:?}", kind, name)))
+        .collect();
+        println!(
+            "  {} {}: {}",
+            index,
+            if material.pbr.is_some() { "pbr" } else { "phong" },
+            if names.is_empty() { "no textures".to_string() } else { names.join(", ") },
+        );
+    }
+
+    println!("Textures: {}", file.materials.textures.len());
+    for (name, texture) in &file.materials.textures {
+        let (storage, bytes) = match &texture.data {
+            TextureData::Rgba(data) => ("rgba", data.len()),
+            TextureData::Png(data) => ("png", data.len()),
+        };
+        println!("  {:?}: {}x{} {} {} bytes", name, texture.width, texture.height, storage, bytes);
+    }
+
+    println!("Nodes: {}", node_count(&file.root));
+    println!("Animations: {}", file.animations.len());
+    for animation in &file.animations {
+        println!("  {:?}: {:.2}s, {} channels", animation.name, animation.duration, animation.channels.len());
+    }
+    Ok(())
+}
+
+fn validate(files: &[&str]) -> ExitCode {
+    let mut valid = true;
+    for file in files {
+        match FileStruct::load(file) {
+            Ok(_) => println!("{}: ok", file),
+            Err(e) => {
+                println!("{}: {}", file, e);
+                valid = false;
+            }
+        }
+    }
+    if valid { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
+
+// Embedded names can be paths or assimp references like "*0", only the file name is kept.
+fn texture_file_name(name: &str, index: usize) -> String {
+    let stem = Path::new(name)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(|stem| stem.trim_start_matches('*'))
+        .filter(|stem| !stem.is_empty())
+        .map(|stem| stem.to_string())
+        .unwrap_or_else(|| index.to_string());
+    format!("{}.png", stem)
+}
+
+fn extract_textures(path: &str, directory: &Path) -> NmdlResult<()> {
+    let file = FileStruct::load(path)?;
+    std::fs::create_dir_all(directory)?;
+    let mut names: Vec<&String> = file.materials.textures.keys().collect();
+    names.sort();
+    let mut written = vec![];
+    for (index, name) in names.into_iter().enumerate() {
+        let mut file_name = texture_file_name(name, index);
+        if written.contains(&file_name) {
+            file_name = format!("{}_{}", index, file_name);
+        }
+        let output = directory.join(&file_name);
+        file.materials.textures[name].image()?.save(&output)?;
+        println!("{:?} -> {}", name, output.display());
+        written.push(file_name);
+    }
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use thiserror::Error;
+
+pub type NmdlResult<T> = Result<T, NmdlError>;
+
+#[derive(Error, Debug)]
+pub enum NmdlError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to encode NMDL: {0}")]
+    Encode(#[from] bincode::error::EncodeError),
+    #[error("Failed to decode NMDL: {0}")]
+    Decode(#[from] bincode::error::DecodeError),
+    #[error("Not an NMDL file, found magic {0:?}")]
+    InvalidMagic(String),
+    #[error("Unsupported NMDL version {found}, supported versions are 1 to {supported}")]
+    UnsupportedVersion { found: u32, supported: u32 },
+    #[error("Invalid NMDL data: {0}")]
+    Invalid(String),
+    #[error("Failed to import model: {0}")]
+    Import(#[from] russimp::RussimpError),
+    #[error("Texture error: {0}")]
+    Texture(#[from] image::ImageError),
+}
@@ -1 +1,4 @@
-pub mod models;
\ No newline at end of file
+pub mod error;
+mod migrate;
+pub mod optimize;
+pub mod models;
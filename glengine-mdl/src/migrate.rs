@@ -0,0 +1,788 @@
+// Layouts of the older NMDL versions. Each one is decoded as it was written and converted
+// one version up until it reaches the current layout, a layout change freezes the current
+// structs here as the previous version.
+use std::collections::HashMap;
+use bincode::Decode;
+
+use crate::error::{NmdlError, NmdlResult};
+use crate::models::{
+    AnimationStruct, BoundsStruct, FileStruct, Header, MaterialStruct, MaterialsStruct, MeshStruct, NodeStruct, PbrStruct,
+    SkinStruct, TextureData, TextureStruct, TexturesStruct, IDENTITY, VERSION,
+};
+
+mod v1 {
+    use super::*;
+
+    #[derive(Decode)]
+    pub struct MeshStruct {
+        pub name: String,
+        pub material_index: u32,
+        pub vertices: Vec<f32>,
+        pub normals: Vec<f32>,
+        pub indices: Vec<u32>,
+        pub texture_coords: Option<Vec<f32>>,
+    }
+
+    #[derive(Decode)]
+    pub struct TexturesStruct {
+        pub diffuse: Option<String>,
+        pub specular: Option<String>,
+        pub normal: Option<String>,
+    }
+
+    #[derive(Decode)]
+    pub struct MaterialStruct {
+        pub texture: TexturesStruct,
+        pub ambient: Option<(f32, f32, f32)>,
+        pub diffuse: Option<(f32, f32, f32)>,
+        pub specular: Option<(f32, f32, f32)>,
+        pub shininess: Option<f32>,
+    }
+
+    // the size was taken from assimp, which leaves it at zero for textures read from disk
+    #[derive(Decode)]
+    pub struct TextureStruct {
+        pub name: String,
+        pub height: u32,
+        pub width: u32,
+        pub data: Vec<(u8, u8, u8, u8)>,
+    }
+
+    #[derive(Decode)]
+    pub struct MaterialsStruct {
+        pub materials: Vec<MaterialStruct>,
+        pub textures: HashMap<String, TextureStruct>,
+    }
+
+    #[derive(Decode)]
+    pub struct FileStruct {
+        pub magic: Header,
+        pub meshes: Vec<MeshStruct>,
+        pub materials: MaterialsStruct,
+    }
+}
+
+// added the metallic/roughness material
+mod v2 {
+    use super::*;
+
+    #[derive(Decode)]
+    pub struct TexturesStruct {
+        pub diffuse: Option<String>,
+        pub specular: Option<String>,
+        pub normal: Option<String>,
+        pub base_color: Option<String>,
+        pub metallic_roughness: Option<String>,
+        pub ambient_occlusion: Option<String>,
+        pub emissive: Option<String>,
+    }
+
+    #[derive(Decode)]
+    pub struct PbrStruct {
+        pub base_color: Option<(f32, f32, f32, f32)>,
+        pub metallic: Option<f32>,
+        pub roughness: Option<f32>,
+        pub emissive: Option<(f32, f32, f32)>,
+    }
+
+    #[derive(Decode)]
+    pub struct MaterialStruct {
+        pub texture: TexturesStruct,
+        pub ambient: Option<(f32, f32, f32)>,
+        pub diffuse: Option<(f32, f32, f32)>,
+        pub specular: Option<(f32, f32, f32)>,
+        pub shininess: Option<f32>,
+        pub pbr: Option<PbrStruct>,
+    }
+
+    #[derive(Decode)]
+    pub struct MaterialsStruct {
+        pub materials: Vec<MaterialStruct>,
+        pub textures: HashMap<String, v1::TextureStruct>,
+    }
+
+    #[derive(Decode)]
+    pub struct FileStruct {
+        pub magic: Header,
+        pub meshes: Vec<v1::MeshStruct>,
+        pub materials: MaterialsStruct,
+    }
+
+    impl From<v1::FileStruct> for FileStruct {
+        fn from(file: v1::FileStruct) -> Self {
+            let materials = file
+                .materials
+                .materials
+                .into_iter()
+                .map(|material| MaterialStruct {
+                    texture: TexturesStruct {
+                        diffuse: material.texture.diffuse,
+                        specular: material.texture.specular,
+                        normal: material.texture.normal,
+                        base_color: None,
+                        metallic_roughness: None,
+                        ambient_occlusion: None,
+                        emissive: None,
+                    },
+                    ambient: material.ambient,
+                    diffuse: material.diffuse,
+                    specular: material.specular,
+                    shininess: material.shininess,
+                    pbr: None,
+                })
+                .collect();
+            FileStruct {
+                magic: file.magic,
+                meshes: file.meshes,
+                materials: MaterialsStruct {
+                    materials,
+                    textures: file.materials.textures,
+                },
+            }
+        }
+    }
+}
+
+// added the tangents and bitangents
+mod v3 {
+    use super::*;
+
+    #[derive(Decode)]
+    pub struct MeshStruct {
+        pub name: String,
+        pub material_index: u32,
+        pub vertices: Vec<f32>,
+        pub normals: Vec<f32>,
+        pub indices: Vec<u32>,
+        pub texture_coords: Option<Vec<f32>>,
+        pub tangents: Option<Vec<f32>>,
+        pub bitangents: Option<Vec<f32>>,
+    }
+
+    #[derive(Decode)]
+    pub struct FileStruct {
+        pub magic: Header,
+        pub meshes: Vec<MeshStruct>,
+        pub materials: v2::MaterialsStruct,
+    }
+
+    impl From<v2::FileStruct> for FileStruct {
+        fn from(file: v2::FileStruct) -> Self {
+            let meshes = file
+                .meshes
+                .into_iter()
+                .map(|mesh| MeshStruct {
+                    name: mesh.name,
+                    material_index: mesh.material_index,
+                    vertices: mesh.vertices,
+                    normals: mesh.normals,
+                    indices: mesh.indices,
+                    texture_coords: mesh.texture_coords,
+                    tangents: None,
+                    bitangents: None,
+                })
+                .collect();
+            FileStruct {
+                magic: file.magic,
+                meshes,
+                materials: file.materials,
+            }
+        }
+    }
+}
+
+// stores the embedded textures as RGBA or PNG with their decoded size
+mod v4 {
+    use super::*;
+
+    #[derive(Decode)]
+    pub struct TextureStruct {
+        pub name: String,
+        pub height: u32,
+        pub width: u32,
+        pub data: TextureData,
+    }
+
+    #[derive(Decode)]
+    pub struct MaterialsStruct {
+        pub materials: Vec<v2::MaterialStruct>,
+        pub textures: HashMap<String, TextureStruct>,
+    }
+
+    #[derive(Decode)]
+    pub struct FileStruct {
+        pub magic: Header,
+        pub meshes: Vec<v3::MeshStruct>,
+        pub materials: MaterialsStruct,
+    }
+
+    impl From<v3::FileStruct> for FileStruct {
+        fn from(file: v3::FileStruct) -> Self {
+            // textures without a usable size are dropped, the loader then reads them from disk
+            let textures = file
+                .materials
+                .textures
+                .into_iter()
+                .filter(|(_, texture)| {
+                    texture.width > 0 && (texture.width as usize * texture.height as usize) == texture.data.len()
+                })
+                .map(|(name, texture)| {
+                    let data = texture.data.iter().flat_map(|c| [c.0, c.1, c.2, c.3]).collect();
+                    let texture = TextureStruct {
+                        name: texture.name,
+                        height: texture.height,
+                        width: texture.width,
+                        data: TextureData::Rgba(data),
+                    };
+                    (name, texture)
+                })
+                .collect();
+            FileStruct {
+                magic: file.magic,
+                meshes: file.meshes,
+                materials: MaterialsStruct {
+                    materials: file.materials.materials,
+                    textures,
+                },
+            }
+        }
+    }
+}
+
+// added the node hierarchy, older files get a single root holding every mesh
+mod v5 {
+    use super::*;
+
+    #[derive(Decode)]
+    pub struct NodeStruct {
+        pub name: String,
+        pub transform: [f32; 16],
+        pub meshes: Vec<u32>,
+        pub children: Vec<NodeStruct>,
+    }
+
+    impl NodeStruct {
+        pub fn flat(mesh_count: usize) -> Self {
+            NodeStruct {
+                name: String::new(),
+                transform: IDENTITY,
+                meshes: (0..mesh_count as u32).collect(),
+                children: Vec::new(),
+            }
+        }
+    }
+
+    #[derive(Decode)]
+    pub struct FileStruct {
+        pub magic: Header,
+        pub meshes: Vec<v3::MeshStruct>,
+        pub materials: v4::MaterialsStruct,
+        pub root: NodeStruct,
+    }
+
+    impl From<v4::FileStruct> for FileStruct {
+        fn from(file: v4::FileStruct) -> Self {
+            FileStruct {
+                magic: file.magic,
+                root: NodeStruct::flat(file.meshes.len()),
+                meshes: file.meshes,
+                materials: file.materials,
+            }
+        }
+    }
+}
+
+fn convert_node(node: v5::NodeStruct) -> NodeStruct {
+    NodeStruct {
+        name: node.name,
+        transform: node.transform,
+        meshes: node.meshes,
+        children: node.children.into_iter().map(convert_node).collect(),
+    }
+}
+
+// version 6 added the skins and animations
+mod v6 {
+    use super::*;
+
+    #[derive(Decode)]
+    pub struct MeshStruct {
+        pub name: String,
+        pub material_index: u32,
+        pub vertices: Vec<f32>,
+        pub normals: Vec<f32>,
+        pub indices: Vec<u32>,
+        pub texture_coords: Option<Vec<f32>>,
+        pub tangents: Option<Vec<f32>>,
+        pub bitangents: Option<Vec<f32>>,
+        pub skin: Option<SkinStruct>,
+    }
+
+    #[derive(Decode)]
+    pub struct FileStruct {
+        pub magic: Header,
+        pub meshes: Vec<MeshStruct>,
+        pub materials: MaterialsStruct,
+        pub root: NodeStruct,
+        pub animations: Vec<AnimationStruct>,
+    }
+
+    impl From<v5::FileStruct> for FileStruct {
+        fn from(file: v5::FileStruct) -> Self {
+            let meshes: Vec<MeshStruct> = file
+                .meshes
+                .into_iter()
+                .map(|mesh| MeshStruct {
+                    name: mesh.name,
+                    material_index: mesh.material_index,
+                    vertices: mesh.vertices,
+                    normals: mesh.normals,
+                    indices: mesh.indices,
+                    texture_coords: mesh.texture_coords,
+                    tangents: mesh.tangents,
+                    bitangents: mesh.bitangents,
+                    skin: None,
+                })
+                .collect();
+            let materials = file
+                .materials
+                .materials
+                .into_iter()
+                .map(|material| MaterialStruct {
+                    texture: TexturesStruct {
+                        diffuse: material.texture.diffuse,
+                        specular: material.texture.specular,
+                        normal: material.texture.normal,
+                        base_color: material.texture.base_color,
+                        metallic_roughness: material.texture.metallic_roughness,
+                        ambient_occlusion: material.texture.ambient_occlusion,
+                        emissive: material.texture.emissive,
+                    },
+                    ambient: material.ambient,
+                    diffuse: material.diffuse,
+                    specular: material.specular,
+                    shininess: material.shininess,
+                    pbr: material.pbr.map(|pbr| PbrStruct {
+                        base_color: pbr.base_color,
+                        metallic: pbr.metallic,
+                        roughness: pbr.roughness,
+                        emissive: pbr.emissive,
+                    }),
+                })
+                .collect();
+            let textures = file
+                .materials
+                .textures
+                .into_iter()
+                .map(|(name, texture)| {
+                    let texture = TextureStruct {
+                        name: texture.name,
+                        height: texture.height,
+                        width: texture.width,
+                        data: texture.data,
+                    };
+                    (name, texture)
+                })
+                .collect();
+            FileStruct {
+                magic: Header::new(),
+                meshes,
+                materials: MaterialsStruct { materials, textures },
+                root: convert_node(file.root),
+                animations: Vec::new(),
+            }
+        }
+    }
+}
+
+// version 7 added the levels of detail and packed attributes
+mod v7 {
+    use super::*;
+    use crate::models::{LodStruct, PackedStruct};
+
+    #[derive(Decode)]
+    pub struct MeshStruct {
+        pub name: String,
+        pub material_index: u32,
+        pub vertices: Vec<f32>,
+        pub normals: Vec<f32>,
+        pub indices: Vec<u32>,
+        pub texture_coords: Option<Vec<f32>>,
+        pub tangents: Option<Vec<f32>>,
+        pub bitangents: Option<Vec<f32>>,
+        pub skin: Option<SkinStruct>,
+        pub lods: Vec<LodStruct>,
+        pub packed: Option<PackedStruct>,
+    }
+
+    #[derive(Decode)]
+    pub struct FileStruct {
+        pub magic: Header,
+        pub meshes: Vec<MeshStruct>,
+        pub materials: MaterialsStruct,
+        pub root: NodeStruct,
+        pub animations: Vec<AnimationStruct>,
+    }
+
+    impl From<v6::FileStruct> for FileStruct {
+        fn from(file: v6::FileStruct) -> Self {
+            let meshes = file
+                .meshes
+                .into_iter()
+                .map(|mesh| MeshStruct {
+                    name: mesh.name,
+                    material_index: mesh.material_index,
+                    vertices: mesh.vertices,
+                    normals: mesh.normals,
+                    indices: mesh.indices,
+                    texture_coords: mesh.texture_coords,
+                    tangents: mesh.tangents,
+                    bitangents: mesh.bitangents,
+                    skin: mesh.skin,
+                    lods: Vec::new(),
+                    packed: None,
+                })
+                .collect();
+            FileStruct {
+                magic: file.magic,
+                meshes,
+                materials: file.materials,
+                root: file.root,
+                animations: file.animations,
+            }
+        }
+    }
+}
+
+// version 8 added the mesh bounds
+impl From<v7::FileStruct> for FileStruct {
+    fn from(file: v7::FileStruct) -> Self {
+        let meshes = file
+            .meshes
+            .into_iter()
+            .map(|mesh| MeshStruct {
+                bounds: BoundsStruct::from_vertices(&mesh.vertices),
+                name: mesh.name,
+                material_index: mesh.material_index,
+                vertices: mesh.vertices,
+                normals: mesh.normals,
+                indices: mesh.indices,
+                texture_coords: mesh.texture_coords,
+                tangents: mesh.tangents,
+                bitangents: mesh.bitangents,
+                skin: mesh.skin,
+                lods: mesh.lods,
+                packed: mesh.packed,
+            })
+            .collect();
+        FileStruct {
+            magic: Header::new(),
+            meshes,
+            materials: file.materials,
+            root: file.root,
+            animations: file.animations,
+        }
+    }
+}
+
+// Decodes a file of any supported version into the current layout.
+pub(crate) fn decode(version: u32, bytes: &[u8]) -> NmdlResult<FileStruct> {
+    let config = bincode::config::standard();
+    let file = match version {
+        1 => {
+            let (file, _): (v1::FileStruct, usize) = bincode::decode_from_slice(bytes, config)?;
+            v7::FileStruct::from(v6::FileStruct::from(v5::FileStruct::from(v4::FileStruct::from(v3::FileStruct::from(v2::FileStruct::from(file)))))).into()
+        }
+        2 => {
+            let (file, _): (v2::FileStruct, usize) = bincode::decode_from_slice(bytes, config)?;
+            v7::FileStruct::from(v6::FileStruct::from(v5::FileStruct::from(v4::FileStruct::from(v3::FileStruct::from(file))))).into()
+        }
+        3 => {
+            let (file, _): (v3::FileStruct, usize) = bincode::decode_from_slice(bytes, config)?;
+            v7::FileStruct::from(v6::FileStruct::from(v5::FileStruct::from(v4::FileStruct::from(file)))).into()
+        }
+        4 => {
+            let (file, _): (v4::FileStruct, usize) = bincode::decode_from_slice(bytes, config)?;
+            v7::FileStruct::from(v6::FileStruct::from(v5::FileStruct::from(file))).into()
+        }
+        5 => {
+            let (file, _): (v5::FileStruct, usize) = bincode::decode_from_slice(bytes, config)?;
+            v7::FileStruct::from(v6::FileStruct::from(file)).into()
+        }
+        6 => {
+            let (file, _): (v6::FileStruct, usize) = bincode::decode_from_slice(bytes, config)?;
+            v7::FileStruct::from(file).into()
+        }
+        7 => {
+            let (file, _): (v7::FileStruct, usize) = bincode::decode_from_slice(bytes, config)?;
+            file.into()
+        }
+        VERSION => {
+            let (file, _): (FileStruct, usize) = bincode::decode_from_slice(bytes, config)?;
+            file
+        }
+        found => {
+            return Err(NmdlError::UnsupportedVersion {
+                found,
+                supported: VERSION,
+            })
+        }
+    };
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use bincode::Encode;
+
+    use super::*;
+    use crate::models::{BoneStruct, LodStruct, PackedStruct, MAGIC, MAX_BONE_INFLUENCES};
+
+    const TRIANGLE: [f32; 9] = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 2.0, 0.0];
+    const NORMALS: [f32; 9] = [0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0];
+
+    // the frozen layouts only decode, their fields are written as tuples which bincode encodes the same way
+    type V1Mesh = (String, u32, Vec<f32>, Vec<f32>, Vec<u32>, Option<Vec<f32>>);
+    type V1Texture = (String, u32, u32, Vec<(u8, u8, u8, u8)>);
+    type V1Material = ((Option<String>, Option<String>, Option<String>), Option<(f32, f32, f32)>, Option<(f32, f32, f32)>, Option<(f32, f32, f32)>, Option<f32>);
+    type V2Pbr = (Option<(f32, f32, f32, f32)>, Option<f32>, Option<f32>, Option<(f32, f32, f32)>);
+    type V2Textures = (Option<String>, Option<String>, Option<String>, Option<String>, Option<String>, Option<String>, Option<String>);
+    type V2Material = (V2Textures, Option<(f32, f32, f32)>, Option<(f32, f32, f32)>, Option<(f32, f32, f32)>, Option<f32>, Option<V2Pbr>);
+    type V3Mesh = (String, u32, Vec<f32>, Vec<f32>, Vec<u32>, Option<Vec<f32>>, Option<Vec<f32>>, Option<Vec<f32>>);
+    type V6Mesh = (String, u32, Vec<f32>, Vec<f32>, Vec<u32>, Option<Vec<f32>>, Option<Vec<f32>>, Option<Vec<f32>>, Option<SkinStruct>);
+    type V7Mesh = (String, u32, Vec<f32>, Vec<f32>, Vec<u32>, Option<Vec<f32>>, Option<Vec<f32>>, Option<Vec<f32>>, Option<SkinStruct>, Vec<LodStruct>, Option<PackedStruct>);
+
+    // Writes the header of the version followed by the body and loads it back.
+    fn load(name: &str, version: u32, body: impl Encode) -> NmdlResult<FileStruct> {
+        let header = Header { magic: MAGIC.to_string(), version };
+        let bytes = bincode::encode_to_vec((header, body), bincode::config::standard()).unwrap();
+        let path = std::env::temp_dir().join(format!("glengine_mdl_{}_{}.nmdl", name, std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        let file = FileStruct::load(path.to_str().unwrap());
+        let _ = std::fs::remove_file(&path);
+        file
+    }
+
+    fn v1_mesh() -> V1Mesh {
+        ("triangle".to_string(), 0, TRIANGLE.to_vec(), NORMALS.to_vec(), vec![0, 1, 2], Some(vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0]))
+    }
+
+    fn v3_mesh() -> V3Mesh {
+        let (name, material_index, vertices, normals, indices, texture_coords) = v1_mesh();
+        (name, material_index, vertices, normals, indices, texture_coords, Some(vec![1.0; 9]), Some(vec![0.5; 9]))
+    }
+
+    fn v2_material() -> V2Material {
+        let textures = (None, None, None, Some("base.png".to_string()), Some("mr.png".to_string()), None, None);
+        let pbr = (Some((1.0, 0.5, 0.25, 1.0)), Some(0.2), Some(0.7), None);
+        (textures, None, Some((0.8, 0.8, 0.8)), None, None, Some(pbr))
+    }
+
+    fn material() -> MaterialStruct {
+        MaterialStruct {
+            texture: TexturesStruct {
+                diffuse: None,
+                specular: None,
+                normal: None,
+                base_color: None,
+                metallic_roughness: None,
+                ambient_occlusion: None,
+                emissive: None,
+            },
+            ambient: None,
+            diffuse: Some((1.0, 1.0, 1.0)),
+            specular: None,
+            shininess: None,
+            pbr: None,
+        }
+    }
+
+    fn triangle_file() -> FileStruct {
+        FileStruct {
+            magic: Header::new(),
+            meshes: vec![MeshStruct {
+                name: "triangle".to_string(),
+                material_index: 0,
+                vertices: TRIANGLE.to_vec(),
+                normals: NORMALS.to_vec(),
+                indices: vec![0, 1, 2],
+                texture_coords: None,
+                tangents: None,
+                bitangents: None,
+                skin: None,
+                lods: Vec::new(),
+                packed: None,
+                bounds: BoundsStruct::from_vertices(&TRIANGLE),
+            }],
+            materials: MaterialsStruct { materials: vec![material()], textures: HashMap::new() },
+            root: NodeStruct::flat(1),
+            animations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn migrates_v1() {
+        let material: V1Material = ((Some("diffuse.png".to_string()), None, None), Some((0.1, 0.1, 0.1)), Some((0.8, 0.8, 0.8)), None, Some(32.0));
+        let mut textures: HashMap<String, V1Texture> = HashMap::new();
+        textures.insert("*0".to_string(), ("*0".to_string(), 1, 1, vec![(1, 2, 3, 4)]));
+        // assimp left the size of textures read from disk at zero
+        textures.insert("*1".to_string(), ("*1".to_string(), 0, 0, vec![(5, 6, 7, 8)]));
+        let file = load("v1", 1, (vec![v1_mesh()], (vec![material], textures))).unwrap();
+
+        assert_eq!(file.magic.version, VERSION);
+        let mesh = &file.meshes[0];
+        assert_eq!(mesh.vertices, TRIANGLE);
+        assert_eq!(mesh.indices, [0, 1, 2]);
+        assert!(mesh.tangents.is_none() && mesh.bitangents.is_none());
+        assert!(mesh.skin.is_none() && mesh.lods.is_empty() && mesh.packed.is_none());
+        assert_eq!(mesh.bounds, BoundsStruct::from_vertices(&TRIANGLE));
+        let material = &file.materials.materials[0];
+        assert_eq!(material.texture.diffuse.as_deref(), Some("diffuse.png"));
+        assert!(material.texture.base_color.is_none());
+        assert_eq!(material.shininess, Some(32.0));
+        assert!(material.pbr.is_none());
+        assert!(matches!(&file.materials.textures["*0"].data, TextureData::Rgba(data) if data == &[1, 2, 3, 4]));
+        assert!(!file.materials.textures.contains_key("*1"));
+        assert_eq!(file.root.meshes, [0]);
+        assert!(file.animations.is_empty());
+    }
+
+    #[test]
+    fn migrates_v2() {
+        let textures: HashMap<String, V1Texture> = HashMap::new();
+        let file = load("v2", 2, (vec![v1_mesh()], (vec![v2_material()], textures))).unwrap();
+
+        assert_eq!(file.magic.version, VERSION);
+        let material = &file.materials.materials[0];
+        assert_eq!(material.texture.base_color.as_deref(), Some("base.png"));
+        assert_eq!(material.texture.metallic_roughness.as_deref(), Some("mr.png"));
+        let pbr = material.pbr.as_ref().unwrap();
+        assert_eq!(pbr.base_color, Some((1.0, 0.5, 0.25, 1.0)));
+        assert_eq!(pbr.metallic, Some(0.2));
+        assert_eq!(pbr.roughness, Some(0.7));
+        assert!(pbr.emissive.is_none());
+        assert!(file.meshes[0].tangents.is_none());
+    }
+
+    #[test]
+    fn migrates_v4() {
+        let textures: HashMap<String, TextureStruct> = HashMap::new();
+        let file = load("v4", 4, (vec![v3_mesh(), v3_mesh()], (vec![v2_material()], textures))).unwrap();
+
+        assert_eq!(file.magic.version, VERSION);
+        assert_eq!(file.meshes[1].tangents.as_deref(), Some(&[1.0; 9][..]));
+        // older files get a single root holding every mesh
+        assert!(file.root.name.is_empty());
+        assert_eq!(file.root.transform, IDENTITY);
+        assert_eq!(file.root.meshes, [0, 1]);
+        assert!(file.root.children.is_empty());
+    }
+
+    #[test]
+    fn migrates_v5() {
+        let mut transform = IDENTITY;
+        transform[12] = 3.0;
+        let root = NodeStruct {
+            name: "root".to_string(),
+            transform: IDENTITY,
+            meshes: Vec::new(),
+            children: vec![NodeStruct { name: "child".to_string(), transform, meshes: vec![0], children: Vec::new() }],
+        };
+        let textures: HashMap<String, TextureStruct> = HashMap::new();
+        let file = load("v5", 5, (vec![v3_mesh()], (vec![v2_material()], textures), root)).unwrap();
+
+        assert_eq!(file.magic.version, VERSION);
+        let mesh = &file.meshes[0];
+        assert_eq!(mesh.tangents.as_deref(), Some(&[1.0; 9][..]));
+        assert_eq!(mesh.bitangents.as_deref(), Some(&[0.5; 9][..]));
+        assert!(mesh.skin.is_none());
+        assert_eq!(file.root.name, "root");
+        assert!(file.root.meshes.is_empty());
+        assert_eq!(file.root.children[0].name, "child");
+        assert_eq!(file.root.children[0].transform, transform);
+        assert_eq!(file.root.children[0].meshes, [0]);
+        assert!(file.animations.is_empty());
+    }
+
+    #[test]
+    fn migrates_v6() {
+        let (name, material_index, vertices, normals, indices, texture_coords, tangents, bitangents) = v3_mesh();
+        let skin = SkinStruct {
+            bones: vec![BoneStruct { name: "bone".to_string(), offset: IDENTITY }],
+            joints: vec![0; 3 * MAX_BONE_INFLUENCES],
+            weights: [1.0, 0.0, 0.0, 0.0].repeat(3),
+        };
+        let mesh: V6Mesh = (name, material_index, vertices, normals, indices, texture_coords, tangents, bitangents, Some(skin));
+        let materials = MaterialsStruct { materials: vec![material()], textures: HashMap::new() };
+        let animation = AnimationStruct { name: "wave".to_string(), duration: 1.0, channels: Vec::new() };
+        let file = load("v6", 6, (vec![mesh], materials, NodeStruct::flat(1), vec![animation])).unwrap();
+
+        assert_eq!(file.magic.version, VERSION);
+        let mesh = &file.meshes[0];
+        assert!(mesh.lods.is_empty() && mesh.packed.is_none());
+        let skin = mesh.skin.as_ref().unwrap();
+        assert_eq!(skin.bones[0].name, "bone");
+        assert_eq!(skin.joints.len(), 3 * MAX_BONE_INFLUENCES);
+        assert_eq!(file.animations[0].name, "wave");
+        assert_eq!(file.animations[0].duration, 1.0);
+    }
+
+    #[test]
+    fn migrates_v7() {
+        let (name, material_index, vertices, normals, indices, texture_coords, tangents, bitangents) = v3_mesh();
+        let lod = LodStruct { indices: vec![0, 1, 2], error: 0.25 };
+        let mesh: V7Mesh = (name, material_index, vertices, normals, indices, texture_coords, tangents, bitangents, None, vec![lod], None);
+        let materials = MaterialsStruct { materials: vec![material()], textures: HashMap::new() };
+        let file = load("v7", 7, (vec![mesh], materials, NodeStruct::flat(1), Vec::<AnimationStruct>::new())).unwrap();
+
+        assert_eq!(file.magic.version, VERSION);
+        let mesh = &file.meshes[0];
+        assert_eq!(mesh.lods.len(), 1);
+        assert_eq!(mesh.lods[0].indices, [0, 1, 2]);
+        assert_eq!(mesh.lods[0].error, 0.25);
+        // the bounds are computed from the vertices
+        let bounds = mesh.bounds.unwrap();
+        assert_eq!(bounds.min, [0.0, 0.0, 0.0]);
+        assert_eq!(bounds.max, [1.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let textures: HashMap<String, V1Texture> = HashMap::new();
+        let file = load("newer", VERSION + 1, (vec![v1_mesh()], (Vec::<V1Material>::new(), textures)));
+        assert!(matches!(file, Err(NmdlError::UnsupportedVersion { found, .. }) if found == VERSION + 1));
+    }
+
+    #[test]
+    fn validates_a_consistent_file() {
+        assert!(triangle_file().validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_a_missing_material() {
+        let mut file = triangle_file();
+        file.meshes[0].material_index = 1;
+        assert!(matches!(file.validate(), Err(NmdlError::Invalid(_))));
+    }
+
+    #[test]
+    fn rejects_indices_past_the_vertices() {
+        let mut file = triangle_file();
+        file.meshes[0].indices = vec![0, 1, 3];
+        assert!(matches!(file.validate(), Err(NmdlError::Invalid(_))));
+
+        let mut file = triangle_file();
+        file.meshes[0].lods = vec![LodStruct { indices: vec![0, 2, 5], error: 0.0 }];
+        assert!(matches!(file.validate(), Err(NmdlError::Invalid(_))));
+    }
+
+    #[test]
+    fn loading_rejects_invalid_files() {
+        let mut mesh = v1_mesh();
+        mesh.1 = 2;
+        let material: V1Material = ((None, None, None), None, None, None, None);
+        let textures: HashMap<String, V1Texture> = HashMap::new();
+        let file = load("invalid", 1, (vec![mesh], (vec![material], textures)));
+        assert!(matches!(file, Err(NmdlError::Invalid(_))));
+    }
+}
@@ -1,18 +1,33 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::error::Error;
 use std::fs::File;
-use std::path::Path;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use bincode::{error, Decode, Encode};
-use image::GenericImageView;
+use bincode::{Decode, Encode};
+use image::{GenericImageView, ImageFormat, RgbaImage};
 use russimp::material::{DataContent, Material, PropertyTypeInfo, Texture, TextureType};
+use russimp::animation::Animation;
 use russimp::mesh::Mesh;
+use russimp::node::Node;
 use russimp::scene::{PostProcess, Scene};
+use russimp::Matrix4x4;
+
+use crate::error::{NmdlError, NmdlResult};
+use crate::migrate;
+use crate::optimize;
 
 pub const EXTENSION: &str = "nmdl";
 pub const MAGIC: &str = "NMDL";
-pub const VERSION: u32 = 1;
+pub const VERSION: u32 = 8;
+// source formats the importer is used with
+pub const IMPORT_EXTENSIONS: [&str; 5] = ["obj", "gltf", "glb", "fbx", "dae"];
+
+pub fn is_importable(path:&Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMPORT_EXTENSIONS.iter().any(|importable| ext.eq_ignore_ascii_case(importable)))
+}
 
 #[derive(Debug, Encode, Decode,Clone)]
 pub struct Header {
@@ -21,14 +36,30 @@ pub struct Header {
 }
 
 impl Header {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Header {
             magic: MAGIC.to_string(),
             version: VERSION,
         }
     }
-    fn validate(&self) -> bool {
-        self.magic == MAGIC
+    // Reads only the header at the start of a file.
+    pub fn read(path:&str) -> NmdlResult<Self> {
+        let mut reader = std::io::BufReader::new(File::open(path)?);
+        Ok(bincode::decode_from_std_read(&mut reader, bincode::config::standard())?)
+    }
+
+    // Older versions are accepted, they are migrated when the file is loaded.
+    pub fn validate(&self) -> NmdlResult<()> {
+        if self.magic != MAGIC {
+            return Err(NmdlError::InvalidMagic(self.magic.clone()));
+        }
+        if self.version == 0 || self.version > VERSION {
+            return Err(NmdlError::UnsupportedVersion {
+                found: self.version,
+                supported: VERSION,
+            });
+        }
+        Ok(())
     }
 }
 
@@ -40,6 +71,169 @@ pub struct MeshStruct {
     pub normals: Vec<f32>,
     pub indices: Vec<u32>,
     pub texture_coords: Option<Vec<f32>>,
+    // tangent space from CalculateTangentSpace, only present when the mesh has texture coordinates
+    pub tangents: Option<Vec<f32>>,
+    pub bitangents: Option<Vec<f32>>,
+    pub skin: Option<SkinStruct>,
+    // coarser index buffers over the same vertices, from the most to the least detailed
+    pub lods: Vec<LodStruct>,
+    // compact attributes from quantization, they replace the float normals, tangents and
+    // texture coordinates of the mesh
+    pub packed: Option<PackedStruct>,
+    // computed from the vertices at import, None for a mesh without vertices
+    pub bounds: Option<BoundsStruct>,
+}
+
+// Axis aligned box around the mesh and the sphere around its vertices, centered on the box.
+#[derive(Debug, Encode, Decode,Clone,Copy,PartialEq)]
+pub struct BoundsStruct {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+    pub center: [f32; 3],
+    pub radius: f32,
+}
+
+impl BoundsStruct {
+    pub fn from_vertices(vertices: &[f32]) -> Option<Self> {
+        let mut positions = vertices.chunks_exact(3);
+        let first = positions.next()?;
+        let init = ([first[0], first[1], first[2]], [first[0], first[1], first[2]]);
+        let (min, max) = positions.fold(init, |(mut min, mut max), vertex| {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(vertex[axis]);
+                max[axis] = max[axis].max(vertex[axis]);
+            }
+            (min, max)
+        });
+        let center = [0, 1, 2].map(|axis| (min[axis] + max[axis]) * 0.5);
+        let radius = vertices
+            .chunks_exact(3)
+            .map(|vertex| (0..3).map(|axis| (vertex[axis] - center[axis]).powi(2)).sum::<f32>())
+            .fold(0.0f32, f32::max)
+            .sqrt();
+        Some(BoundsStruct { min, max, center, radius })
+    }
+}
+
+#[derive(Debug, Encode, Decode,Clone)]
+pub struct LodStruct {
+    pub indices: Vec<u32>,
+    // largest distance to the full mesh, relative to the size of the mesh
+    pub error: f32,
+}
+
+// Normals and tangents are four signed normalized bytes per vertex, the last one unused,
+// texture coordinates are half floats.
+#[derive(Debug, Encode, Decode,Clone)]
+pub struct PackedStruct {
+    pub normals: Vec<i8>,
+    pub tangents: Option<Vec<i8>>,
+    pub bitangents: Option<Vec<i8>>,
+    pub texture_coords: Option<Vec<u16>>,
+}
+
+#[derive(Debug, Encode, Decode,Clone)]
+pub struct BoneStruct {
+    // name of the node moving the bone
+    pub name: String,
+    // from the mesh space to the bone space in the bind pose, column major
+    pub offset: [f32; 16],
+}
+
+// Four bone influences per vertex, unused ones have a weight of zero.
+#[derive(Debug, Encode, Decode,Clone)]
+pub struct SkinStruct {
+    pub bones: Vec<BoneStruct>,
+    pub joints: Vec<u32>,
+    pub weights: Vec<f32>,
+}
+
+pub const MAX_BONE_INFLUENCES: usize = 4;
+
+impl SkinStruct{
+    pub fn from_assimp_mesh(mesh:&Mesh) -> Option<Self>{
+        if mesh.bones.is_empty() {
+            return None;
+        }
+        let vertex_count = mesh.vertices.len();
+        let mut joints = vec![0u32; vertex_count * MAX_BONE_INFLUENCES];
+        let mut weights = vec![0f32; vertex_count * MAX_BONE_INFLUENCES];
+        for (bone_index, bone) in mesh.bones.iter().enumerate() {
+            for weight in &bone.weights {
+                let start = weight.vertex_id as usize * MAX_BONE_INFLUENCES;
+                let slots = &mut weights[start..start + MAX_BONE_INFLUENCES];
+                // keep the strongest influences when a vertex has more than four
+                let (slot, smallest) = slots
+                    .iter()
+                    .enumerate()
+                    .fold((0, f32::MAX), |min, (i, w)| if *w < min.1 { (i, *w) } else { min });
+                if weight.weight > smallest {
+                    slots[slot] = weight.weight;
+                    joints[start + slot] = bone_index as u32;
+                }
+            }
+        }
+        for vertex in weights.chunks_mut(MAX_BONE_INFLUENCES) {
+            let total: f32 = vertex.iter().sum();
+            if total > 0.0 {
+                vertex.iter_mut().for_each(|w| *w /= total);
+            }
+        }
+        Some(SkinStruct{
+            bones: mesh.bones.iter().map(|bone| BoneStruct{
+                name: bone.name.clone(),
+                offset: matrix_cols(&bone.offset_matrix),
+            }).collect(),
+            joints,
+            weights,
+        })
+    }
+}
+
+// Keyframes of one node, times are in seconds and rotations are x, y, z, w quaternions.
+#[derive(Debug, Encode, Decode,Clone)]
+pub struct ChannelStruct {
+    pub node: String,
+    pub positions: Vec<(f32, [f32; 3])>,
+    pub rotations: Vec<(f32, [f32; 4])>,
+    pub scales: Vec<(f32, [f32; 3])>,
+}
+
+#[derive(Debug, Encode, Decode,Clone)]
+pub struct AnimationStruct {
+    pub name: String,
+    pub duration: f32,
+    pub channels: Vec<ChannelStruct>,
+}
+
+// assimp leaves the tick rate at zero when the source format has none
+const DEFAULT_TICKS_PER_SECOND: f64 = 25.0;
+
+impl AnimationStruct{
+    pub fn from_assimp_animation(animation:&Animation) -> Self{
+        let ticks = if animation.ticks_per_second > 0.0 { animation.ticks_per_second } else { DEFAULT_TICKS_PER_SECOND };
+        let seconds = |time: f64| (time / ticks) as f32;
+        AnimationStruct{
+            name: animation.name.clone(),
+            duration: seconds(animation.duration),
+            channels: animation.channels.iter().map(|channel| ChannelStruct{
+                node: channel.name.clone(),
+                positions: channel.position_keys.iter().map(|key| (seconds(key.time), [key.value.x, key.value.y, key.value.z])).collect(),
+                rotations: channel.rotation_keys.iter().map(|key| (seconds(key.time), [key.value.x, key.value.y, key.value.z, key.value.w])).collect(),
+                scales: channel.scaling_keys.iter().map(|key| (seconds(key.time), [key.value.x, key.value.y, key.value.z])).collect(),
+            }).collect(),
+        }
+    }
+}
+
+// assimp matrices are row major
+fn matrix_cols(m:&Matrix4x4) -> [f32; 16]{
+    [
+        m.a1, m.b1, m.c1, m.d1,
+        m.a2, m.b2, m.c2, m.d2,
+        m.a3, m.b3, m.c3, m.d3,
+        m.a4, m.b4, m.c4, m.d4,
+    ]
 }
 
 impl MeshStruct{
@@ -55,13 +249,27 @@ impl MeshStruct{
                 None
             }
         };
+        let (tangents, bitangents) = if mesh.tangents.is_empty() || mesh.bitangents.is_empty() {
+            (None, None)
+        } else {
+            (
+                Some(mesh.tangents.iter().flat_map(|v| vec!(v.x,v.y,v.z)).collect()),
+                Some(mesh.bitangents.iter().flat_map(|v| vec!(v.x,v.y,v.z)).collect()),
+            )
+        };
         MeshStruct{
             name: mesh.name.clone(),
             material_index: mesh.material_index,
             vertices: mesh.vertices.iter().flat_map(|v| vec!(v.x,v.y,v.z)).collect(),
             normals: mesh.normals.iter().flat_map(|v| vec!(v.x,v.y,v.z)).collect(),
             indices: mesh.faces.iter().flat_map(|f| f.0.clone()).collect(),
-            texture_coords
+            texture_coords,
+            tangents,
+            bitangents,
+            skin: SkinStruct::from_assimp_mesh(mesh),
+            lods: Vec::new(),
+            packed: None,
+            bounds: None,
         }
     }
 }
@@ -75,6 +283,16 @@ pub struct MaterialStruct {
     pub diffuse: Option<(f32, f32, f32)>,
     pub specular: Option<(f32, f32, f32)>,
     pub shininess: Option<f32>,
+    pub pbr: Option<PbrStruct>,
+}
+
+// Metallic/roughness parameters, set when the source material is physically based.
+#[derive(Debug, Encode, Decode,Clone,Default)]
+pub struct PbrStruct {
+    pub base_color: Option<(f32, f32, f32, f32)>,
+    pub metallic: Option<f32>,
+    pub roughness: Option<f32>,
+    pub emissive: Option<(f32, f32, f32)>,
 }
 
 #[derive(Debug, Encode, Decode,Clone)]
@@ -91,12 +309,18 @@ impl MaterialStruct{
                 diffuse: None,
                 specular: None,
                 normal: None,
+                base_color: None,
+                metallic_roughness: None,
+                ambient_occlusion: None,
+                emissive: None,
             },
             ambient: None,
             diffuse: None,
             specular: None,
             shininess: None,
+            pbr: None,
         };
+        let mut pbr = PbrStruct::default();
         material
             .properties
             .iter()
@@ -118,6 +342,19 @@ impl MaterialStruct{
                     material_struct.shininess = Some((val / 1000.0)*128.0);
                     // println!("Shininess: {:?}", data.shininess);
                 }
+                // older assimp versions prefix the glTF factors
+                ("$clr.base" | "$mat.gltf.pbrMetallicRoughness.baseColorFactor", &PropertyTypeInfo::FloatArray(ref color)) => {
+                    pbr.base_color = Some((color[0], color[1], color[2], *color.get(3).unwrap_or(&1.0)));
+                }
+                ("$mat.metallicFactor" | "$mat.gltf.pbrMetallicRoughness.metallicFactor", &PropertyTypeInfo::FloatArray(ref val)) => {
+                    pbr.metallic = Some(val[0]);
+                }
+                ("$mat.roughnessFactor" | "$mat.gltf.pbrMetallicRoughness.roughnessFactor", &PropertyTypeInfo::FloatArray(ref val)) => {
+                    pbr.roughness = Some(val[0]);
+                }
+                ("$clr.emissive", &PropertyTypeInfo::FloatArray(ref color)) => {
+                    pbr.emissive = Some((color[0], color[1], color[2]));
+                }
                 // (a,b) => {
                 //     println!("Unknown property: {:?} {:?}", a,b);
                 // }
@@ -135,16 +372,57 @@ impl MaterialStruct{
             material_struct.texture.normal = Some(normal_texture.borrow().filename.clone());
             texture_filenames.push(normal_texture.clone());
         }
+        let find_texture = |types: &[TextureType]| types.iter().find_map(|texture_type| material.textures.get(texture_type)).cloned();
+        if let Some(base_color_texture) = find_texture(&[TextureType::BaseColor]) {
+            material_struct.texture.base_color = Some(base_color_texture.borrow().filename.clone());
+            texture_filenames.push(base_color_texture);
+        }
+        // glTF packs roughness in green and metallic in blue, assimp lists the same file under both types
+        if let Some(metallic_roughness_texture) = find_texture(&[TextureType::Metalness, TextureType::Roughness, TextureType::Unknown]) {
+            material_struct.texture.metallic_roughness = Some(metallic_roughness_texture.borrow().filename.clone());
+            texture_filenames.push(metallic_roughness_texture);
+        }
+        if let Some(ambient_occlusion_texture) = find_texture(&[TextureType::AmbientOcclusion, TextureType::LightMap]) {
+            material_struct.texture.ambient_occlusion = Some(ambient_occlusion_texture.borrow().filename.clone());
+            texture_filenames.push(ambient_occlusion_texture);
+        }
+        if let Some(emissive_texture) = find_texture(&[TextureType::EmissionColor, TextureType::Emissive]) {
+            material_struct.texture.emissive = Some(emissive_texture.borrow().filename.clone());
+            texture_filenames.push(emissive_texture);
+        }
+
+        let is_pbr = pbr.base_color.is_some()
+            || pbr.metallic.is_some()
+            || pbr.roughness.is_some()
+            || material_struct.texture.base_color.is_some()
+            || material_struct.texture.metallic_roughness.is_some();
+        if is_pbr {
+            material_struct.pbr = Some(pbr);
+        }
         (material_struct,texture_filenames)
     }
 }
 
+// How the pixels of an embedded texture are stored in the file.
+#[derive(Debug, Encode, Decode, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextureCompression {
+    #[default]
+    None,
+    Png,
+}
+
+#[derive(Debug, Encode, Decode,Clone)]
+pub enum TextureData {
+    Rgba(Vec<u8>),
+    Png(Vec<u8>),
+}
+
 #[derive(Debug, Encode, Decode,Clone)]
 pub struct TextureStruct {
     pub name: String,
     pub height: u32,
     pub width: u32,
-    pub data: Vec<(u8, u8, u8, u8)>,
+    pub data: TextureData,
 }
 
 #[derive(Debug, Encode, Decode,Clone)]
@@ -152,68 +430,222 @@ pub struct TexturesStruct {
     pub diffuse: Option<String>,
     pub specular: Option<String>,
     pub normal: Option<String>,
+    pub base_color: Option<String>,
+    pub metallic_roughness: Option<String>,
+    pub ambient_occlusion: Option<String>,
+    pub emissive: Option<String>,
+}
+
+impl TexturesStruct{
+    // names of the textures the material uses
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        [
+            &self.diffuse,
+            &self.specular,
+            &self.normal,
+            &self.base_color,
+            &self.metallic_roughness,
+            &self.ambient_occlusion,
+            &self.emissive,
+        ]
+        .into_iter()
+        .flatten()
+    }
 }
 
 impl TextureStruct{
-    pub fn from_assimp_texture(texture:&Texture) -> Self{
-        let width = texture.width;
-        let height = texture.height;
-        // let texture_data = match texture.data{
-        //     DataContent::Texel(data) => {
-        //         let data:Vec<(u8,u8,u8,u8)> = data.iter().map(|c| (c.r,c.g,c.b,c.a)).collect();
-        //         TextureStruct{
-        //             name: texture.filename.clone(),
-        //             height,
-        //             width,
-        //             data,
-        //         }
-        //     }
-        //     DataContent::Bytes(data) => {
-        //         let data:Vec<(u8,u8,u8,u8)> = data.chunks(4).map(|c| (c[0],c[1],c[2],c[3])).collect();
-        //         TextureStruct{
-        //             name: texture.filename.clone(),
-        //             height,
-        //             width,
-        //             data,
-        //         }
-        //     }
-        // };
-        let img = image::open(&texture.filename).expect("Failed to load texture");
-        let data = img.into_rgba8().into_raw();
-        let data = data.chunks(4).map(|c| (c[0],c[1],c[2],c[3])).collect();
-        TextureStruct{
+    // Decodes a texture referenced by a material, either embedded in the scene or on disk
+    // next to the model.
+    pub fn from_assimp_texture(texture:&Texture,model_dir:&Path,compression:TextureCompression) -> NmdlResult<Self>{
+        let img = match &texture.data {
+            DataContent::Texel(texels) if !texels.is_empty() && texture.width > 0 && texture.height > 0 => {
+                let data = texels.iter().flat_map(|c| [c.r,c.g,c.b,c.a]).collect();
+                let rgba = RgbaImage::from_raw(texture.width, texture.height, data).ok_or_else(|| {
+                    NmdlError::Invalid(format!("texel count of {} does not match its size", texture.filename))
+                })?;
+                image::DynamicImage::ImageRgba8(rgba)
+            }
+            // a height of 0 marks a compressed image kept in its file format
+            DataContent::Bytes(bytes) if !bytes.is_empty() => image::load_from_memory(bytes)?,
+            _ => image::open(resolve_texture_path(model_dir, &texture.filename))?,
+        };
+        let (width, height) = img.dimensions();
+        let rgba = img.into_rgba8();
+        let data = match compression {
+            TextureCompression::None => TextureData::Rgba(rgba.into_raw()),
+            TextureCompression::Png => {
+                let mut png = Cursor::new(Vec::new());
+                rgba.write_to(&mut png, ImageFormat::Png)?;
+                TextureData::Png(png.into_inner())
+            }
+        };
+        Ok(TextureStruct{
             name: texture.filename.clone(),
             height,
             width,
             data,
+        })
+    }
+
+    // RGBA8 pixels, rows from the top
+    pub fn rgba(&self) -> NmdlResult<Vec<u8>>{
+        match &self.data {
+            TextureData::Rgba(data) => Ok(data.clone()),
+            TextureData::Png(png) => Ok(image::load_from_memory_with_format(png, ImageFormat::Png)?.into_rgba8().into_raw()),
+        }
+    }
+
+    pub fn image(&self) -> NmdlResult<RgbaImage>{
+        RgbaImage::from_raw(self.width, self.height, self.rgba()?)
+            .ok_or_else(|| NmdlError::Invalid(format!("texture {:?} does not match its size", self.name)))
+    }
+}
+
+// Material texture paths are usually relative to the model file, glTF stores them as URIs
+// which may be percent encoded.
+pub fn resolve_texture_path(model_dir:&Path,filename:&str) -> PathBuf{
+    let path = Path::new(filename);
+    let resolved = if path.is_relative() && !path.exists() {
+        model_dir.join(path)
+    } else {
+        path.to_path_buf()
+    };
+    if !resolved.exists() && filename.contains('%') {
+        let decoded = percent_decode(filename);
+        if decoded != filename {
+            return resolve_texture_path(model_dir, &decoded);
+        }
+    }
+    resolved
+}
+
+fn percent_decode(uri:&str) -> String{
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
         }
     }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImportOptions {
+    pub texture_compression: TextureCompression,
+    // for sources with texture coordinates starting at the top
+    pub flip_uvs: bool,
+    // generated normals are averaged between faces instead of flat
+    pub smooth_normals: bool,
+    // reorders triangles and vertices for the vertex cache, overdraw and vertex fetch
+    pub optimize: bool,
+    // stores normals, tangents and texture coordinates in compact formats
+    pub quantize: bool,
+    // simplified levels generated per mesh, fewer are stored when a mesh cannot be simplified further
+    pub lod_levels: u32,
+}
+
+impl ImportOptions {
+    fn post_process(&self) -> Vec<PostProcess> {
+        let mut post_process = vec![
+            PostProcess::CalculateTangentSpace,
+            // PostProcess::PreTransformVertices,
+            PostProcess::OptimizeMeshes,
+            PostProcess::Triangulate,
+            PostProcess::JoinIdenticalVertices,
+            PostProcess::SortByPrimitiveType,
+            PostProcess::LimitBoneWeights,
+        ];
+        post_process.push(if self.smooth_normals { PostProcess::GenerateSmoothNormals } else { PostProcess::GenerateNormals });
+        if self.flip_uvs {
+            post_process.push(PostProcess::FlipUVs);
+        }
+        post_process
+    }
+}
+
+// Node of the source scene graph, the transform is relative to the parent and column major.
+#[derive(Debug, Encode, Decode,Clone)]
+pub struct NodeStruct {
+    pub name: String,
+    pub transform: [f32; 16],
+    pub meshes: Vec<u32>,
+    pub children: Vec<NodeStruct>,
+}
+
+impl NodeStruct{
+    pub fn from_assimp_node(node:&Node) -> Self{
+        NodeStruct{
+            name: node.name.clone(),
+            transform: matrix_cols(&node.transformation),
+            meshes: node.meshes.clone(),
+            children: node.children.borrow().iter().map(|child| NodeStruct::from_assimp_node(child)).collect(),
+        }
+    }
+
+    // a root holding every mesh, for scenes without a node graph
+    pub fn flat(mesh_count: usize) -> Self{
+        NodeStruct{
+            name: String::new(),
+            transform: IDENTITY,
+            meshes: (0..mesh_count as u32).collect(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn mesh_indices(&self) -> Box<dyn Iterator<Item = u32> + '_>{
+        Box::new(self.meshes.iter().copied().chain(self.children.iter().flat_map(|child| child.mesh_indices())))
+    }
 }
 
+pub const IDENTITY: [f32; 16] = [
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 1.0, 0.0,
+    0.0, 0.0, 0.0, 1.0,
+];
+
 #[derive(Debug, Encode, Decode,Clone)]
 pub struct FileStruct {
     pub magic: Header,
     pub meshes: Vec<MeshStruct>,
     pub materials: MaterialsStruct,
+    pub root: NodeStruct,
+    pub animations: Vec<AnimationStruct>,
 }
 
 impl FileStruct{
-    pub fn import(path:&str)->FileStruct{
-        let scene = Scene::from_file(
-            path,
-            vec![
-                PostProcess::CalculateTangentSpace,
-                // PostProcess::PreTransformVertices,
-                PostProcess::OptimizeMeshes,
-                PostProcess::OptimizeGraph,
-                PostProcess::Triangulate,
-                PostProcess::JoinIdenticalVertices,
-                PostProcess::GenerateNormals,
-                PostProcess::SortByPrimitiveType,
-            ],
-        )
-            .expect("Failed to load obj file");
-        let meshes = scene.meshes.iter().map(|mesh| MeshStruct::from_assimp_mesh(mesh)).collect();
+    pub fn import(path:&str)->NmdlResult<FileStruct>{
+        Self::import_with_options(path, &ImportOptions::default())
+    }
+
+    pub fn import_with_options(path:&str,options:&ImportOptions)->NmdlResult<FileStruct>{
+        let model_dir = Path::new(path).parent().unwrap_or(Path::new("")).to_path_buf();
+        let scene = Scene::from_file(path, options.post_process())?;
+        let mut meshes: Vec<MeshStruct> = scene.meshes.iter().map(|mesh| MeshStruct::from_assimp_mesh(mesh)).collect();
+        for mesh in &mut meshes {
+            mesh.lods = optimize::generate_lods(mesh, options.lod_levels);
+            if options.optimize {
+                optimize::optimize_mesh(mesh);
+            }
+            if options.quantize {
+                optimize::quantize(mesh);
+            }
+            mesh.bounds = BoundsStruct::from_vertices(&mesh.vertices);
+        }
+        let root = match &scene.root {
+            Some(root) => NodeStruct::from_assimp_node(root),
+            None => NodeStruct::flat(meshes.len()),
+        };
         let mut materials: MaterialsStruct = MaterialsStruct{
             materials: Vec::new(),
             textures: HashMap::new(),
@@ -222,17 +654,31 @@ impl FileStruct{
             let (material_struct, texture) = MaterialStruct::from_assimp_material(material);
             materials.materials.push(material_struct);
             for texture in texture{
-                let tex_struct = TextureStruct::from_assimp_texture(&*texture.borrow());
-                materials.textures.insert(texture.borrow().filename.clone(), tex_struct);
+                let texture = texture.borrow();
+                if materials.textures.contains_key(&texture.filename) {
+                    continue;
+                }
+                // textures that cannot be read now are left to the loader, which looks for them on disk
+                match TextureStruct::from_assimp_texture(&texture, &model_dir, options.texture_compression) {
+                    Ok(tex_struct) => {
+                        materials.textures.insert(texture.filename.clone(), tex_struct);
+                    }
+                    Err(e) => println!("Could not embed texture {}: {}", texture.filename, e),
+                }
             }
         });
-        FileStruct{
+        let file = FileStruct{
             magic: Header::new(),
             meshes,
             materials,
-        }
+            root,
+            animations: scene.animations.iter().map(AnimationStruct::from_assimp_animation).collect(),
+        };
+        file.validate()?;
+        Ok(file)
     }
-    pub fn save(&self,path:&str) ->Result<(),Box<dyn Error>>{
+
+    pub fn save(&self,path:&str) ->NmdlResult<()>{
         let path = Path::new(path).with_extension(EXTENSION);
         let file = File::create(path)?;
         let mut buf_writer = std::io::BufWriter::new(file);
@@ -240,10 +686,80 @@ impl FileStruct{
         Ok(())
     }
 
-    pub fn load(path:&str) ->Result<Self,Box<dyn Error>>{
-        let file = File::open(path)?;
-        let mut buf_reader = std::io::BufReader::new(file);
-        let data = bincode::decode_from_std_read(&mut buf_reader, bincode::config::standard())?;
-        Ok(data)
+    // Checks the header before decoding the rest, files of older versions are migrated.
+    pub fn load(path:&str) ->NmdlResult<Self>{
+        let bytes = std::fs::read(path)?;
+        let (header, _): (Header, usize) = bincode::decode_from_slice(&bytes, bincode::config::standard())?;
+        header.validate()?;
+        let file = migrate::decode(header.version, &bytes)?;
+        file.validate()?;
+        Ok(file)
     }
-}
\ No newline at end of file
+
+    // Checks that the meshes are consistent with each other and their materials.
+    pub fn validate(&self) -> NmdlResult<()>{
+        self.magic.validate()?;
+        for mesh in &self.meshes {
+            let invalid = |reason: &str| Err(NmdlError::Invalid(format!("mesh {:?} {}", mesh.name, reason)));
+            if mesh.vertices.len() % 3 != 0 {
+                return invalid("has a partial vertex");
+            }
+            let vertex_count = mesh.vertices.len() / 3;
+            if !mesh.normals.is_empty() && mesh.normals.len() != mesh.vertices.len() {
+                return invalid("has a normal count different from its vertex count");
+            }
+            if mesh.indices.len() % 3 != 0 {
+                return invalid("has a partial triangle");
+            }
+            if mesh.indices.iter().any(|index| *index as usize >= vertex_count) {
+                return invalid("indexes past its vertices");
+            }
+            if mesh.texture_coords.as_ref().is_some_and(|coords| coords.len() != vertex_count * 2) {
+                return invalid("has a texture coordinate count different from its vertex count");
+            }
+            let tangent_counts = [&mesh.tangents, &mesh.bitangents];
+            if tangent_counts.iter().any(|tangents| tangents.as_ref().is_some_and(|tangents| tangents.len() != mesh.vertices.len())) {
+                return invalid("has a tangent count different from its vertex count");
+            }
+            if mesh.lods.iter().any(|lod| lod.indices.len() % 3 != 0 || lod.indices.iter().any(|index| *index as usize >= vertex_count)) {
+                return invalid("has a level of detail with invalid indices");
+            }
+            if let Some(packed) = &mesh.packed {
+                let packed_tangents = [&packed.tangents, &packed.bitangents];
+                if (!packed.normals.is_empty() && packed.normals.len() != vertex_count * 4)
+                    || packed_tangents.iter().any(|tangents| tangents.as_ref().is_some_and(|tangents| tangents.len() != vertex_count * 4)) {
+                    return invalid("has a packed normal or tangent count different from its vertex count");
+                }
+                if packed.texture_coords.as_ref().is_some_and(|coords| coords.len() != vertex_count * 2) {
+                    return invalid("has a packed texture coordinate count different from its vertex count");
+                }
+            }
+            if mesh.bounds.is_some() != (vertex_count > 0) {
+                return invalid("has bounds that do not match its vertices");
+            }
+            if mesh.material_index as usize >= self.materials.materials.len() {
+                return invalid("uses a material that does not exist");
+            }
+            if let Some(skin) = &mesh.skin {
+                let influences = vertex_count * MAX_BONE_INFLUENCES;
+                if skin.joints.len() != influences || skin.weights.len() != influences {
+                    return invalid("has a bone influence count different from its vertex count");
+                }
+                if skin.joints.iter().any(|joint| *joint as usize >= skin.bones.len()) {
+                    return invalid("is skinned to a bone that does not exist");
+                }
+            }
+        }
+        if self.root.mesh_indices().any(|index| index as usize >= self.meshes.len()) {
+            return Err(NmdlError::Invalid("a node uses a mesh that does not exist".to_string()));
+        }
+        for (name, texture) in &self.materials.textures {
+            if let TextureData::Rgba(data) = &texture.data {
+                if data.len() != texture.width as usize * texture.height as usize * 4 {
+                    return Err(NmdlError::Invalid(format!("texture {:?} does not match its size", name)));
+                }
+            }
+        }
+        Ok(())
+    }
+}
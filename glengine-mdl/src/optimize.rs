@@ -0,0 +1,615 @@
+// Optional import steps: reordering triangles and vertices for the GPU caches, simplified
+// levels of detail sharing the vertices of the mesh, and compact vertex attributes.
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::models::{LodStruct, MeshStruct, PackedStruct, MAX_BONE_INFLUENCES};
+
+// vertices kept by the post transform cache model of the optimizer
+const CACHE_SIZE: usize = 32;
+// cache used to find where the optimized order restarts with a cold cache
+const OVERDRAW_CACHE_SIZE: usize = 16;
+// largest simplification error of a level, relative to the size of the mesh
+pub const LOD_MAX_ERROR: f32 = 0.05;
+
+type Vec3 = [f32; 3];
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn add(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn scale(a: Vec3, factor: f32) -> Vec3 {
+    [a[0] * factor, a[1] * factor, a[2] * factor]
+}
+
+fn dot(a: Vec3, b: Vec3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn position(positions: &[f32], vertex: u32) -> Vec3 {
+    let start = vertex as usize * 3;
+    [positions[start], positions[start + 1], positions[start + 2]]
+}
+
+// unnormalized, its length is twice the area of the triangle
+fn face_normal(triangle: [Vec3; 3]) -> Vec3 {
+    cross(sub(triangle[1], triangle[0]), sub(triangle[2], triangle[0]))
+}
+
+// Score of a vertex in Forsyth's algorithm, favouring recently used vertices and vertices
+// with few triangles left so they leave the cache for good.
+fn vertex_score(cache_position: Option<usize>, remaining: usize) -> f32 {
+    if remaining == 0 {
+        return -1.0;
+    }
+    let cache_score = match cache_position {
+        // the last triangle's vertices, a fixed score avoids reusing the same edge forever
+        Some(position) if position < 3 => 0.75,
+        Some(position) => (1.0 - (position - 3) as f32 / (CACHE_SIZE - 3) as f32).powf(1.5),
+        None => 0.0,
+    };
+    cache_score + 2.0 * (remaining as f32).powf(-0.5)
+}
+
+// Reorders the triangles to reuse the transformed vertices, Forsyth's linear speed optimization.
+pub fn optimize_vertex_cache(indices: &[u32], vertex_count: usize) -> Vec<u32> {
+    let triangle_count = indices.len() / 3;
+    let mut vertex_triangles: Vec<Vec<usize>> = vec![Vec::new(); vertex_count];
+    for (triangle, vertices) in indices.chunks_exact(3).enumerate() {
+        for vertex in vertices {
+            vertex_triangles[*vertex as usize].push(triangle);
+        }
+    }
+    let mut remaining: Vec<usize> = vertex_triangles.iter().map(Vec::len).collect();
+    let mut cache_position: Vec<Option<usize>> = vec![None; vertex_count];
+    let mut vertex_scores: Vec<f32> = remaining.iter().map(|remaining| vertex_score(None, *remaining)).collect();
+    let mut emitted = vec![false; triangle_count];
+    let mut cache: Vec<u32> = Vec::with_capacity(CACHE_SIZE + 3);
+    let mut optimized = Vec::with_capacity(triangle_count * 3);
+    let mut next_unemitted = 0;
+    let mut best: Option<usize> = None;
+    for _ in 0..triangle_count {
+        let triangle = match best {
+            Some(triangle) => triangle,
+            None => {
+                while emitted[next_unemitted] {
+                    next_unemitted += 1;
+                }
+                next_unemitted
+            }
+        };
+        emitted[triangle] = true;
+        let vertices = &indices[triangle * 3..triangle * 3 + 3];
+        optimized.extend_from_slice(vertices);
+        for vertex in vertices {
+            let vertex = *vertex as usize;
+            remaining[vertex] -= 1;
+            vertex_triangles[vertex].retain(|other| *other != triangle);
+        }
+
+        // the vertices of the triangle move to the front, the ones pushed out also get a new score
+        let mut touched: Vec<u32> = vertices.to_vec();
+        touched.extend(cache.iter().filter(|vertex| !vertices.contains(vertex)));
+        for (position, vertex) in touched.iter().enumerate() {
+            let vertex = *vertex as usize;
+            cache_position[vertex] = (position < CACHE_SIZE).then_some(position);
+            vertex_scores[vertex] = vertex_score(cache_position[vertex], remaining[vertex]);
+        }
+        cache = touched.iter().take(CACHE_SIZE).copied().collect();
+
+        best = None;
+        let mut best_score = f32::MIN;
+        for vertex in &touched {
+            for candidate in &vertex_triangles[*vertex as usize] {
+                let score: f32 = indices[candidate * 3..candidate * 3 + 3]
+                    .iter()
+                    .map(|vertex| vertex_scores[*vertex as usize])
+                    .sum();
+                if score > best_score {
+                    best_score = score;
+                    best = Some(*candidate);
+                }
+            }
+        }
+    }
+    optimized
+}
+
+// Splits cache optimized triangles where the cache is cold anyway and draws the clusters facing
+// away from the center first, as they are likely to hide the ones behind them.
+pub fn optimize_overdraw(indices: &[u32], positions: &[f32]) -> Vec<u32> {
+    let triangles: Vec<&[u32]> = indices.chunks_exact(3).collect();
+    if triangles.is_empty() {
+        return Vec::new();
+    }
+    let mut clusters: Vec<(usize, usize)> = Vec::new();
+    let mut fifo: VecDeque<u32> = VecDeque::with_capacity(OVERDRAW_CACHE_SIZE);
+    let mut start = 0;
+    for (index, triangle) in triangles.iter().enumerate() {
+        let mut misses = 0;
+        for vertex in triangle.iter() {
+            if !fifo.contains(vertex) {
+                misses += 1;
+                if fifo.len() == OVERDRAW_CACHE_SIZE {
+                    fifo.pop_front();
+                }
+                fifo.push_back(*vertex);
+            }
+        }
+        if misses == 3 && index > start {
+            clusters.push((start, index));
+            start = index;
+        }
+    }
+    clusters.push((start, triangles.len()));
+
+    let mut mesh_center = [0.0; 3];
+    let mut mesh_area = 0.0;
+    let cluster_keys: Vec<(Vec3, Vec3)> = clusters
+        .iter()
+        .map(|(start, end)| {
+            let mut center = [0.0; 3];
+            let mut normal = [0.0; 3];
+            let mut area = 0.0;
+            for triangle in &triangles[*start..*end] {
+                let corners = [0, 1, 2].map(|corner| position(positions, triangle[corner]));
+                let triangle_normal = face_normal(corners);
+                let triangle_area = dot(triangle_normal, triangle_normal).sqrt();
+                let centroid = scale(add(add(corners[0], corners[1]), corners[2]), 1.0 / 3.0);
+                center = add(center, scale(centroid, triangle_area));
+                normal = add(normal, triangle_normal);
+                area += triangle_area;
+            }
+            mesh_center = add(mesh_center, center);
+            mesh_area += area;
+            (if area > 0.0 { scale(center, 1.0 / area) } else { center }, normal)
+        })
+        .collect();
+    if mesh_area > 0.0 {
+        mesh_center = scale(mesh_center, 1.0 / mesh_area);
+    }
+
+    let mut order: Vec<usize> = (0..clusters.len()).collect();
+    let facing = |cluster: usize| {
+        let (center, normal) = cluster_keys[cluster];
+        dot(sub(center, mesh_center), normal)
+    };
+    order.sort_by(|a, b| facing(*b).total_cmp(&facing(*a)));
+    order
+        .into_iter()
+        .flat_map(|cluster| {
+            let (start, end) = clusters[cluster];
+            triangles[start..end].iter().flat_map(|triangle| triangle.iter().copied())
+        })
+        .collect()
+}
+
+fn remap_attribute<T: Copy>(data: &[T], components: usize, order: &[u32]) -> Vec<T> {
+    order
+        .iter()
+        .flat_map(|vertex| {
+            let start = *vertex as usize * components;
+            data[start..start + components].iter().copied()
+        })
+        .collect()
+}
+
+// Orders the vertices by first use so they are read sequentially, unused vertices are dropped.
+// Runs before quantization, the packed attributes are not reordered.
+pub fn optimize_vertex_fetch(mesh: &mut MeshStruct) {
+    let vertex_count = mesh.vertices.len() / 3;
+    let mut remap: Vec<Option<u32>> = vec![None; vertex_count];
+    let mut order: Vec<u32> = Vec::with_capacity(vertex_count);
+    let lod_indices = mesh.lods.iter().flat_map(|lod| lod.indices.iter());
+    for vertex in mesh.indices.iter().chain(lod_indices) {
+        if remap[*vertex as usize].is_none() {
+            remap[*vertex as usize] = Some(order.len() as u32);
+            order.push(*vertex);
+        }
+    }
+    let apply = |indices: &mut Vec<u32>| {
+        indices.iter_mut().for_each(|vertex| *vertex = remap[*vertex as usize].unwrap());
+    };
+    apply(&mut mesh.indices);
+    mesh.lods.iter_mut().for_each(|lod| apply(&mut lod.indices));
+
+    mesh.vertices = remap_attribute(&mesh.vertices, 3, &order);
+    if !mesh.normals.is_empty() {
+        mesh.normals = remap_attribute(&mesh.normals, 3, &order);
+    }
+    for (attribute, components) in [
+        (&mut mesh.texture_coords, 2),
+        (&mut mesh.tangents, 3),
+        (&mut mesh.bitangents, 3),
+    ] {
+        if let Some(data) = attribute {
+            *data = remap_attribute(data, components, &order);
+        }
+    }
+    if let Some(skin) = &mut mesh.skin {
+        skin.joints = remap_attribute(&skin.joints, MAX_BONE_INFLUENCES, &order);
+        skin.weights = remap_attribute(&skin.weights, MAX_BONE_INFLUENCES, &order);
+    }
+}
+
+// Reorders the triangles of every level and then the vertices.
+pub fn optimize_mesh(mesh: &mut MeshStruct) {
+    let vertex_count = mesh.vertices.len() / 3;
+    let optimize = |indices: &[u32]| optimize_overdraw(&optimize_vertex_cache(indices, vertex_count), &mesh.vertices);
+    let indices = optimize(&mesh.indices);
+    let lods: Vec<Vec<u32>> = mesh.lods.iter().map(|lod| optimize(&lod.indices)).collect();
+    mesh.indices = indices;
+    for (lod, indices) in mesh.lods.iter_mut().zip(lods) {
+        lod.indices = indices;
+    }
+    optimize_vertex_fetch(mesh);
+}
+
+// Symmetric 4x4 matrix summing the squared distances to a set of planes.
+#[derive(Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane(normal: Vec3, distance: f32) -> Self {
+        let [a, b, c] = normal.map(f64::from);
+        let d = f64::from(distance);
+        Quadric([a * a, a * b, a * c, a * d, b * b, b * c, b * d, c * c, c * d, d * d])
+    }
+
+    fn add(&self, other: &Quadric) -> Self {
+        let mut sum = self.0;
+        sum.iter_mut().zip(other.0.iter()).for_each(|(a, b)| *a += b);
+        Quadric(sum)
+    }
+
+    fn error(&self, point: Vec3) -> f64 {
+        let [x, y, z] = point.map(f64::from);
+        let q = &self.0;
+        q[0] * x * x + 2.0 * q[1] * x * y + 2.0 * q[2] * x * z + 2.0 * q[3] * x
+            + q[4] * y * y + 2.0 * q[5] * y * z + 2.0 * q[6] * y
+            + q[7] * z * z + 2.0 * q[8] * z
+            + q[9]
+    }
+}
+
+// Quadric error edge collapses onto existing vertices, so the result indexes the same vertices.
+// Vertices on open borders and on seams, where several vertices share a position, stay in place.
+// Returns the indices and the largest error introduced, relative to the size of the mesh.
+pub fn simplify(indices: &[u32], positions: &[f32], target_triangles: usize, max_error: f32) -> (Vec<u32>, f32) {
+    let vertex_count = positions.len() / 3;
+    let mut result = indices.to_vec();
+    if vertex_count == 0 || result.len() / 3 <= target_triangles {
+        return (result, 0.0);
+    }
+    let mut min = position(positions, 0);
+    let mut max = min;
+    for vertex in 1..vertex_count as u32 {
+        let point = position(positions, vertex);
+        min = [0, 1, 2].map(|axis| min[axis].min(point[axis]));
+        max = [0, 1, 2].map(|axis| max[axis].max(point[axis]));
+    }
+    let extent = dot(sub(max, min), sub(max, min)).sqrt().max(f32::EPSILON);
+
+    let mut canonical: Vec<u32> = (0..vertex_count as u32).collect();
+    let mut locked = vec![false; vertex_count];
+    let mut by_position: HashMap<[u32; 3], u32> = HashMap::new();
+    for vertex in 0..vertex_count as u32 {
+        let key = position(positions, vertex).map(f32::to_bits);
+        match by_position.get(&key) {
+            Some(first) => {
+                canonical[vertex as usize] = *first;
+                locked[vertex as usize] = true;
+                locked[*first as usize] = true;
+            }
+            None => {
+                by_position.insert(key, vertex);
+            }
+        }
+    }
+    let mut edge_uses: HashMap<(u32, u32), u32> = HashMap::new();
+    let mut quadrics = vec![Quadric::default(); vertex_count];
+    for triangle in result.chunks_exact(3) {
+        for corner in 0..3 {
+            let (a, b) = (canonical[triangle[corner] as usize], canonical[triangle[(corner + 1) % 3] as usize]);
+            *edge_uses.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+        }
+        let normal = face_normal([0, 1, 2].map(|corner| position(positions, triangle[corner])));
+        let length = dot(normal, normal).sqrt();
+        if length > 0.0 {
+            let normal = scale(normal, 1.0 / length);
+            let plane = Quadric::from_plane(normal, -dot(normal, position(positions, triangle[0])));
+            for vertex in triangle {
+                let vertex = canonical[*vertex as usize] as usize;
+                quadrics[vertex] = quadrics[vertex].add(&plane);
+            }
+        }
+    }
+    for ((a, b), uses) in edge_uses {
+        if uses == 1 {
+            locked[a as usize] = true;
+            locked[b as usize] = true;
+        }
+    }
+    for vertex in 0..vertex_count {
+        locked[vertex] = locked[vertex] || locked[canonical[vertex] as usize];
+    }
+
+    let max_cost = f64::from(max_error * extent).powi(2);
+    let mut error: f64 = 0.0;
+    while result.len() / 3 > target_triangles {
+        let mut vertex_triangles: Vec<Vec<usize>> = vec![Vec::new(); vertex_count];
+        for (triangle, vertices) in result.chunks_exact(3).enumerate() {
+            for vertex in vertices {
+                vertex_triangles[*vertex as usize].push(triangle);
+            }
+        }
+        let mut seen: HashSet<(u32, u32)> = HashSet::new();
+        let mut collapses: Vec<(f64, u32, u32)> = Vec::new();
+        for triangle in result.chunks_exact(3) {
+            for corner in 0..3 {
+                let (a, b) = (triangle[corner], triangle[(corner + 1) % 3]);
+                if !seen.insert((a.min(b), a.max(b))) {
+                    continue;
+                }
+                let quadric = quadrics[canonical[a as usize] as usize].add(&quadrics[canonical[b as usize] as usize]);
+                let cheapest = [(a, b), (b, a)]
+                    .into_iter()
+                    .filter(|(from, _)| !locked[*from as usize])
+                    .map(|(from, to)| (quadric.error(position(positions, to)), from, to))
+                    .min_by(|a, b| a.0.total_cmp(&b.0));
+                collapses.extend(cheapest);
+            }
+        }
+        collapses.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // the triangles around a collapsed vertex may not change again in the same pass
+        let flips = |from: u32, to: u32| {
+            vertex_triangles[from as usize].iter().any(|triangle| {
+                let vertices = &result[triangle * 3..triangle * 3 + 3];
+                if vertices.contains(&to) {
+                    return false;
+                }
+                let before = [0, 1, 2].map(|corner| position(positions, vertices[corner]));
+                let after = [0, 1, 2].map(|corner| {
+                    position(positions, if vertices[corner] == from { to } else { vertices[corner] })
+                });
+                dot(face_normal(before), face_normal(after)) <= 0.0
+            })
+        };
+        let to_remove = result.len() / 3 - target_triangles;
+        let mut removed = 0;
+        let mut collapse: Vec<Option<u32>> = vec![None; vertex_count];
+        let mut touched = vec![false; vertex_count];
+        for (cost, from, to) in collapses {
+            if cost > max_cost || removed >= to_remove {
+                break;
+            }
+            if touched[from as usize] || touched[to as usize] || flips(from, to) {
+                continue;
+            }
+            collapse[from as usize] = Some(to);
+            for triangle in &vertex_triangles[from as usize] {
+                let vertices = &result[triangle * 3..triangle * 3 + 3];
+                vertices.iter().for_each(|vertex| touched[*vertex as usize] = true);
+                if vertices.contains(&to) {
+                    removed += 1;
+                }
+            }
+            let (from, to) = (canonical[from as usize] as usize, canonical[to as usize] as usize);
+            quadrics[to] = quadrics[to].add(&quadrics[from]);
+            error = error.max(cost);
+        }
+        if removed == 0 {
+            break;
+        }
+        result = result
+            .chunks_exact(3)
+            .map(|triangle| [0, 1, 2].map(|corner| collapse[triangle[corner] as usize].unwrap_or(triangle[corner])))
+            .filter(|[a, b, c]| a != b && b != c && a != c)
+            .flatten()
+            .collect();
+    }
+    (result, error.sqrt() as f32 / extent)
+}
+
+// Each level aims for half the triangles of the previous one, generation stops at the first
+// level that cannot get meaningfully smaller within LOD_MAX_ERROR.
+pub fn generate_lods(mesh: &MeshStruct, levels: u32) -> Vec<LodStruct> {
+    let mut lods: Vec<LodStruct> = Vec::new();
+    let mut indices = mesh.indices.clone();
+    let mut error: f32 = 0.0;
+    for _ in 0..levels {
+        let triangles = indices.len() / 3;
+        let (simplified, level_error) = simplify(&indices, &mesh.vertices, triangles / 2, LOD_MAX_ERROR);
+        if simplified.is_empty() || simplified.len() / 3 > triangles * 9 / 10 {
+            break;
+        }
+        error = error.max(level_error);
+        lods.push(LodStruct {
+            indices: simplified.clone(),
+            error,
+        });
+        indices = simplified;
+    }
+    lods
+}
+
+fn snorm(value: f32) -> i8 {
+    (value.clamp(-1.0, 1.0) * 127.0).round() as i8
+}
+
+// IEEE 754 half precision bits, rounded to nearest
+pub fn half_from_f32(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = bits & 0x7f_ffff;
+    if bits & 0x7fff_ffff > 0x7f80_0000 {
+        return sign | 0x7e00;
+    }
+    if exponent >= 31 {
+        return sign | 0x7c00;
+    }
+    if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exponent) as u32;
+        let round = ((mantissa >> (shift - 1)) & 1) as u16;
+        return sign | ((mantissa >> shift) as u16 + round);
+    }
+    // a rounding carry into the exponent still gives the right value
+    (sign | ((exponent as u16) << 10) | (mantissa >> 13) as u16) + ((mantissa >> 12) & 1) as u16
+}
+
+// Normals and tangents become signed normalized bytes, padded to four per vertex to keep the
+// attributes aligned, and texture coordinates half floats. Positions keep their precision.
+pub fn quantize(mesh: &mut MeshStruct) {
+    let pack_directions = |directions: &[f32]| -> Vec<i8> {
+        directions.chunks_exact(3).flat_map(|d| [snorm(d[0]), snorm(d[1]), snorm(d[2]), 0]).collect()
+    };
+    mesh.packed = Some(PackedStruct {
+        normals: pack_directions(&mesh.normals),
+        tangents: mesh.tangents.take().map(|tangents| pack_directions(&tangents)),
+        bitangents: mesh.bitangents.take().map(|bitangents| pack_directions(&bitangents)),
+        texture_coords: mesh
+            .texture_coords
+            .take()
+            .map(|coords| coords.iter().map(|coord| half_from_f32(*coord)).collect()),
+    });
+    mesh.normals = Vec::new();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // flat square grid of size by size vertices, two triangles per cell
+    fn grid(size: u32) -> (Vec<f32>, Vec<u32>) {
+        let positions = (0..size * size).flat_map(|vertex| [(vertex % size) as f32, (vertex / size) as f32, 0.0]).collect();
+        let indices = (0..size - 1)
+            .flat_map(|row| (0..size - 1).map(move |column| row * size + column))
+            .flat_map(|corner| [corner, corner + 1, corner + size + 1, corner, corner + size + 1, corner + size])
+            .collect();
+        (positions, indices)
+    }
+
+    fn is_valid(indices: &[u32], vertex_count: usize) -> bool {
+        indices.len() % 3 == 0
+            && indices.iter().all(|index| (*index as usize) < vertex_count)
+            && indices.chunks_exact(3).all(|t| t[0] != t[1] && t[1] != t[2] && t[0] != t[2])
+    }
+
+    // edges used by a single triangle
+    fn border_edges(indices: &[u32]) -> HashSet<(u32, u32)> {
+        let mut uses: HashMap<(u32, u32), u32> = HashMap::new();
+        for triangle in indices.chunks_exact(3) {
+            for corner in 0..3 {
+                let (a, b) = (triangle[corner], triangle[(corner + 1) % 3]);
+                *uses.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+        uses.into_iter().filter(|(_, uses)| *uses == 1).map(|(edge, _)| edge).collect()
+    }
+
+    #[test]
+    fn half_from_f32_converts_exact_values() {
+        assert_eq!(half_from_f32(0.0), 0x0000);
+        assert_eq!(half_from_f32(-0.0), 0x8000);
+        assert_eq!(half_from_f32(1.0), 0x3c00);
+        assert_eq!(half_from_f32(0.5), 0x3800);
+        assert_eq!(half_from_f32(-2.0), 0xc000);
+        assert_eq!(half_from_f32(65504.0), 0x7bff);
+        // smallest normal and subnormal
+        assert_eq!(half_from_f32(2f32.powi(-14)), 0x0400);
+        assert_eq!(half_from_f32(2f32.powi(-24)), 0x0001);
+    }
+
+    #[test]
+    fn half_from_f32_rounds_to_nearest() {
+        assert_eq!(half_from_f32(1.0 / 3.0), 0x3555);
+        assert_eq!(half_from_f32(1.0 + 3.0 * 2f32.powi(-12)), 0x3c01);
+        assert_eq!(half_from_f32(1.0 + 2f32.powi(-12)), 0x3c00);
+        // the rounding carries into the exponent
+        assert_eq!(half_from_f32(2.0 - 2f32.powi(-12)), 0x4000);
+    }
+
+    #[test]
+    fn half_from_f32_handles_out_of_range_values() {
+        assert_eq!(half_from_f32(65520.0), 0x7c00);
+        assert_eq!(half_from_f32(1.0e6), 0x7c00);
+        assert_eq!(half_from_f32(f32::NEG_INFINITY), 0xfc00);
+        assert_eq!(half_from_f32(f32::NAN) & 0x7e00, 0x7e00);
+        assert_eq!(half_from_f32(2f32.powi(-26)), 0x0000);
+        assert_eq!(half_from_f32(-2f32.powi(-26)), 0x8000);
+    }
+
+    #[test]
+    fn simplify_returns_valid_indices() {
+        let (positions, indices) = grid(8);
+        let (simplified, error) = simplify(&indices, &positions, 20, LOD_MAX_ERROR);
+        assert!(is_valid(&simplified, positions.len() / 3));
+        assert!(simplified.len() < indices.len());
+        assert!(error <= LOD_MAX_ERROR);
+    }
+
+    #[test]
+    fn simplify_keeps_the_borders() {
+        let (positions, indices) = grid(8);
+        let (simplified, _) = simplify(&indices, &positions, 20, LOD_MAX_ERROR);
+        assert_eq!(border_edges(&simplified), border_edges(&indices));
+    }
+
+    #[test]
+    fn simplify_keeps_seams() {
+        // the right half of the grid gets its own copy of the middle column, like a texture seam
+        let (mut positions, indices) = grid(8);
+        let vertex_count = positions.len() as u32 / 3;
+        let seam: Vec<u32> = (0..8).map(|row| row * 8 + 4).collect();
+        for vertex in &seam {
+            let copy = position(&positions, *vertex);
+            positions.extend_from_slice(&copy);
+        }
+        let split: Vec<u32> = indices
+            .chunks_exact(3)
+            .flat_map(|triangle| {
+                let right = triangle.iter().all(|vertex| vertex % 8 >= 4);
+                triangle
+                    .iter()
+                    .map(move |vertex| if right && vertex % 8 == 4 { vertex_count + vertex / 8 } else { *vertex })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let (simplified, _) = simplify(&split, &positions, 20, LOD_MAX_ERROR);
+        assert!(is_valid(&simplified, positions.len() / 3));
+        assert!(simplified.len() < split.len());
+        // the seam edges are used once on each side, like the borders
+        assert_eq!(border_edges(&simplified), border_edges(&split));
+    }
+
+    #[test]
+    fn simplify_stays_within_the_error() {
+        // a curved surface, every collapse moves it
+        let (mut positions, indices) = grid(8);
+        positions.chunks_exact_mut(3).for_each(|vertex| vertex[2] = (vertex[0] - 3.5).powi(2) + (vertex[1] - 3.5).powi(2));
+        let (simplified, error) = simplify(&indices, &positions, 0, 0.0);
+        assert_eq!(simplified, indices);
+        assert_eq!(error, 0.0);
+    }
+
+    #[test]
+    fn simplify_leaves_small_meshes() {
+        let (positions, indices) = grid(3);
+        let (simplified, error) = simplify(&indices, &positions, 8, LOD_MAX_ERROR);
+        assert_eq!(simplified, indices);
+        assert_eq!(error, 0.0);
+    }
+}
@@ -20,12 +20,17 @@ pub mod gpu {
 pub mod models{
     use std::error::Error;
     use std::path::{Path, PathBuf};
-    use glengine_mdl::models::{FileStruct, EXTENSION};
-    const CONVERTIBLE_EXTENSIONS: [&str;1] = ["obj"];
+    use glengine_mdl::models::{is_importable, FileStruct, EXTENSION};
+
+    // obj, gltf, glb, fbx and dae, glTF materials, embedded images, nodes and skins are kept
+    pub fn is_convertible(path: &Path) -> bool {
+        is_importable(path)
+    }
     pub fn convert_file(source: &Path,destination:&Path) -> Result<(),Box<dyn Error>> {
-        let file = FileStruct::import(source.to_str().unwrap());
+        let file = FileStruct::import(source.to_str().unwrap())?;
         std::fs::create_dir_all(Path::new(&destination).parent().unwrap())?;
-        file.save(destination.to_str().unwrap())
+        file.save(destination.to_str().unwrap())?;
+        Ok(())
     }
 
     pub fn convert_name(file:&Path,source: &Path,destination:&Path) -> PathBuf {
@@ -39,7 +44,7 @@ pub mod models{
         for entry in glob::glob(&format!("{}/**/*", source.to_str().unwrap()))? {
             match entry {
                 Ok(path) => {
-                    if !CONVERTIBLE_EXTENSIONS.iter().any(|ext| path.to_str().unwrap().ends_with(ext)){
+                    if !is_convertible(&path){
                         continue;
                     }
                     let destination = convert_name(&path,&source,&destination);
@@ -56,11 +61,11 @@ pub mod models{
         for entry in glob::glob(&format!("{}/**/*", source.to_str().unwrap()))? {
             match entry {
                 Ok(path) => {
-                    let destination = convert_name(&path,&source,&destination);
-                    if !should_convert(&path,&destination){
+                    if !is_convertible(&path) {
                         continue;
                     }
-                    if !CONVERTIBLE_EXTENSIONS.iter().any(|ext| path.to_str().unwrap().ends_with(ext)) {
+                    let destination = convert_name(&path,&source,&destination);
+                    if !should_convert(&path,&destination){
                         continue;
                     }
                     convert_file(&path,&destination)?;
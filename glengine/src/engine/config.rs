@@ -4,6 +4,15 @@ use std::io::{Read, Write};
 use std::time::Duration;
 use once_cell::sync::Lazy;
 
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RenderPath {
+    // every object is lit while it is drawn
+    #[default]
+    Forward,
+    // lit objects are written to a G-buffer first and lit once per pixel
+    Deferred,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Config {
     resolution: (u32, u32),
@@ -11,6 +20,22 @@ pub struct Config {
     near_clip: f32,
     far_clip: f32,
     fixed_step: Duration,
+    #[serde(default)]
+    render_path: RenderPath,
+    // GL upload time of the asset loader per frame
+    #[serde(default = "default_upload_budget")]
+    upload_budget: Duration,
+    // skip the objects outside the camera frustum
+    #[serde(default = "default_frustum_culling")]
+    frustum_culling: bool,
+}
+
+fn default_upload_budget() -> Duration {
+    Duration::from_millis(4)
+}
+
+fn default_frustum_culling() -> bool {
+    true
 }
 
 impl Config {
@@ -55,6 +80,21 @@ impl Config {
         self
     }
 
+    pub fn with_render_path(mut self, render_path: RenderPath) -> Self {
+        self.render_path = render_path;
+        self
+    }
+
+    pub fn with_upload_budget(mut self, upload_budget: Duration) -> Self {
+        self.upload_budget = upload_budget;
+        self
+    }
+
+    pub fn with_frustum_culling(mut self, frustum_culling: bool) -> Self {
+        self.frustum_culling = frustum_culling;
+        self
+    }
+
     pub fn get_resolution(&self) -> (u32, u32) {
         self.resolution
     }
@@ -78,6 +118,18 @@ impl Config {
     pub fn get_fixed_step(&self) -> Duration {
         self.fixed_step
     }
+
+    pub fn get_render_path(&self) -> RenderPath {
+        self.render_path
+    }
+
+    pub fn get_upload_budget(&self) -> Duration {
+        self.upload_budget
+    }
+
+    pub fn get_frustum_culling(&self) -> bool {
+        self.frustum_culling
+    }
 }
 
 impl Default for Config {
@@ -88,6 +140,9 @@ impl Default for Config {
             near_clip: 0.1,
             far_clip: 300.0,
             fixed_step: Duration::from_millis(20),
+            render_path: RenderPath::Forward,
+            upload_budget: default_upload_budget(),
+            frustum_culling: default_frustum_culling(),
         };
         default
     }
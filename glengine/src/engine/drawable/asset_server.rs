@@ -0,0 +1,340 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime};
+use glengine_mdl::models::{resolve_texture_path, FileStruct, ImportOptions};
+use once_cell::unsync::Lazy;
+
+use crate::engine::drawable::base::BaseDrawable;
+use crate::engine::drawable::importer::nmdl::mesh_data;
+use crate::engine::drawable::manager::DRAWABLE_MANAGER;
+use crate::engine::drawable::material::manager::MaterialHandle;
+use crate::engine::drawable::material::Material;
+use crate::engine::drawable::mesh::manager::MeshHandle;
+use crate::engine::drawable::mesh::BaseMesh;
+use crate::engine::drawable::shader::manager::CustomShaderHandle;
+use crate::engine::drawable::shader::Shader;
+use crate::result::{EngineRenderResult, ShaderError};
+
+// Shader stages read from glsl files, each stage is the concatenation of its files like the
+// included shaders built with concat!.
+#[derive(Clone, Debug, Default)]
+pub struct ShaderSource {
+    pub vertex: Vec<PathBuf>,
+    pub fragment: Vec<PathBuf>,
+    pub geometry: Vec<PathBuf>,
+}
+
+impl ShaderSource {
+    pub fn new(vertex: &[&Path], fragment: &[&Path]) -> Self {
+        Self {
+            vertex: vertex.iter().map(|path| path.to_path_buf()).collect(),
+            fragment: fragment.iter().map(|path| path.to_path_buf()).collect(),
+            geometry: Vec::new(),
+        }
+    }
+
+    pub fn with_geometry(mut self, geometry: &[&Path]) -> Self {
+        self.geometry = geometry.iter().map(|path| path.to_path_buf()).collect();
+        self
+    }
+
+    fn files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.vertex.iter().chain(&self.fragment).chain(&self.geometry)
+    }
+
+    fn read(files: &[PathBuf]) -> EngineRenderResult<Option<String>> {
+        if files.is_empty() {
+            return Ok(None);
+        }
+        let mut source = String::new();
+        for file in files {
+            match std::fs::read_to_string(file) {
+                Ok(text) => source.push_str(&text),
+                Err(e) => {
+                    eprintln!("Could not read shader {}: {}", file.display(), e);
+                    return Err(ShaderError::ReadSourceError.into());
+                }
+            }
+        }
+        Ok(Some(source))
+    }
+
+    pub fn compile(&self) -> EngineRenderResult<Shader> {
+        let vertex = Self::read(&self.vertex)?;
+        let fragment = Self::read(&self.fragment)?;
+        let geometry = Self::read(&self.geometry)?;
+        Shader::new(vertex.as_deref(), fragment.as_deref(), geometry.as_deref())
+    }
+}
+
+enum Asset {
+    Model {
+        source: PathBuf,
+        options: ImportOptions,
+        // one entry per mesh of the source, in the order of the file meshes
+        draw_data: Vec<(MeshHandle, Option<MaterialHandle>)>,
+        // texture files found by importing the source on a separate thread
+        dependencies: Option<mpsc::Receiver<Vec<PathBuf>>>,
+        // the source being re-imported on a separate thread, swapped in once it is done
+        import: Option<mpsc::Receiver<Result<FileStruct, String>>>,
+    },
+    Shader {
+        source: ShaderSource,
+        handle: CustomShaderHandle,
+    },
+}
+
+struct Watch {
+    asset: Asset,
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn watched(files: impl IntoIterator<Item = PathBuf>) -> Vec<(PathBuf, Option<SystemTime>)> {
+    files.into_iter().map(|file| {
+        let time = modified(&file);
+        (file, time)
+    }).collect()
+}
+
+// The source with its material library and textures, textures embedded in the source are skipped.
+fn model_files(source: &Path, file: Option<&FileStruct>) -> Vec<PathBuf> {
+    let mut files = vec![source.to_path_buf()];
+    let mtl = source.with_extension("mtl");
+    if mtl.exists() {
+        files.push(mtl);
+    }
+    if let Some(file) = file {
+        let dir = source.parent().unwrap_or(Path::new(""));
+        for material in &file.materials.materials {
+            for name in material.texture.names() {
+                let path = resolve_texture_path(dir, name);
+                if path.is_file() && !files.contains(&path) {
+                    files.push(path);
+                }
+            }
+        }
+    }
+    files
+}
+
+impl Watch {
+    // the watch is dropped once the server holds the last handles
+    fn is_orphaned(&self) -> bool {
+        match &self.asset {
+            Asset::Model { draw_data, .. } => draw_data.iter().all(|(mesh, _)| mesh.is_unique()),
+            Asset::Shader { handle, .. } => handle.is_unique(),
+        }
+    }
+
+    fn changed(&mut self) -> bool {
+        let mut changed = false;
+        for (file, time) in &mut self.files {
+            let current = modified(file);
+            // a file being rewritten can be missing for a moment, it is picked up once it is back
+            if current.is_some() && current != *time {
+                *time = current;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn poll_dependencies(&mut self) {
+        let Asset::Model { dependencies: Some(receiver), .. } = &self.asset else { return };
+        match receiver.try_recv() {
+            Ok(files) => {
+                for file in files {
+                    if !self.files.iter().any(|(watched, _)| *watched == file) {
+                        let time = modified(&file);
+                        self.files.push((file, time));
+                    }
+                }
+            }
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => {}
+        }
+        if let Asset::Model { dependencies, .. } = &mut self.asset {
+            *dependencies = None;
+        }
+    }
+
+    // Swaps in the re-imported model once its import thread is done.
+    fn poll_import(&mut self) {
+        let Asset::Model { import: Some(receiver), .. } = &self.asset else { return };
+        let file = match receiver.try_recv() {
+            Ok(file) => file,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => Err("the import thread stopped".to_string()),
+        };
+        let Asset::Model { source, draw_data, import, .. } = &mut self.asset else { return };
+        *import = None;
+        let file = match file {
+            Ok(file) => file,
+            Err(e) => {
+                eprintln!("Could not reload model {}: {}", source.display(), e);
+                return;
+            }
+        };
+        if file.meshes.len() != draw_data.len() {
+            eprintln!(
+                "Could not reload model {}: it has {} meshes instead of {}, restart to apply",
+                source.display(),
+                file.meshes.len(),
+                draw_data.len()
+            );
+            return;
+        }
+        let mut materials = HashSet::new();
+        DRAWABLE_MANAGER.with(|dm| {
+            let mut dm = dm.borrow_mut();
+            for (mesh, (mesh_handle, material_handle)) in file.meshes.iter().zip(draw_data.iter()) {
+                dm.mesh.replace(mesh_handle, Box::new(BaseMesh { mesh_data: mesh_data(mesh) }));
+                // meshes sharing a material share its handle
+                if let Some(material_handle) = material_handle {
+                    if materials.insert(mesh.material_index) {
+                        let material = file.materials.materials[mesh.material_index as usize].clone();
+                        match Material::from_nmdl(material, &file.materials.textures) {
+                            Ok(material) => {
+                                dm.material.replace(material_handle, material);
+                            }
+                            // the previous material is kept, like the previous shader on a compile error
+                            Err(e) => eprintln!(
+                                "Could not reload material {} of {}: {}",
+                                mesh.material_index,
+                                source.display(),
+                                e
+                            ),
+                        }
+                    }
+                }
+            }
+        });
+        let files = model_files(source, Some(&file));
+        self.files.retain(|(watched, _)| files.contains(watched));
+        for file in files {
+            if !self.files.iter().any(|(watched, _)| *watched == file) {
+                let time = modified(&file);
+                self.files.push((file, time));
+            }
+        }
+        println!("Reloaded model {}", source.display());
+    }
+
+    // Models are re-imported on a separate thread, the import with its optimizations would stall
+    // the frame. Shaders are compiled right away.
+    fn reload(&mut self) {
+        match &mut self.asset {
+            Asset::Model { source, options, import, .. } => {
+                let (sender, receiver) = mpsc::channel();
+                let thread_source = source.clone();
+                let options = *options;
+                std::thread::spawn(move || {
+                    let file = FileStruct::import_with_options(thread_source.to_str().unwrap(), &options)
+                        .map_err(|e| e.to_string());
+                    let _ = sender.send(file);
+                });
+                // an import still running is replaced, its result is dropped
+                *import = Some(receiver);
+            }
+            Asset::Shader { source, handle } => match source.compile() {
+                Ok(shader) => {
+                    DRAWABLE_MANAGER.with(|dm| dm.borrow_mut().shader.replace(handle, shader));
+                    println!("Reloaded shader {:?}", source.fragment);
+                }
+                // the compile and link errors are already printed, the previous shader is kept
+                Err(e) => eprintln!("Could not reload shader {:?}: {}", source.fragment, e),
+            },
+        }
+    }
+}
+
+// Polls the source files of watched models and shaders and swaps the resources behind their
+// handles when the files change, so drawables using them pick up the change without a restart.
+pub struct AssetServer {
+    watches: Vec<Watch>,
+    interval: Duration,
+    last_poll: Instant,
+}
+
+impl AssetServer {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            watches: Vec::new(),
+            interval,
+            last_poll: Instant::now(),
+        }
+    }
+
+    // Re-imports the source model when it, its material library or its textures change. The
+    // drawable must have been imported from the same source, mesh for mesh.
+    pub fn watch_model(&mut self, source: impl AsRef<Path>, drawable: &BaseDrawable) {
+        self.watch_model_with_options(source, drawable, ImportOptions::default());
+    }
+
+    pub fn watch_model_with_options(&mut self, source: impl AsRef<Path>, drawable: &BaseDrawable, options: ImportOptions) {
+        let source = source.as_ref().to_path_buf();
+        let (sender, receiver) = mpsc::channel();
+        let thread_source = source.clone();
+        std::thread::spawn(move || {
+            if let Ok(file) = FileStruct::import_with_options(thread_source.to_str().unwrap(), &options) {
+                let _ = sender.send(model_files(&thread_source, Some(&file)));
+            }
+        });
+        self.watches.push(Watch {
+            files: watched(model_files(&source, None)),
+            asset: Asset::Model {
+                source,
+                options,
+                draw_data: drawable
+                    .draw_data
+                    .iter()
+                    .map(|draw_data| (draw_data.mesh_handle.clone(), draw_data.material_handle.clone()))
+                    .collect(),
+                dependencies: Some(receiver),
+                import: None,
+            },
+        });
+    }
+
+    // Compiles the shader and recompiles it whenever one of its files changes.
+    pub fn watch_shader(&mut self, source: ShaderSource) -> EngineRenderResult<CustomShaderHandle> {
+        let shader = source.compile()?;
+        let handle = DRAWABLE_MANAGER.with(|dm| dm.borrow_mut().shader.add(shader));
+        self.watches.push(Watch {
+            files: watched(source.files().cloned()),
+            asset: Asset::Shader { source, handle: handle.clone() },
+        });
+        Ok(handle)
+    }
+
+    pub fn update(&mut self) {
+        if self.watches.is_empty() || self.last_poll.elapsed() < self.interval {
+            return;
+        }
+        self.last_poll = Instant::now();
+        self.watches.retain(|watch| !watch.is_orphaned());
+        for watch in &mut self.watches {
+            watch.poll_dependencies();
+            watch.poll_import();
+            if watch.changed() {
+                watch.reload();
+            }
+        }
+    }
+}
+
+impl Default for AssetServer {
+    fn default() -> Self {
+        Self::new(Duration::from_millis(500))
+    }
+}
+
+thread_local! {
+    pub static ASSET_SERVER: Lazy<RefCell<AssetServer>> = Lazy::new(|| RefCell::new(AssetServer::default()));
+}
@@ -1,8 +1,10 @@
-use glam::Mat4;
+use glam::{Mat4, Vec4};
 
+use crate::engine::drawable::bounds::Bounds;
 use crate::engine::drawable::{mesh, Drawable};
 use crate::engine::drawable::DrawData;
 use crate::engine::drawable::mesh::manager::MeshHandle;
+use crate::engine::drawable::shader::Shader;
 use crate::engine::scene::lights::Lights;
 
 use super::shader::manager::{IncludedShaderHandle, ShaderHandle};
@@ -18,6 +20,11 @@ impl BaseDrawable {
             mesh_handle,
             shader_handle,
             material_handle: None,
+            casts_shadows: true,
+            receives_shadows: true,
+            skin: None,
+            lod: 0,
+            color: Vec4::ONE,
         };
         Self {
             draw_data: vec![draw_object],
@@ -31,6 +38,28 @@ impl Drawable for BaseDrawable {
             drawable.draw(modelmat, viewmat,lights);
         }
     }
+
+    fn draw_shadow(&mut self, modelmat: &Mat4, shader: &Shader) {
+        for drawable in self.draw_data.iter_mut() {
+            drawable.draw_shadow(modelmat, shader);
+        }
+    }
+
+    fn draw_geometry(&mut self, modelmat: &Mat4, viewmat: &Mat4) {
+        for drawable in self.draw_data.iter_mut() {
+            drawable.draw_geometry(modelmat, viewmat);
+        }
+    }
+
+    fn set_lod(&mut self, lod: usize) {
+        for drawable in self.draw_data.iter_mut() {
+            drawable.set_lod(lod);
+        }
+    }
+
+    fn bounds(&self) -> Bounds {
+        self.draw_data.iter().fold(Bounds::Empty, |bounds, drawable| bounds.union(&drawable.bounds()))
+    }
 }
 
 impl Default for BaseDrawable {
@@ -39,6 +68,11 @@ impl Default for BaseDrawable {
             mesh_handle: mesh::cube::new(),
             shader_handle: IncludedShaderHandle::Basic.into(),
             material_handle: None,
+            casts_shadows: true,
+            receives_shadows: true,
+            skin: None,
+            lod: 0,
+            color: Vec4::ONE,
         };
         Self {
             draw_data: vec![draw_object],
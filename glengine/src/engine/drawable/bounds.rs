@@ -0,0 +1,83 @@
+use glam::{Mat4, Vec3};
+use glengine_mdl::models::BoundsStruct;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    // None for an empty vertex list
+    pub fn from_vertices(vertices: &[f32]) -> Option<Self> {
+        let mut positions = vertices.chunks_exact(3).map(|vertex| Vec3::new(vertex[0], vertex[1], vertex[2]));
+        let first = positions.next()?;
+        Some(positions.fold(Self::new(first, first), |aabb, position| {
+            Self::new(aabb.min.min(position), aabb.max.max(position))
+        }))
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    // Box around the transformed box, the extents are projected on the absolute matrix.
+    pub fn transform(&self, mat: &Mat4) -> Aabb {
+        let center = mat.transform_point3(self.center());
+        let extents = self.extents();
+        let extents = mat.x_axis.truncate().abs() * extents.x
+            + mat.y_axis.truncate().abs() * extents.y
+            + mat.z_axis.truncate().abs() * extents.z;
+        Self::new(center - extents, center + extents)
+    }
+}
+
+impl From<BoundsStruct> for Aabb {
+    fn from(bounds: BoundsStruct) -> Self {
+        Self::new(Vec3::from(bounds.min), Vec3::from(bounds.max))
+    }
+}
+
+// Bounds of what a drawable draws. Unbounded drawables, like skinned meshes moving away from
+// their bind pose, are never culled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bounds {
+    Empty,
+    Finite(Aabb),
+    Unbounded,
+}
+
+impl Bounds {
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        match (self, other) {
+            (Bounds::Unbounded, _) | (_, Bounds::Unbounded) => Bounds::Unbounded,
+            (Bounds::Empty, bounds) | (bounds, Bounds::Empty) => *bounds,
+            (Bounds::Finite(a), Bounds::Finite(b)) => Bounds::Finite(a.union(b)),
+        }
+    }
+
+    pub fn transform(&self, mat: &Mat4) -> Bounds {
+        match self {
+            Bounds::Finite(aabb) => Bounds::Finite(aabb.transform(mat)),
+            bounds => *bounds,
+        }
+    }
+}
+
+impl From<Option<Aabb>> for Bounds {
+    fn from(aabb: Option<Aabb>) -> Self {
+        aabb.map_or(Bounds::Empty, Bounds::Finite)
+    }
+}
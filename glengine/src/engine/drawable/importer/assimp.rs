@@ -3,11 +3,12 @@ use russimp::scene::Scene;
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
+use glam::Vec4;
 
 use crate::engine::drawable::base::BaseDrawable;
 use crate::engine::drawable::material::{Material, manager::MaterialHandle};
 use crate::engine::drawable::mesh::{BaseMesh, MeshData};
-use crate::engine::drawable::shader::manager::{IncludedShaderHandle, ShaderHandle};
+use crate::engine::drawable::shader::manager::ShaderHandle;
 use crate::engine::drawable::DrawData;
 use crate::engine::drawable::manager::DRAWABLE_MANAGER;
 
@@ -34,10 +35,7 @@ pub fn import(path: &str) -> BaseDrawable {
                 Some(mat) => mat.clone(),
                 None => {
                     let mat: Material = scene.materials[mesh.material_index as usize].clone().into();
-                    let shader_handle:ShaderHandle = match mat.data.ambient{
-                        Some(_) => IncludedShaderHandle::LitColor.into(),
-                        None => IncludedShaderHandle::Basic.into(),
-                    };
+                    let shader_handle:ShaderHandle = mat.included_shader().into();
                     let material_handle = dm.borrow_mut().material.add(mat);
                     materials.insert(mesh.material_index, (material_handle.clone(),shader_handle.clone()));
                     (material_handle,shader_handle)
@@ -82,12 +80,31 @@ pub fn import(path: &str) -> BaseDrawable {
                     .collect::<Vec<f32>>(),
             );
         }
+        if !mesh.tangents.is_empty() && !mesh.bitangents.is_empty() {
+            mesh_data = mesh_data.with_tangents(
+                &mesh
+                    .tangents
+                    .iter()
+                    .flat_map(|v| vec![v.x, v.y, v.z])
+                    .collect::<Vec<f32>>(),
+                &mesh
+                    .bitangents
+                    .iter()
+                    .flat_map(|v| vec![v.x, v.y, v.z])
+                    .collect::<Vec<f32>>(),
+            );
+        }
         let mesh_handle = DRAWABLE_MANAGER.with(|dm| dm.borrow_mut().mesh.add(Box::new(BaseMesh{mesh_data})));
         
         let draw = DrawData {
             mesh_handle,
             shader_handle,
             material_handle: Some(material_handle),
+            casts_shadows: true,
+            receives_shadows: true,
+            skin: None,
+            lod: 0,
+            color: Vec4::ONE,
         };
         draw_data.push(draw);
     });
@@ -14,4 +14,9 @@ impl Image {
         let data = img.into_rgba8().into_raw();
         Ok(Self { width, height, data })
     }
-}
\ No newline at end of file
+
+    pub fn from_embedded(texture: &glengine_mdl::models::TextureStruct) -> Result<Self, String> {
+        let data = texture.rgba().map_err(|e| e.to_string())?;
+        Ok(Self { width: texture.width, height: texture.height, data })
+    }
+}
@@ -0,0 +1,336 @@
+use std::cell::{Ref, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Instant;
+use glengine_mdl::models::{FileStruct, MeshStruct, TextureData};
+use once_cell::unsync::Lazy;
+
+use crate::engine::config::CONFIG;
+use crate::engine::drawable::base::BaseDrawable;
+use crate::engine::drawable::importer::img::Image;
+use crate::engine::drawable::importer::nmdl::{upload_material, upload_mesh};
+use crate::engine::drawable::manager::DRAWABLE_MANAGER;
+use crate::engine::drawable::material::manager::MaterialHandle;
+use crate::engine::drawable::material::Texture;
+use crate::engine::drawable::shader::manager::ShaderHandle;
+use crate::engine::drawable::DrawData;
+
+pub enum AssetState<T> {
+    Loading,
+    Ready(T),
+    Failed(String),
+}
+
+// Future like handle to an asset of the AssetLoader, resolved on the main thread once its
+// GL resources are uploaded.
+pub struct AssetHandle<T> {
+    state: Rc<RefCell<AssetState<T>>>,
+}
+
+impl<T> Clone for AssetHandle<T> {
+    fn clone(&self) -> Self {
+        Self { state: self.state.clone() }
+    }
+}
+
+impl<T> AssetHandle<T> {
+    fn new() -> Self {
+        Self { state: Rc::new(RefCell::new(AssetState::Loading)) }
+    }
+
+    fn resolve(&self, state: AssetState<T>) {
+        *self.state.borrow_mut() = state;
+    }
+
+    pub fn is_loading(&self) -> bool {
+        matches!(*self.state.borrow(), AssetState::Loading)
+    }
+
+    pub fn is_ready(&self) -> bool {
+        matches!(*self.state.borrow(), AssetState::Ready(_))
+    }
+
+    pub fn get(&self) -> Option<Ref<T>> {
+        Ref::filter_map(self.state.borrow(), |state| match state {
+            AssetState::Ready(asset) => Some(asset),
+            _ => None,
+        })
+        .ok()
+    }
+
+    pub fn error(&self) -> Option<String> {
+        match &*self.state.borrow() {
+            AssetState::Failed(error) => Some(error.clone()),
+            _ => None,
+        }
+    }
+}
+
+pub struct ModelAsset {
+    pub drawable: BaseDrawable,
+    // kept for building colliders
+    pub meshes: Rc<Vec<MeshStruct>>,
+}
+
+enum Job {
+    Model(u64, String),
+    Image(u64, String),
+}
+
+enum Decoded {
+    Model(u64, Result<FileStruct, String>),
+    Image(u64, Result<Image, String>),
+}
+
+// Runs on the worker threads, embedded textures are decoded here so the upload only copies pixels.
+fn decode(job: Job) -> Decoded {
+    match job {
+        Job::Model(id, path) => {
+            let file = FileStruct::load(&path).map_err(|e| format!("Could not load NMDL {}: {}", path, e));
+            let file = file.map(|mut file| {
+                for texture in file.materials.textures.values_mut() {
+                    if let (TextureData::Png(_), Ok(rgba)) = (&texture.data, texture.rgba()) {
+                        texture.data = TextureData::Rgba(rgba);
+                    }
+                }
+                file
+            });
+            Decoded::Model(id, file)
+        }
+        Job::Image(id, path) => {
+            Decoded::Image(id, Image::load(&path).map_err(|e| format!("Could not load image {}: {}", path, e)))
+        }
+    }
+}
+
+// A decoded model uploaded one material or mesh at a time.
+struct ModelUpload {
+    path: String,
+    file: FileStruct,
+    materials: HashMap<u32, (MaterialHandle, ShaderHandle)>,
+    draw_data: Vec<DrawData>,
+    handle: AssetHandle<ModelAsset>,
+}
+
+impl ModelUpload {
+    // returns true once every mesh is uploaded, fails when a material can't be loaded
+    fn step(&mut self) -> Result<bool, String> {
+        if let Some(mesh) = self.file.meshes.get(self.draw_data.len()) {
+            match self.materials.get(&mesh.material_index) {
+                Some(material) => {
+                    self.draw_data.push(upload_mesh(mesh, material.clone()));
+                }
+                None => {
+                    let material = upload_material(&self.file, mesh.material_index)
+                        .map_err(|e| format!("Could not load material {} of {}: {}", mesh.material_index, self.path, e))?;
+                    self.materials.insert(mesh.material_index, material);
+                }
+            }
+        }
+        Ok(self.draw_data.len() == self.file.meshes.len())
+    }
+
+    fn finish(self) {
+        let drawable = BaseDrawable { draw_data: self.draw_data };
+        let meshes = Rc::new(self.file.meshes);
+        DRAWABLE_MANAGER.with(|dm| {
+            dm.borrow_mut().cache_model(Path::new(&self.path), &drawable, Some(meshes.clone()))
+        });
+        self.handle.resolve(AssetState::Ready(ModelAsset { drawable, meshes }));
+    }
+}
+
+enum Upload {
+    Model(ModelUpload),
+    Image(Image, AssetHandle<Texture>),
+}
+
+// Decodes NMDL files and images on worker threads and uploads them on the main thread,
+// spending at most the upload budget of the config per frame.
+pub struct AssetLoader {
+    jobs: Option<mpsc::Sender<Job>>,
+    decoded: mpsc::Receiver<Decoded>,
+    workers: Vec<JoinHandle<()>>,
+    models: HashMap<u64, (String, AssetHandle<ModelAsset>)>,
+    images: HashMap<u64, AssetHandle<Texture>>,
+    uploads: VecDeque<Upload>,
+    next_id: u64,
+}
+
+impl AssetLoader {
+    pub fn new(worker_count: usize) -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (decoded_sender, decoded) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let workers = (0..worker_count.max(1))
+            .map(|index| {
+                let job_receiver = job_receiver.clone();
+                let decoded_sender = decoded_sender.clone();
+                std::thread::Builder::new()
+                    .name(format!("asset loader {}", index))
+                    .spawn(move || loop {
+                        // the lock is released before decoding so the other workers can take jobs
+                        let job = job_receiver.lock().unwrap().recv();
+                        match job {
+                            Ok(job) => {
+                                if decoded_sender.send(decode(job)).is_err() {
+                                    break;
+                                }
+                            }
+                            Err(_) => break,
+                        }
+                    })
+                    .expect("Could not start asset loader thread")
+            })
+            .collect();
+        Self {
+            jobs: Some(jobs),
+            decoded,
+            workers,
+            models: HashMap::new(),
+            images: HashMap::new(),
+            uploads: VecDeque::new(),
+            next_id: 0,
+        }
+    }
+
+    fn send(&mut self, job: impl FnOnce(u64) -> Job) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        if let Some(jobs) = &self.jobs {
+            jobs.send(job(id)).expect("Asset loader threads stopped");
+        }
+        id
+    }
+
+    // Models already imported from the path resolve at once, sharing their GPU resources.
+    pub fn load_model(&mut self, path: &str) -> AssetHandle<ModelAsset> {
+        let handle = AssetHandle::new();
+        if let Some((drawable, Some(meshes))) = DRAWABLE_MANAGER.with(|dm| dm.borrow_mut().cached_model(Path::new(path))) {
+            handle.resolve(AssetState::Ready(ModelAsset { drawable, meshes }));
+            return handle;
+        }
+        let id = self.send(|id| Job::Model(id, path.to_string()));
+        self.models.insert(id, (path.to_string(), handle.clone()));
+        handle
+    }
+
+    pub fn load_texture(&mut self, path: &str) -> AssetHandle<Texture> {
+        let handle = AssetHandle::new();
+        let id = self.send(|id| Job::Image(id, path.to_string()));
+        self.images.insert(id, handle.clone());
+        handle
+    }
+
+    // assets still being decoded or uploaded
+    pub fn pending(&self) -> usize {
+        self.models.len() + self.images.len() + self.uploads.len()
+    }
+
+    // Queues the decoded assets and uploads them until the budget runs out, at least one step
+    // is taken every frame. Must be called on the thread owning the GL context.
+    pub fn update(&mut self) {
+        while let Ok(decoded) = self.decoded.try_recv() {
+            match decoded {
+                Decoded::Model(id, result) => {
+                    let Some((path, handle)) = self.models.remove(&id) else { continue };
+                    let file = match result {
+                        Ok(file) => file,
+                        Err(e) => {
+                            handle.resolve(AssetState::Failed(e));
+                            continue;
+                        }
+                    };
+                    // the path may have been imported while the file was decoded
+                    let cached = DRAWABLE_MANAGER.with(|dm| dm.borrow_mut().cached_model(Path::new(&path)));
+                    match cached {
+                        Some((drawable, meshes)) => {
+                            let meshes = meshes.unwrap_or_else(|| {
+                                let meshes = Rc::new(file.meshes);
+                                DRAWABLE_MANAGER.with(|dm| dm.borrow_mut().cache_meshes(Path::new(&path), meshes.clone()));
+                                meshes
+                            });
+                            handle.resolve(AssetState::Ready(ModelAsset { drawable, meshes }));
+                        }
+                        None => self.uploads.push_back(Upload::Model(ModelUpload {
+                            path,
+                            file,
+                            materials: HashMap::new(),
+                            draw_data: Vec::new(),
+                            handle,
+                        })),
+                    }
+                }
+                Decoded::Image(id, result) => {
+                    let Some(handle) = self.images.remove(&id) else { continue };
+                    match result {
+                        Ok(image) => self.uploads.push_back(Upload::Image(image, handle)),
+                        Err(e) => handle.resolve(AssetState::Failed(e)),
+                    }
+                }
+            }
+        }
+
+        let budget = CONFIG.config().get_upload_budget();
+        let start = Instant::now();
+        while let Some(upload) = self.uploads.front_mut() {
+            let done = match upload {
+                Upload::Model(model) => model.step(),
+                Upload::Image(..) => Ok(true),
+            };
+            match done {
+                Ok(false) => {}
+                Ok(true) => match self.uploads.pop_front() {
+                    Some(Upload::Model(model)) => model.finish(),
+                    Some(Upload::Image(image, handle)) => handle.resolve(AssetState::Ready(image.into())),
+                    None => {}
+                },
+                Err(e) => {
+                    if let Some(Upload::Model(model)) = self.uploads.pop_front() {
+                        model.handle.resolve(AssetState::Failed(e));
+                    }
+                }
+            }
+            if start.elapsed() >= budget {
+                break;
+            }
+        }
+    }
+}
+
+impl Default for AssetLoader {
+    fn default() -> Self {
+        let threads = std::thread::available_parallelism().map_or(2, |threads| threads.get());
+        // one thread is left to the main loop
+        Self::new((threads - 1).clamp(1, 4))
+    }
+}
+
+impl Drop for AssetLoader {
+    fn drop(&mut self) {
+        // closing the job channel stops the workers
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+thread_local! {
+    pub static ASSET_LOADER: Lazy<RefCell<AssetLoader>> = Lazy::new(|| RefCell::new(AssetLoader::default()));
+}
+
+#[macro_export]
+macro_rules! nmdl_load {
+    ($mdl_path:expr) =>{{
+        use glengine::build_utils::models::convert_name;
+        use glengine::engine::drawable::importer::loader::ASSET_LOADER;
+        use std::path::PathBuf;
+        let location = std::env::current_exe().unwrap().parent().unwrap().join("models").join($mdl_path);
+        let nmdl_location = convert_name(&location,&PathBuf::new(),&PathBuf::new());
+        ASSET_LOADER.with(|loader| loader.borrow_mut().load_model(nmdl_location.to_str().unwrap()))
+    }
+}}
@@ -1,4 +1,5 @@
 pub mod img;
 pub mod assimp;
 pub mod nmdl;
+pub mod loader;
 
@@ -1,46 +1,51 @@
 use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
 
 use crate::engine::drawable::base::BaseDrawable;
 use crate::engine::drawable::material::{Material, manager::MaterialHandle};
 use crate::engine::drawable::mesh::{BaseMesh, MeshData};
-use crate::engine::drawable::shader::manager::{IncludedShaderHandle, ShaderHandle};
+use crate::engine::drawable::shader::manager::ShaderHandle;
+use crate::engine::drawable::skin::{Bone, Skin};
 use crate::engine::drawable::DrawData;
-use glengine_mdl::models::{FileStruct, MeshStruct};
+use glam::{Mat4, Vec4};
+use glengine_mdl::models::{FileStruct, MeshStruct, NodeStruct};
 use crate::engine::drawable::manager::DRAWABLE_MANAGER;
+use crate::engine::scene::gameobject::components::animator::{AnimationClip, AnimatorComponent};
 use crate::engine::scene::gameobject::components::collider::ColliderComponent;
+use crate::engine::scene::gameobject::components::drawable::DrawableComponent;
+use crate::engine::scene::gameobject::GameObject;
+use crate::engine::transform::Transform;
+use crate::result::EngineRenderResult;
 
-fn import_w_mesh(path: &str) -> (BaseDrawable,Vec<MeshStruct>) {
-    let nmdl = FileStruct::load(path).expect("Could not load NMDL");
-    let mut materials: HashMap<u32,(MaterialHandle,ShaderHandle)> = HashMap::new();
-    let mut draw_data: Vec<DrawData> = vec![];
-    nmdl.meshes.iter().for_each(|mesh| {
-        let (material_handle,shader_handle)= DRAWABLE_MANAGER.with(|mut dm| {
-            match materials.get(&mesh.material_index) {
-                Some(mat) => mat.clone(),
-                None => {
-                    let mat: Material = nmdl.materials.materials[mesh.material_index as usize].clone().into();
-                    let shader_handle:ShaderHandle = match mat.data.ambient{
-                        Some(_) => IncludedShaderHandle::LitColor.into(),
-                        None => IncludedShaderHandle::Basic.into(),
-                    };
-                    let material_handle = dm.borrow_mut().material.add(mat);
-                    materials.insert(mesh.material_index, (material_handle.clone(),shader_handle.clone()));
-                    (material_handle,shader_handle)
-                }
-            }
-        });
-        let mut mesh_data = MeshData::new(
-            &mesh
-                .vertices
-        )
-            .with_normals(
-                &mesh
-                    .normals
-            )
-            .with_indices(
-                &mesh.indices
-            );
-        if mesh.normals.len() == 0 {
+fn load(path: &str) -> FileStruct {
+    FileStruct::load(path).unwrap_or_else(|e| panic!("Could not load NMDL {}: {}", path, e))
+}
+
+// Material of the file with the included shader it is drawn with.
+pub(crate) fn upload_material(nmdl: &FileStruct, index: u32) -> EngineRenderResult<(MaterialHandle, ShaderHandle)> {
+    let mat = Material::from_nmdl(nmdl.materials.materials[index as usize].clone(), &nmdl.materials.textures)?;
+    let shader_handle: ShaderHandle = mat.included_shader().into();
+    let material_handle = DRAWABLE_MANAGER.with(|dm| dm.borrow_mut().material.add(mat));
+    Ok((material_handle, shader_handle))
+}
+
+// Vertex buffers of the mesh with its levels of detail, packed attributes and bone weights.
+pub(crate) fn mesh_data(mesh: &MeshStruct) -> MeshData {
+    let mut mesh_data = MeshData::new(
+        &mesh
+            .vertices
+    );
+    mesh_data = if mesh.lods.is_empty() {
+        mesh_data.with_indices(&mesh.indices)
+    } else {
+        let lods: Vec<&[u32]> = mesh.lods.iter().map(|lod| lod.indices.as_slice()).collect();
+        mesh_data.with_lods(&mesh.indices, &lods)
+    };
+    match &mesh.packed {
+        Some(packed) if !packed.normals.is_empty() => mesh_data = mesh_data.with_packed_normals(&packed.normals),
+        _ if !mesh.normals.is_empty() => mesh_data = mesh_data.with_normals(&mesh.normals),
+        _ => {
             mesh_data = mesh_data.with_normals(
                 &mesh
                     .vertices
@@ -49,28 +54,155 @@ fn import_w_mesh(path: &str) -> (BaseDrawable,Vec<MeshStruct>) {
                     .collect::<Vec<f32>>(),
             );
         }
-        if let Some(tex_coords) = &mesh.texture_coords{
-            mesh_data = mesh_data.with_texcoords(
-                &tex_coords
-            );
+    }
+    if let Some(tex_coords) = &mesh.texture_coords{
+        mesh_data = mesh_data.with_texcoords(
+            &tex_coords
+        );
+    }
+    if let (Some(tangents), Some(bitangents)) = (&mesh.tangents, &mesh.bitangents) {
+        mesh_data = mesh_data.with_tangents(tangents, bitangents);
+    }
+    if let Some(packed) = &mesh.packed {
+        if let Some(tex_coords) = &packed.texture_coords {
+            mesh_data = mesh_data.with_packed_texcoords(tex_coords);
+        }
+        if let (Some(tangents), Some(bitangents)) = (&packed.tangents, &packed.bitangents) {
+            mesh_data = mesh_data.with_packed_tangents(tangents, bitangents);
         }
-        let mesh_handle = DRAWABLE_MANAGER.with(|dm| dm.borrow_mut().mesh.add(Box::new(BaseMesh { mesh_data })));
+    }
+    if let Some(skin) = &mesh.skin {
+        mesh_data = mesh_data.with_skin(&skin.joints, &skin.weights);
+    }
+    if let Some(bounds) = mesh.bounds {
+        mesh_data = mesh_data.with_bounds(bounds.into());
+    }
+    mesh_data
+}
 
-        let draw = DrawData {
-            mesh_handle,
-            shader_handle,
-            material_handle: Some(material_handle),
-        };
-        draw_data.push(draw);
+pub(crate) fn upload_mesh(mesh: &MeshStruct, (material_handle, shader_handle): (MaterialHandle, ShaderHandle)) -> DrawData {
+    let mesh_data = mesh_data(mesh);
+    let shader_handle = match (&mesh.skin, shader_handle) {
+        (Some(_), ShaderHandle::Included(included)) => included.skinned().into(),
+        (_, shader_handle) => shader_handle,
+    };
+    let skin = mesh.skin.as_ref().map(|skin| {
+        Skin::new(skin.bones.iter().map(|bone| Bone {
+            name: bone.name.clone(),
+            offset: Mat4::from_cols_array(&bone.offset),
+        }).collect())
     });
-    (BaseDrawable { draw_data },nmdl.meshes)
+    let mesh_handle = DRAWABLE_MANAGER.with(|dm| dm.borrow_mut().mesh.add(Box::new(BaseMesh { mesh_data })));
+
+    DrawData {
+        mesh_handle,
+        shader_handle,
+        material_handle: Some(material_handle),
+        casts_shadows: true,
+        receives_shadows: true,
+        skin,
+        lod: 0,
+        color: Vec4::ONE,
+    }
+}
+
+// One DrawData per mesh of the file, in the order of the file meshes.
+fn upload(nmdl: &FileStruct) -> Vec<DrawData> {
+    let mut materials: HashMap<u32,(MaterialHandle,ShaderHandle)> = HashMap::new();
+    nmdl.meshes.iter().map(|mesh| {
+        let material = materials
+            .entry(mesh.material_index)
+            .or_insert_with(|| {
+                upload_material(nmdl, mesh.material_index)
+                    .unwrap_or_else(|e| panic!("Could not load material {}: {}", mesh.material_index, e))
+            })
+            .clone();
+        upload_mesh(mesh, material)
+    }).collect()
+}
+
+fn cached(path: &str) -> Option<(BaseDrawable, Option<Rc<Vec<MeshStruct>>>)> {
+    DRAWABLE_MANAGER.with(|dm| dm.borrow_mut().cached_model(Path::new(path)))
+}
+
+// Uploads the file and caches its handles by path, later imports of the path share them.
+fn upload_cached(path: &str, nmdl: &FileStruct, meshes: Option<Rc<Vec<MeshStruct>>>) -> BaseDrawable {
+    let drawable = BaseDrawable { draw_data: upload(nmdl) };
+    DRAWABLE_MANAGER.with(|dm| dm.borrow_mut().cache_model(Path::new(path), &drawable, meshes));
+    drawable
 }
+
 pub fn import(path: &str) -> BaseDrawable {
-    import_w_mesh(path).0
+    match cached(path) {
+        Some((drawable, _)) => drawable,
+        None => upload_cached(path, &load(path), None),
+    }
 }
 pub fn import_w_collider(path: &str,scale:f32) -> (BaseDrawable,ColliderComponent) {
-    let (draw_data,meshes) = import_w_mesh(path);
-    (draw_data,ColliderComponent::hull_from_meshvec(&meshes,scale))
+    let (drawable, meshes) = match cached(path) {
+        Some((drawable, Some(meshes))) => (drawable, meshes),
+        Some((drawable, None)) => {
+            // only the meshes are read again, the GPU resources are shared
+            let meshes = Rc::new(load(path).meshes);
+            DRAWABLE_MANAGER.with(|dm| dm.borrow_mut().cache_meshes(Path::new(path), meshes.clone()));
+            (drawable, meshes)
+        }
+        None => {
+            let nmdl = load(path);
+            let drawable = upload_cached(path, &nmdl, None);
+            let meshes = Rc::new(nmdl.meshes);
+            DRAWABLE_MANAGER.with(|dm| dm.borrow_mut().cache_meshes(Path::new(path), meshes.clone()));
+            (drawable, meshes)
+        }
+    };
+    (drawable,ColliderComponent::hull_from_meshvec(&meshes,scale))
+}
+
+// Builds a GameObject per node of the model, named after the node and carrying its transform and meshes.
+// Models with skins or animations get an AnimatorComponent on the root object.
+pub fn import_hierarchy(path: &str, parent: Option<GameObject>) -> GameObject {
+    let nmdl = load(path);
+    let draw_data = match cached(path) {
+        Some((drawable, _)) => drawable.draw_data,
+        None => upload_cached(path, &nmdl, None).draw_data,
+    };
+    let mut root = build_node(&nmdl.root, &draw_data, parent);
+    let mut skins = vec![];
+    collect_skins(&nmdl.root, &draw_data, &mut skins);
+    if !skins.is_empty() || !nmdl.animations.is_empty() {
+        let mut animator = AnimatorComponent::new(nmdl.animations.iter().map(AnimationClip::from).collect());
+        for (node, skin) in skins {
+            animator.add_skin(&node, skin);
+        }
+        root.add_component(animator);
+    }
+    root
+}
+
+fn collect_skins(node: &NodeStruct, draw_data: &[DrawData], skins: &mut Vec<(String, Skin)>) {
+    for index in &node.meshes {
+        if let Some(skin) = &draw_data[*index as usize].skin {
+            skins.push((node.name.clone(), skin.clone()));
+        }
+    }
+    for child in &node.children {
+        collect_skins(child, draw_data, skins);
+    }
+}
+
+fn build_node(node: &NodeStruct, draw_data: &[DrawData], parent: Option<GameObject>) -> GameObject {
+    let transform = Transform::from(Mat4::from_cols_array(&node.transform));
+    let mut object = GameObject::new_w_transform(parent, transform).with_name(&node.name);
+    if !node.meshes.is_empty() {
+        let drawable = BaseDrawable {
+            draw_data: node.meshes.iter().map(|index| draw_data[*index as usize].clone()).collect(),
+        };
+        object.add_component(DrawableComponent::new(Box::new(drawable)));
+    }
+    for child in &node.children {
+        build_node(child, draw_data, Some(object.clone()));
+    }
+    object
 }
 #[macro_export]
 macro_rules! nmdl_import {
@@ -94,4 +226,16 @@ macro_rules! nmdl_import_w_collider {
         let nmdl_location = convert_name(&location,&PathBuf::new(),&PathBuf::new());
         import_w_collider(nmdl_location.to_str().unwrap(),$scale)
     }
+}}
+
+#[macro_export]
+macro_rules! nmdl_import_hierarchy {
+    ($mdl_path:expr,$parent:expr) =>{{
+        use glengine::build_utils::models::convert_name;
+        use glengine::engine::drawable::importer::nmdl::import_hierarchy;
+        use std::path::PathBuf;
+        let location = std::env::current_exe().unwrap().parent().unwrap().join("models").join($mdl_path);
+        let nmdl_location = convert_name(&location,&PathBuf::new(),&PathBuf::new());
+        import_hierarchy(nmdl_location.to_str().unwrap(),$parent)
+    }
 }}
\ No newline at end of file
@@ -0,0 +1,91 @@
+use gl::types::GLuint;
+use glam::{Mat4, Vec4};
+
+// must match the instanced vertex shaders, the matrix takes four locations
+pub const INSTANCE_MODEL_LOCATION: u32 = 7;
+pub const INSTANCE_COLOR_LOCATION: u32 = 11;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Instance {
+    model_mat: [f32; 16],
+    color: [f32; 4],
+}
+
+impl Instance {
+    pub fn new(model_mat: &Mat4, color: &Vec4) -> Self {
+        Self {
+            model_mat: model_mat.to_cols_array(),
+            color: color.to_array(),
+        }
+    }
+}
+
+// Per instance attributes of instanced draws, refilled before each of them.
+#[derive(Default)]
+pub struct InstanceBuffer {
+    vbo: GLuint,
+}
+
+impl InstanceBuffer {
+    // Uploads the instances and points the instance attributes of the bound vertex array at them.
+    pub fn bind(&mut self, instances: &[Instance]) {
+        unsafe {
+            if self.vbo == 0 {
+                gl::GenBuffers(1, &mut self.vbo);
+            }
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                std::mem::size_of_val(instances) as isize,
+                instances.as_ptr() as *const _,
+                gl::STREAM_DRAW,
+            );
+            let stride = std::mem::size_of::<Instance>() as i32;
+            for column in 0..4 {
+                let location = INSTANCE_MODEL_LOCATION + column;
+                gl::VertexAttribPointer(
+                    location,
+                    4,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    stride,
+                    (column as usize * std::mem::size_of::<Vec4>()) as *const _,
+                );
+                gl::VertexAttribDivisor(location, 1);
+                gl::EnableVertexAttribArray(location);
+            }
+            gl::VertexAttribPointer(
+                INSTANCE_COLOR_LOCATION,
+                4,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                std::mem::size_of::<[f32; 16]>() as *const _,
+            );
+            gl::VertexAttribDivisor(INSTANCE_COLOR_LOCATION, 1);
+            gl::EnableVertexAttribArray(INSTANCE_COLOR_LOCATION);
+        }
+    }
+
+    // the attributes are disabled again so the vertex array draws normally afterwards
+    pub fn unbind() {
+        unsafe {
+            for location in INSTANCE_MODEL_LOCATION..=INSTANCE_COLOR_LOCATION {
+                gl::DisableVertexAttribArray(location);
+                gl::VertexAttribDivisor(location, 0);
+            }
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+    }
+}
+
+impl Drop for InstanceBuffer {
+    fn drop(&mut self) {
+        if self.vbo != 0 {
+            unsafe {
+                gl::DeleteBuffers(1, &self.vbo);
+            }
+        }
+    }
+}
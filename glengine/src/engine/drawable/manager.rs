@@ -1,13 +1,65 @@
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use glam::Vec4;
+use glengine_mdl::models::MeshStruct;
 use once_cell::unsync::Lazy;
-use crate::engine::drawable::material::manager::MaterialManager;
-use crate::engine::drawable::mesh::manager::MeshManager;
-use crate::engine::drawable::shader::manager::ShaderManager;
+use crate::engine::drawable::base::BaseDrawable;
+use crate::engine::drawable::material::manager::{MaterialManager, MaterialWeakHandle};
+use crate::engine::drawable::mesh::manager::{MeshManager, MeshWeakHandle};
+use crate::engine::drawable::shader::manager::{ShaderHandle, ShaderManager};
+use crate::engine::drawable::skin::{Bone, Skin};
+use crate::engine::drawable::DrawData;
+
+struct CachedDrawData {
+    mesh_handle: MeshWeakHandle,
+    shader_handle: ShaderHandle,
+    material_handle: Option<MaterialWeakHandle>,
+    bones: Option<Rc<Vec<Bone>>>,
+}
+
+// Weak handles of a model imported from a file, every drawable using it has to be dropped for
+// it to be evicted.
+struct CachedModel {
+    draw_data: Vec<CachedDrawData>,
+    // the file meshes, kept once a collider was built from them
+    meshes: Option<Rc<Vec<MeshStruct>>>,
+}
+
+impl CachedModel {
+    fn upgrade(&self) -> Option<BaseDrawable> {
+        let draw_data = self.draw_data.iter().map(|cached| {
+            let material_handle = match &cached.material_handle {
+                Some(material) => Some(material.upgrade()?),
+                None => None,
+            };
+            Some(DrawData {
+                mesh_handle: cached.mesh_handle.upgrade()?,
+                shader_handle: cached.shader_handle.clone(),
+                material_handle,
+                casts_shadows: true,
+                receives_shadows: true,
+                // every import is posed on its own
+                skin: cached.bones.clone().map(Skin::shared),
+                lod: 0,
+                color: Vec4::ONE,
+            })
+        }).collect::<Option<Vec<DrawData>>>()?;
+        Some(BaseDrawable { draw_data })
+    }
+}
+
+// the same file reached through different relative paths shares its entry
+fn cache_key(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
 
 pub struct DrawableManager {
     pub mesh: MeshManager,
     pub material: MaterialManager,
     pub shader: ShaderManager,
+    models: HashMap<PathBuf, CachedModel>,
 }
 
 impl DrawableManager {
@@ -15,6 +67,38 @@ impl DrawableManager {
         self.mesh.clean();
         self.material.clean();
         self.shader.clean();
+        self.models.retain(|_, model| model.upgrade().is_some());
+    }
+
+    // Drawable sharing the GPU resources of an earlier import of the file, with the file meshes
+    // if they were cached.
+    pub fn cached_model(&mut self, path: &Path) -> Option<(BaseDrawable, Option<Rc<Vec<MeshStruct>>>)> {
+        let key = cache_key(path);
+        match self.models.get(&key).map(|model| (model.upgrade(), model.meshes.clone())) {
+            Some((Some(drawable), meshes)) => Some((drawable, meshes)),
+            Some((None, _)) => {
+                self.models.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    // Drawables with fewer DrawData than the file meshes, like the nodes of a hierarchy, must not be cached.
+    pub fn cache_model(&mut self, path: &Path, drawable: &BaseDrawable, meshes: Option<Rc<Vec<MeshStruct>>>) {
+        let draw_data = drawable.draw_data.iter().map(|draw_data| CachedDrawData {
+            mesh_handle: draw_data.mesh_handle.downgrade(),
+            shader_handle: draw_data.shader_handle.clone(),
+            material_handle: draw_data.material_handle.as_ref().map(|material| material.downgrade()),
+            bones: draw_data.skin.as_ref().map(|skin| skin.bones.clone()),
+        }).collect();
+        self.models.insert(cache_key(path), CachedModel { draw_data, meshes });
+    }
+
+    pub fn cache_meshes(&mut self, path: &Path, meshes: Rc<Vec<MeshStruct>>) {
+        if let Some(model) = self.models.get_mut(&cache_key(path)) {
+            model.meshes = Some(meshes);
+        }
     }
 }
 
@@ -24,6 +108,7 @@ impl Default for DrawableManager {
             mesh: MeshManager::default(),
             material: MaterialManager::default(),
             shader: ShaderManager::default(),
+            models: HashMap::new(),
         }
     }
 }
@@ -1,5 +1,6 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::rc::{Rc, Weak};
 use once_cell::unsync::Lazy;
 use crate::engine::drawable::material::Material;
@@ -9,8 +10,54 @@ pub struct MaterialHandle {
     rc: Rc<()>,
     handle: usize,
 }
+impl MaterialHandle {
+    // true when no other handle to the material exists
+    pub fn is_unique(&self) -> bool {
+        Rc::strong_count(&self.rc) == 1
+    }
+
+    pub fn downgrade(&self) -> MaterialWeakHandle {
+        MaterialWeakHandle {
+            handle: Rc::downgrade(&self.rc),
+            index: self.handle,
+        }
+    }
+
+    // index of the material in the manager
+    pub(crate) fn id(&self) -> usize {
+        self.handle
+    }
+}
+
+// handles are equal when they refer to the same material
+impl PartialEq for MaterialHandle {
+    fn eq(&self, other: &Self) -> bool {
+        self.handle == other.handle
+    }
+}
+
+impl Eq for MaterialHandle {}
+
+impl Hash for MaterialHandle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.handle.hash(state);
+    }
+}
+
+// Does not keep the material alive, upgrading fails once every MaterialHandle is dropped.
+#[derive(Clone)]
 pub struct MaterialWeakHandle {
     handle: Weak<()>,
+    index: usize,
+}
+
+impl MaterialWeakHandle {
+    pub fn upgrade(&self) -> Option<MaterialHandle> {
+        Some(MaterialHandle {
+            rc: self.handle.upgrade()?,
+            handle: self.index,
+        })
+    }
 }
 
 pub struct MaterialManager {
@@ -33,9 +80,7 @@ impl MaterialManager {
             rc: Rc::new(()),
             handle: index,
         };
-        let weak_handle = MaterialWeakHandle {
-            handle: Rc::downgrade(&handle.rc),
-        };
+        let weak_handle = handle.downgrade();
         self.materials.insert(index, (material,weak_handle));
         self.index += 1;
         while let Some((_,weak)) = self.materials.get(&self.index){
@@ -50,6 +95,12 @@ impl MaterialManager {
         handle
     }
 
+    // Swaps the material behind the handle, returning the previous one.
+    pub fn replace(&mut self, handle:&MaterialHandle, material: Material) -> Option<Material> {
+        let (old,_) = self.materials.get_mut(&handle.handle)?;
+        Some(std::mem::replace(old, material))
+    }
+
     pub fn clean(&mut self) {
         self.materials.retain(|_,(_,weak)|weak.handle.upgrade().is_some());
     }
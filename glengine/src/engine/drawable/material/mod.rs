@@ -1,12 +1,17 @@
 pub mod manager;
+pub mod render_state;
 use std::collections::HashMap;
 use gl;
 use gl::types::{GLenum, GLuint};
-use glam::{vec3, Vec3};
+use glam::{vec3, vec4, Vec3, Vec4};
+use glengine_mdl::models::TextureStruct;
 use russimp::material::{PropertyTypeInfo, TextureType};
 
 use crate::engine::drawable::importer::img::Image;
+use crate::engine::drawable::material::render_state::{BlendMode, RenderState};
+use crate::engine::drawable::shader::manager::IncludedShaderHandle;
 use crate::engine::drawable::shader::Shader;
+use crate::result::{EngineRenderError, EngineRenderResult};
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
@@ -15,6 +20,19 @@ pub struct MaterialData {
     pub diffuse: Option<Vec3>,
     pub specular: Option<Vec3>,
     pub shininess: Option<f32>,
+    pub base_color: Option<Vec4>,
+    pub metallic: Option<f32>,
+    pub roughness: Option<f32>,
+    pub ao: Option<f32>,
+    pub emissive: Option<Vec3>,
+    pub render_state: RenderState,
+}
+
+impl MaterialData {
+    // a material with any metallic/roughness parameter is drawn with the PBR shader
+    pub fn is_pbr(&self) -> bool {
+        self.base_color.is_some() || self.metallic.is_some() || self.roughness.is_some()
+    }
 }
 
 impl Default for MaterialData {
@@ -24,10 +42,34 @@ impl Default for MaterialData {
             diffuse: None,
             specular: None,
             shininess: None,
+            base_color: None,
+            metallic: None,
+            roughness: None,
+            ao: None,
+            emissive: None,
+            render_state: RenderState::default(),
         }
     }
 }
 
+// Texture slots sampled by the included shaders. A slot without a texture still gets its own
+// unit, otherwise its sampler would share unit 0 with samplers of other types.
+pub const MATERIAL_TEXTURES: [&str; 7] = [
+    "diffuse_texture",
+    "specular_texture",
+    "base_color_texture",
+    "metallic_roughness_texture",
+    "ao_texture",
+    "emissive_texture",
+    "normal_texture",
+];
+
+fn texture_from_file(path: &str) -> EngineRenderResult<Texture> {
+    Image::load(path)
+        .map(Texture::from)
+        .map_err(|e| EngineRenderError::TextureLoadError(format!("{}: {}", path, e)))
+}
+
 pub struct Material {
     pub data: MaterialData,
     pub textures: HashMap<&'static str, Texture>,
@@ -35,8 +77,18 @@ pub struct Material {
 
 impl From<russimp::material::Material> for Material {
     fn from(material: russimp::material::Material) -> Self {
+        let load_texture = |path: &str| texture_from_file(path).unwrap_or_else(|e| panic!("{}", e));
         let mut data = MaterialData::default();
         let mut textures = HashMap::new();
+        // glTF defaults to 0.5 when the mask has no cutoff
+        let alpha_cutoff = material
+            .properties
+            .iter()
+            .find_map(|prop| match (&*prop.key, &prop.data) {
+                ("$mat.gltf.alphaCutoff", PropertyTypeInfo::FloatArray(val)) => val.first().copied(),
+                _ => None,
+            })
+            .unwrap_or(0.5);
 
         material
             .properties
@@ -59,28 +111,77 @@ impl From<russimp::material::Material> for Material {
                     data.shininess = Some((val / 1000.0)*128.0);
                     // println!("Shininess: {:?}", data.shininess);
                 }
+                ("$clr.base", &PropertyTypeInfo::FloatArray(ref color)) => {
+                    data.base_color = Some(vec4(color[0], color[1], color[2], *color.get(3).unwrap_or(&1.0)));
+                }
+                ("$mat.metallicFactor", &PropertyTypeInfo::FloatArray(ref val)) => {
+                    data.metallic = Some(val[0]);
+                }
+                ("$mat.roughnessFactor", &PropertyTypeInfo::FloatArray(ref val)) => {
+                    data.roughness = Some(val[0]);
+                }
+                ("$clr.emissive", &PropertyTypeInfo::FloatArray(ref color)) => {
+                    data.emissive = Some(vec3(color[0], color[1], color[2]));
+                }
+                ("$mat.opacity", &PropertyTypeInfo::FloatArray(ref val)) if val[0] < 1.0 => {
+                    data.render_state.blend = BlendMode::AlphaBlend;
+                }
+                ("$mat.gltf.alphaMode", &PropertyTypeInfo::String(ref mode)) => {
+                    data.render_state.blend = match mode.as_str() {
+                        "MASK" => BlendMode::AlphaTest(alpha_cutoff),
+                        "BLEND" => BlendMode::AlphaBlend,
+                        _ => BlendMode::Opaque,
+                    };
+                }
                 // (a,b) => {
                 //     println!("Unknown property: {:?} {:?}", a,b);
                 // }
                 _ => {}
             });
         if let Some(diffuse_texture) = material.textures.get(&TextureType::Diffuse) {
-            let image = Image::load(&diffuse_texture.borrow().filename).expect(
-                format!(
-                    "Failed to load texture: {}",
-                    &diffuse_texture.borrow().filename
-                )
-                .as_str(),
-            );
-            textures.insert("diffuse_texture", image.into());
+            textures.insert("diffuse_texture", load_texture(&diffuse_texture.borrow().filename));
         };
+        if let Some(specular_texture) = material.textures.get(&TextureType::Specular) {
+            textures.insert("specular_texture", load_texture(&specular_texture.borrow().filename));
+        }
+        let pbr_textures = [
+            ("base_color_texture", &[TextureType::BaseColor][..]),
+            ("metallic_roughness_texture", &[TextureType::Metalness, TextureType::Roughness, TextureType::Unknown][..]),
+            ("ao_texture", &[TextureType::AmbientOcclusion, TextureType::LightMap][..]),
+            ("emissive_texture", &[TextureType::EmissionColor, TextureType::Emissive][..]),
+            ("normal_texture", &[TextureType::Normals][..]),
+        ];
+        for (name, texture_types) in pbr_textures {
+            if let Some(texture) = texture_types.iter().find_map(|texture_type| material.textures.get(texture_type)) {
+                textures.insert(name, load_texture(&texture.borrow().filename));
+            }
+        }
+        if !data.is_pbr() && textures.contains_key("base_color_texture") {
+            data.base_color = Some(Vec4::ONE);
+        }
 
         Self { data, textures }
     }
 }
 
-impl From<glengine_mdl::models::MaterialStruct> for Material {
-    fn from(material: glengine_mdl::models::MaterialStruct) -> Self {
+impl Material {
+    // Textures come from the ones embedded in the NMDL file, the source path is only read when missing.
+    // Embedded textures that fail to decode are left out, their key is not a path to read. Fails
+    // when a texture file can't be read.
+    pub fn from_nmdl(
+        material: glengine_mdl::models::MaterialStruct,
+        embedded: &HashMap<String, TextureStruct>,
+    ) -> EngineRenderResult<Self> {
+        let load_texture = |path: &str| -> EngineRenderResult<Option<Texture>> {
+            match embedded.get(path).map(Image::from_embedded) {
+                Some(Ok(image)) => Ok(Some(image.into())),
+                Some(Err(e)) => {
+                    eprintln!("Could not decode embedded texture {}: {}", path, e);
+                    Ok(None)
+                }
+                None => texture_from_file(path).map(Some),
+            }
+        };
         let mut data = MaterialData::default();
         let mut textures = HashMap::new();
 
@@ -97,38 +198,84 @@ impl From<glengine_mdl::models::MaterialStruct> for Material {
             data.shininess = Some(shininess);
         }
 
-        if let Some(diffuse_texture) = material.texture.diffuse {
-            let image = Image::load(&diffuse_texture).expect(
-                format!(
-                    "Failed to load texture: {}",
-                    &diffuse_texture
-                )
-                .as_str(),
-            );
-            textures.insert("diffuse_texture", image.into());
+        if let Some(diffuse_texture) = material.texture.diffuse.as_deref().map(load_texture).transpose()?.flatten() {
+            textures.insert("diffuse_texture", diffuse_texture);
         };
+        if let Some(specular_texture) = material.texture.specular.as_deref().map(load_texture).transpose()?.flatten() {
+            textures.insert("specular_texture", specular_texture);
+        }
+        if let Some(pbr) = material.pbr {
+            data.base_color = Some(pbr.base_color.map_or(Vec4::ONE, |c| vec4(c.0, c.1, c.2, c.3)));
+            data.metallic = pbr.metallic;
+            data.roughness = pbr.roughness;
+            data.emissive = pbr.emissive.map(|c| vec3(c.0, c.1, c.2));
+            // NMDL keeps no alpha mode, a translucent base color is blended
+            if pbr.base_color.is_some_and(|c| c.3 < 1.0) {
+                data.render_state.blend = BlendMode::AlphaBlend;
+            }
+        }
+        let pbr_textures = [
+            ("base_color_texture", material.texture.base_color),
+            ("metallic_roughness_texture", material.texture.metallic_roughness),
+            ("ao_texture", material.texture.ambient_occlusion),
+            ("emissive_texture", material.texture.emissive),
+            ("normal_texture", material.texture.normal),
+        ];
+        for (name, path) in pbr_textures {
+            if let Some(texture) = path.as_deref().map(load_texture).transpose()?.flatten() {
+                textures.insert(name, texture);
+            }
+        }
 
-        Self { data, textures }
+        Ok(Self { data, textures })
     }
 }
 impl Material {
+    // the included shader an imported material is drawn with
+    pub fn included_shader(&self) -> IncludedShaderHandle {
+        if self.data.is_pbr() {
+            IncludedShaderHandle::Pbr
+        } else if self.data.ambient.is_some() {
+            IncludedShaderHandle::LitColor
+        } else {
+            IncludedShaderHandle::Basic
+        }
+    }
+
+    // The same material is drawn with several shaders, uniforms a shader lacks are skipped.
     pub fn set_uniforms(&self, shader: &Shader) {
         if let Some(ambient) = self.data.ambient {
-            shader.set_vec3("material.ambient", &ambient);
+            shader.set_optional("material.ambient", &ambient);
         }
         if let Some(diffuse) = self.data.diffuse {
-            shader.set_vec3("material.diffuse", &diffuse);
+            shader.set_optional("material.diffuse", &diffuse);
         }
         if let Some(specular) = self.data.specular {
-            shader.set_vec3("material.specular", &specular);
+            shader.set_optional("material.specular", &specular);
         }
         if let Some(shininess) = self.data.shininess {
-            shader.set_float("material.shininess", shininess);
+            shader.set_optional("material.shininess", &shininess);
+        }
+        shader.set_optional("alpha_cutoff", &self.data.render_state.alpha_cutoff());
+        if self.data.is_pbr() {
+            let base_color = self.data.base_color.unwrap_or(Vec4::ONE);
+            shader.set_optional("material.base_color", &base_color);
+            shader.set_optional("material.metallic", &self.data.metallic.unwrap_or(1.0));
+            shader.set_optional("material.roughness", &self.data.roughness.unwrap_or(1.0));
+            shader.set_optional("material.ao", &self.data.ao.unwrap_or(1.0));
+            shader.set_optional("material.emissive", &self.data.emissive.unwrap_or(Vec3::ZERO));
         }
 
         for (name, texture) in self.textures.iter() {
             shader.add_texture(name, texture.id(), texture.texture_type());
         }
+        for name in MATERIAL_TEXTURES {
+            let present = self.textures.contains_key(name);
+            shader.set_optional(&format!("has_{}", name), &if present { 1.0f32 } else { 0.0 });
+            if !present {
+                shader.add_texture(name, 0, gl::TEXTURE_2D);
+            }
+        }
     }
 }
 
@@ -0,0 +1,100 @@
+use gl;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BlendMode {
+    #[default]
+    Opaque,
+    // fragments with an alpha under the cutoff are discarded, the rest is drawn opaque
+    AlphaTest(f32),
+    AlphaBlend,
+    Additive,
+    // the color is already multiplied by the alpha
+    Premultiplied,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CullMode {
+    #[default]
+    Back,
+    Front,
+    None,
+}
+
+// Fixed function state a material is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RenderState {
+    pub blend: BlendMode,
+    // None writes the depth of the opaque modes only
+    pub depth_write: Option<bool>,
+    pub cull: CullMode,
+}
+
+impl RenderState {
+    pub fn with_blend(mut self, blend: BlendMode) -> Self {
+        self.blend = blend;
+        self
+    }
+
+    pub fn with_depth_write(mut self, depth_write: bool) -> Self {
+        self.depth_write = Some(depth_write);
+        self
+    }
+
+    pub fn with_cull(mut self, cull: CullMode) -> Self {
+        self.cull = cull;
+        self
+    }
+
+    // transparent materials are drawn after the opaque ones, back to front
+    pub fn is_transparent(&self) -> bool {
+        matches!(self.blend, BlendMode::AlphaBlend | BlendMode::Additive | BlendMode::Premultiplied)
+    }
+
+    pub fn writes_depth(&self) -> bool {
+        self.depth_write.unwrap_or(!self.is_transparent())
+    }
+
+    pub fn alpha_cutoff(&self) -> f32 {
+        match self.blend {
+            BlendMode::AlphaTest(cutoff) => cutoff,
+            _ => 0.0,
+        }
+    }
+
+    pub fn apply(&self) {
+        unsafe {
+            match self.blend {
+                BlendMode::Opaque | BlendMode::AlphaTest(_) => gl::Disable(gl::BLEND),
+                BlendMode::AlphaBlend => {
+                    gl::Enable(gl::BLEND);
+                    gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+                }
+                BlendMode::Additive => {
+                    gl::Enable(gl::BLEND);
+                    gl::BlendFunc(gl::SRC_ALPHA, gl::ONE);
+                }
+                BlendMode::Premultiplied => {
+                    gl::Enable(gl::BLEND);
+                    gl::BlendFunc(gl::ONE, gl::ONE_MINUS_SRC_ALPHA);
+                }
+            }
+            gl::DepthMask(if self.writes_depth() { gl::TRUE } else { gl::FALSE });
+            match self.cull {
+                CullMode::Back => {
+                    gl::Enable(gl::CULL_FACE);
+                    gl::CullFace(gl::BACK);
+                }
+                CullMode::Front => {
+                    gl::Enable(gl::CULL_FACE);
+                    gl::CullFace(gl::FRONT);
+                }
+                CullMode::None => gl::Disable(gl::CULL_FACE),
+            }
+        }
+    }
+
+    // back to the opaque state set by Engine::gl_init
+    pub fn reset() {
+        RenderState::default().apply();
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::rc::{Rc, Weak};
 use crate::engine::drawable::mesh::Mesh;
 
@@ -9,16 +10,51 @@ pub struct MeshHandle{
 }
 
 impl MeshHandle{
-    fn downgrade(&self)->MeshWeakHandle{
+    // true when no other handle to the mesh exists
+    pub fn is_unique(&self)->bool{
+        Rc::strong_count(&self.rc) == 1
+    }
+    pub fn downgrade(&self)->MeshWeakHandle{
         MeshWeakHandle{
-            weak:Rc::downgrade(&self.rc)
+            weak:Rc::downgrade(&self.rc),
+            handle:self.handle
         }
     }
+    // index of the mesh in the manager
+    pub(crate) fn id(&self)->usize{
+        self.handle
+    }
+}
+
+// handles are equal when they refer to the same mesh
+impl PartialEq for MeshHandle{
+    fn eq(&self, other: &Self) -> bool{
+        self.handle == other.handle
+    }
 }
 
+impl Eq for MeshHandle{}
+
+impl Hash for MeshHandle{
+    fn hash<H: Hasher>(&self, state: &mut H){
+        self.handle.hash(state);
+    }
+}
+
+// Does not keep the mesh alive, upgrading fails once every MeshHandle is dropped.
 #[derive(Clone)]
-struct MeshWeakHandle{
-    weak: Weak<()>
+pub struct MeshWeakHandle{
+    weak: Weak<()>,
+    handle:usize
+}
+
+impl MeshWeakHandle{
+    pub fn upgrade(&self)->Option<MeshHandle>{
+        Some(MeshHandle{
+            rc:self.weak.upgrade()?,
+            handle:self.handle
+        })
+    }
 }
 
 pub struct MeshManager {
@@ -57,6 +93,12 @@ impl MeshManager {
         mesh_handle
     }
 
+    // Swaps the mesh behind the handle, returning the previous one.
+    pub fn replace(&mut self, handle: &MeshHandle, mesh: Box<dyn Mesh>) -> Option<Box<dyn Mesh>>{
+        let (old,_) = self.meshes.get_mut(&handle.handle)?;
+        Some(std::mem::replace(old, mesh))
+    }
+
     pub fn clean(&mut self){
         self.meshes.retain(|_,(_,weak)|weak.weak.upgrade().is_some());
     }
@@ -2,11 +2,29 @@ pub mod cube;
 pub mod screenquad;
 pub mod manager;
 
+use crate::engine::drawable::bounds::Aabb;
+
 pub trait Mesh: Send + Sync {
     fn get(&self) -> &MeshData;
     fn get_mut(&mut self) -> &mut MeshData;
     fn bind(&self);
     fn draw(&self);
+    fn draw_lod(&self, _lod: usize) {
+        self.draw();
+    }
+    // draws the level of detail once per instance, with the instance attributes already bound
+    fn draw_lod_instanced(&self, lod: usize, instances: usize) {
+        let (offset, count) = self.get().lod_range(lod);
+        unsafe {
+            gl::DrawElementsInstanced(
+                gl::TRIANGLES,
+                count as i32,
+                gl::UNSIGNED_INT,
+                (offset * std::mem::size_of::<u32>()) as *const _,
+                instances as i32,
+            );
+        }
+    }
 }
 
 pub struct BaseMesh {
@@ -33,6 +51,17 @@ impl Mesh for BaseMesh {
             );
         }
     }
+    fn draw_lod(&self, lod: usize) {
+        let (offset, count) = self.mesh_data.lod_range(lod);
+        unsafe {
+            gl::DrawElements(
+                gl::TRIANGLES,
+                count as i32,
+                gl::UNSIGNED_INT,
+                (offset * std::mem::size_of::<u32>()) as *const _,
+            );
+        }
+    }
 }
 
 pub struct MeshData {
@@ -40,8 +69,15 @@ pub struct MeshData {
     vbo_vertices: u32,
     vbo_normals: Option<u32>,
     vbo_texcoords: Option<u32>,
+    vbo_tangents: Option<u32>,
+    vbo_bitangents: Option<u32>,
+    vbo_joints: Option<u32>,
+    vbo_weights: Option<u32>,
     ebo: Option<u32>,
     indices_count: u32,
+    // offset and count in the index buffer of every level of detail after the first
+    lods: Vec<(usize, u32)>,
+    bounds: Option<Aabb>,
 }
 
 impl MeshData {
@@ -75,8 +111,14 @@ impl MeshData {
             vbo_vertices,
             vbo_normals: None,
             vbo_texcoords: None,
+            vbo_tangents: None,
+            vbo_bitangents: None,
+            vbo_joints: None,
+            vbo_weights: None,
             ebo: None,
             indices_count: vertices.len() as u32 / 3,
+            lods: Vec::new(),
+            bounds: Aabb::from_vertices(vertices),
         }
     }
     fn bind(&self) {
@@ -125,6 +167,132 @@ impl MeshData {
         self
     }
 
+    // Tangents and bitangents for normal mapping, at attribute locations 3 and 4.
+    pub fn with_tangents(mut self, tangents: &[f32], bitangents: &[f32]) -> Self {
+        self.bind();
+        unsafe {
+            let mut vbo = [0; 2];
+            gl::GenBuffers(2, vbo.as_mut_ptr());
+            for (location, (buffer, data)) in [(3, (vbo[0], tangents)), (4, (vbo[1], bitangents))] {
+                gl::BindBuffer(gl::ARRAY_BUFFER, buffer);
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    (data.len() * std::mem::size_of::<f32>()) as isize,
+                    data.as_ptr() as *const _,
+                    gl::STATIC_DRAW,
+                );
+                gl::VertexAttribPointer(location, 3, gl::FLOAT, gl::FALSE, 0, std::ptr::null());
+                gl::EnableVertexAttribArray(location);
+            }
+            self.vbo_tangents = Some(vbo[0]);
+            self.vbo_bitangents = Some(vbo[1]);
+        }
+        Self::unbind();
+        self
+    }
+
+    // Four bone indices and weights per vertex, at attribute locations 5 and 6.
+    pub fn with_skin(mut self, joints: &[u32], weights: &[f32]) -> Self {
+        self.bind();
+        unsafe {
+            let mut vbo = [0; 2];
+            gl::GenBuffers(2, vbo.as_mut_ptr());
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo[0]);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (joints.len() * std::mem::size_of::<u32>()) as isize,
+                joints.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            gl::VertexAttribIPointer(5, 4, gl::UNSIGNED_INT, 0, std::ptr::null());
+            gl::EnableVertexAttribArray(5);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo[1]);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (weights.len() * std::mem::size_of::<f32>()) as isize,
+                weights.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            gl::VertexAttribPointer(6, 4, gl::FLOAT, gl::FALSE, 0, std::ptr::null());
+            gl::EnableVertexAttribArray(6);
+            self.vbo_joints = Some(vbo[0]);
+            self.vbo_weights = Some(vbo[1]);
+        }
+        Self::unbind();
+        self
+    }
+
+    fn packed_attribute<T>(&self, location: u32, size: i32, gl_type: gl::types::GLenum, normalized: bool, data: &[T]) -> u32 {
+        unsafe {
+            let mut vbo = 0;
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (data.len() * std::mem::size_of::<T>()) as isize,
+                data.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            let normalized = if normalized { gl::TRUE } else { gl::FALSE };
+            gl::VertexAttribPointer(location, size, gl_type, normalized, 0, std::ptr::null());
+            gl::EnableVertexAttribArray(location);
+            vbo
+        }
+    }
+
+    // Quantized attributes from NMDL, the shaders read them as floats.
+    // Normals are four signed normalized bytes per vertex.
+    pub fn with_packed_normals(mut self, normals: &[i8]) -> Self {
+        self.bind();
+        self.vbo_normals = Some(self.packed_attribute(1, 4, gl::BYTE, true, normals));
+        Self::unbind();
+        self
+    }
+
+    // Half float texture coordinates.
+    pub fn with_packed_texcoords(mut self, texcoords: &[u16]) -> Self {
+        self.bind();
+        self.vbo_texcoords = Some(self.packed_attribute(2, 2, gl::HALF_FLOAT, false, texcoords));
+        Self::unbind();
+        self
+    }
+
+    pub fn with_packed_tangents(mut self, tangents: &[i8], bitangents: &[i8]) -> Self {
+        self.bind();
+        self.vbo_tangents = Some(self.packed_attribute(3, 4, gl::BYTE, true, tangents));
+        self.vbo_bitangents = Some(self.packed_attribute(4, 4, gl::BYTE, true, bitangents));
+        Self::unbind();
+        self
+    }
+
+    // Index buffer holding the full mesh followed by its levels of detail.
+    pub fn with_lods(mut self, indices: &[u32], lods: &[&[u32]]) -> Self {
+        let mut all_indices = indices.to_vec();
+        self.lods = lods
+            .iter()
+            .map(|lod| {
+                let range = (all_indices.len(), lod.len() as u32);
+                all_indices.extend_from_slice(lod);
+                range
+            })
+            .collect();
+        self = self.with_indices(&all_indices);
+        self.indices_count = indices.len() as u32;
+        self
+    }
+
+    // Level 0 is the full mesh, levels past the last one give the coarsest.
+    pub fn lod_range(&self, lod: usize) -> (usize, u32) {
+        match lod.min(self.lods.len()) {
+            0 => (0, self.indices_count),
+            level => self.lods[level - 1],
+        }
+    }
+
+    pub fn lod_count(&self) -> usize {
+        self.lods.len() + 1
+    }
+
     pub fn with_indices(mut self, indices: &[u32]) -> Self {
         self.bind();
         self.ebo = unsafe {
@@ -143,6 +311,16 @@ impl MeshData {
         Self::unbind();
         self
     }
+    // replaces the bounds computed from the vertices, like the ones stored at import
+    pub fn with_bounds(mut self, bounds: Aabb) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.bounds
+    }
+
     pub fn get_indices_count(&self) -> u32 {
         self.indices_count
     }
@@ -164,6 +342,18 @@ impl Drop for MeshData {
             if let Some(vbo) = self.vbo_texcoords {
                 gl::DeleteBuffers(1, &vbo);
             };
+            if let Some(vbo) = self.vbo_tangents {
+                gl::DeleteBuffers(1, &vbo);
+            };
+            if let Some(vbo) = self.vbo_bitangents {
+                gl::DeleteBuffers(1, &vbo);
+            };
+            if let Some(vbo) = self.vbo_joints {
+                gl::DeleteBuffers(1, &vbo);
+            };
+            if let Some(vbo) = self.vbo_weights {
+                gl::DeleteBuffers(1, &vbo);
+            };
             if let Some(ebo) = self.ebo {
                 gl::DeleteBuffers(1, &ebo);
             };
@@ -1,12 +1,16 @@
 use std::collections::HashMap;
-use glam::Mat4;
+use glam::{Mat4, Vec4};
 use shader::Shader;
-use crate::engine::config::CONFIG;
+use crate::engine::config::{RenderPath, CONFIG};
+use crate::engine::drawable::bounds::Bounds;
+use crate::engine::drawable::queue::RENDER_QUEUE;
 use crate::engine::drawable::manager::DRAWABLE_MANAGER;
 use crate::engine::drawable::material::{Material, MaterialData, Texture, manager::MaterialHandle};
+use crate::engine::drawable::material::render_state::{BlendMode, RenderState};
 use crate::engine::drawable::mesh::{MeshData, manager::MeshHandle};
-use crate::engine::drawable::shader::manager::ShaderHandle;
-use crate::engine::fbo::Fbo;
+use crate::engine::drawable::shader::manager::{IncludedShaderHandle, ShaderHandle};
+use crate::engine::drawable::skin::Skin;
+use crate::engine::fbo::{Fbo, GBuffer};
 use crate::engine::scene::lights::Lights;
 
 pub mod base;
@@ -15,9 +19,21 @@ pub mod material;
 pub mod mesh;
 pub mod shader;
 pub mod manager;
+pub mod skin;
+pub mod asset_server;
+pub mod bounds;
+pub mod instancing;
+pub mod queue;
 
 pub trait Drawable{
     fn draw(&mut self, modelmat: &Mat4, viewmat: &Mat4, lights: Option<&Lights>);
+    fn draw_shadow(&mut self, _modelmat: &Mat4, _shader: &Shader) {}
+    fn draw_geometry(&mut self, _modelmat: &Mat4, _viewmat: &Mat4) {}
+    fn set_lod(&mut self, _lod: usize) {}
+    // model space bounds of what draw draws
+    fn bounds(&self) -> Bounds {
+        Bounds::Unbounded
+    }
 }
 
 
@@ -26,10 +42,49 @@ pub struct DrawData {
     pub mesh_handle: MeshHandle,
     pub shader_handle: ShaderHandle,
     pub material_handle: Option<MaterialHandle>,
+    pub casts_shadows: bool,
+    pub receives_shadows: bool,
+    pub skin: Option<Skin>,
+    // level of detail drawn, clamped to the levels the mesh has
+    pub lod: usize,
+    // multiplied into the material color, draws of the same mesh and material keep one batch
+    // with different colors
+    pub color: Vec4,
+}
+
+impl DrawData {
+    // With the deferred path the lit surfaces go through the G-buffer instead of the forward pass,
+    // transparent ones are blended over it.
+    pub fn is_deferred(&self) -> bool {
+        CONFIG.config().get_render_path() == RenderPath::Deferred
+            && matches!(self.shader_handle, ShaderHandle::Included(IncludedShaderHandle::LitColor))
+            && !self.render_state().is_transparent()
+    }
+
+    // state of the material, opaque without one
+    pub fn render_state(&self) -> RenderState {
+        let Some(material_id) = &self.material_handle else {
+            return RenderState::default();
+        };
+        DRAWABLE_MANAGER.with(|dm| {
+            dm.borrow()
+                .material
+                .get(material_id)
+                .map(|material| material.data.render_state)
+                .unwrap_or_default()
+        })
+    }
 }
 
 impl Drawable for DrawData {
     fn draw(&mut self, modelmat: &Mat4, viewmat: &Mat4, lights: Option<&Lights>) {
+        if self.is_deferred() {
+            return;
+        }
+        // drawn sorted with the rest of the pass when the scene collects a render queue
+        if RENDER_QUEUE.with(|queue| queue.borrow_mut().push(self, self.shader_handle.clone(), modelmat, viewmat)) {
+            return;
+        }
         let projection = *CONFIG.projection();
         DRAWABLE_MANAGER.with(|dm|
             {
@@ -37,22 +92,102 @@ impl Drawable for DrawData {
                 let shader = draw_manager.shader.get(&self.shader_handle).expect("Shader not found");
                 shader.use_program();
                 shader.reset_texture_count();
-                shader.set_mat4("view_mat", viewmat);
-                shader.set_mat4("model_mat", modelmat);
-                shader.set_mat4("projection_mat", &projection);
+                // also draws the screen quads, which have none of these
+                shader.set_optional("view_mat", viewmat);
+                shader.set_optional("model_mat", modelmat);
+                shader.set_optional("projection_mat", &projection);
+                shader.set_optional("color", &self.color);
+                shader.set_optional("instanced", &0.0f32);
                 if let Some(material_id) = &self.material_handle {
-                    draw_manager.material.get(&material_id).expect("Material not found").set_uniforms(&shader); 
+                    let material = draw_manager.material.get(&material_id).expect("Material not found");
+                    material.data.render_state.apply();
+                    material.set_uniforms(&shader);
                 }
         if let Some(lights) = lights {
             lights.bind(5);
+            lights.bind_shadow_maps(&shader);
+            lights.bind_environment(&shader);
+            shader.set_optional("receives_shadows", &if self.receives_shadows { 1.0f32 } else { 0.0 });
+        }
+        if let Some(skin) = &self.skin {
+            skin.bind();
         }
         draw_manager.mesh.get(&self.mesh_handle).expect("Mesh not found").bind();
-        draw_manager.mesh.get(&self.mesh_handle).expect("Mesh not found").draw();
+        draw_manager.mesh.get(&self.mesh_handle).expect("Mesh not found").draw_lod(self.lod);
             }
         );
         Lights::unbind(5);
+        Skin::unbind();
         MeshData::unbind();
         Shader::unbind();
+        RenderState::reset();
+    }
+
+    fn draw_shadow(&mut self, modelmat: &Mat4, shader: &Shader) {
+        if !self.casts_shadows {
+            return;
+        }
+        shader.set_mat4("model_mat", modelmat);
+        shader.set_float("skinned", if self.skin.is_some() { 1.0 } else { 0.0 });
+        if let Some(skin) = &self.skin {
+            skin.bind();
+        }
+        DRAWABLE_MANAGER.with(|dm| {
+            let draw_manager = dm.borrow();
+            let mesh = draw_manager.mesh.get(&self.mesh_handle).expect("Mesh not found");
+            mesh.bind();
+            mesh.draw_lod(self.lod);
+        });
+        MeshData::unbind();
+        Skin::unbind();
+    }
+
+    fn draw_geometry(&mut self, modelmat: &Mat4, viewmat: &Mat4) {
+        if !self.is_deferred() {
+            return;
+        }
+        let geometry_shader: ShaderHandle = IncludedShaderHandle::DeferredGeometry.into();
+        if RENDER_QUEUE.with(|queue| queue.borrow_mut().push(self, geometry_shader, modelmat, viewmat)) {
+            return;
+        }
+        let projection = *CONFIG.projection();
+        DRAWABLE_MANAGER.with(|dm| {
+            let draw_manager = dm.borrow();
+            let shader = draw_manager.shader.get_included(&IncludedShaderHandle::DeferredGeometry);
+            shader.use_program();
+            shader.reset_texture_count();
+            shader.set_mat4("view_mat", viewmat);
+            shader.set_mat4("model_mat", modelmat);
+            shader.set_mat4("projection_mat", &projection);
+            shader.set_vec4("color", &self.color);
+            shader.set_float("instanced", 0.0);
+            if let Some(material_id) = &self.material_handle {
+                let material = draw_manager.material.get(&material_id).expect("Material not found");
+                material.data.render_state.apply();
+                material.set_uniforms(&shader);
+            }
+            shader.set_float("receives_shadows", if self.receives_shadows { 1.0 } else { 0.0 });
+            let mesh = draw_manager.mesh.get(&self.mesh_handle).expect("Mesh not found");
+            mesh.bind();
+            mesh.draw_lod(self.lod);
+        });
+        MeshData::unbind();
+        Shader::unbind();
+        RenderState::reset();
+    }
+
+    fn set_lod(&mut self, lod: usize) {
+        self.lod = lod;
+    }
+
+    fn bounds(&self) -> Bounds {
+        if self.skin.is_some() {
+            return Bounds::Unbounded;
+        }
+        DRAWABLE_MANAGER.with(|dm| match dm.borrow().mesh.get(&self.mesh_handle) {
+            Some(mesh) => mesh.get().bounds().map_or(Bounds::Unbounded, Bounds::Finite),
+            None => Bounds::Empty,
+        })
     }
 }
 
@@ -75,5 +210,43 @@ pub fn screenquad(fbo: &Fbo) -> DrawData {
         mesh_handle: mesh_id,
         shader_handle,
         material_handle: Some(material_id),
+        casts_shadows: false,
+        receives_shadows: false,
+        skin: None,
+        lod: 0,
+        color: Vec4::ONE,
+    }
+}
+
+pub fn lighting_quad(gbuffer: &GBuffer) -> DrawData {
+    let mesh_id = mesh::screenquad::new();
+    let shader_handle = IncludedShaderHandle::DeferredLighting.into();
+    let mut textures = HashMap::new();
+    textures.insert("g_position", Texture::new(gbuffer.position_texture, gl::TEXTURE_2D));
+    textures.insert("g_normal", Texture::new(gbuffer.normal_texture, gl::TEXTURE_2D));
+    textures.insert("g_albedo", Texture::new(gbuffer.albedo_texture, gl::TEXTURE_2D));
+    textures.insert("g_specular", Texture::new(gbuffer.specular_texture, gl::TEXTURE_2D));
+    textures.insert("g_depth", Texture::new(gbuffer.depth_stencil_texture, gl::TEXTURE_2D));
+
+    // blended over the background by the coverage in its alpha
+    let material = Material {
+        data: MaterialData {
+            render_state: RenderState::default()
+                .with_blend(BlendMode::AlphaBlend)
+                .with_depth_write(true),
+            ..Default::default()
+        },
+        textures,
+    };
+    let material_id = DRAWABLE_MANAGER.with(|dm|dm.borrow_mut().material.add(material));
+    DrawData {
+        mesh_handle: mesh_id,
+        shader_handle,
+        material_handle: Some(material_id),
+        casts_shadows: false,
+        receives_shadows: false,
+        skin: None,
+        lod: 0,
+        color: Vec4::ONE,
     }
 }
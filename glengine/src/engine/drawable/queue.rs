@@ -0,0 +1,239 @@
+use std::cell::RefCell;
+use glam::{Mat4, Vec4};
+use once_cell::unsync::Lazy;
+
+use crate::engine::config::CONFIG;
+use crate::engine::drawable::instancing::{Instance, InstanceBuffer};
+use crate::engine::drawable::manager::DRAWABLE_MANAGER;
+use crate::engine::drawable::material::manager::MaterialHandle;
+use crate::engine::drawable::material::render_state::RenderState;
+use crate::engine::drawable::mesh::manager::MeshHandle;
+use crate::engine::drawable::mesh::MeshData;
+use crate::engine::drawable::shader::manager::ShaderHandle;
+use crate::engine::drawable::shader::Shader;
+use crate::engine::drawable::skin::Skin;
+use crate::engine::drawable::DrawData;
+use crate::engine::scene::lights::Lights;
+
+// One draw of a mesh, collected by the RenderQueue and drawn once the whole pass is known.
+#[derive(Clone)]
+pub struct DrawCommand {
+    pub mesh_handle: MeshHandle,
+    pub shader_handle: ShaderHandle,
+    pub material_handle: Option<MaterialHandle>,
+    pub skin: Option<Skin>,
+    pub lod: usize,
+    pub receives_shadows: bool,
+    pub color: Vec4,
+    pub model_mat: Mat4,
+    pub render_state: RenderState,
+    // order of the command in the pass, see sort_key
+    pub sort_key: u64,
+}
+
+impl DrawCommand {
+    // The DrawData drawn with the shader, keyed by the distance of its origin to the camera.
+    pub fn new(draw_data: &DrawData, shader_handle: ShaderHandle, modelmat: &Mat4, viewmat: &Mat4) -> Self {
+        let depth = (*viewmat * *modelmat).w_axis.truncate().length();
+        let render_state = draw_data.render_state();
+        let sort_key = sort_key(
+            &shader_handle,
+            draw_data.material_handle.as_ref(),
+            &draw_data.mesh_handle,
+            depth,
+            render_state.is_transparent(),
+        );
+        Self {
+            mesh_handle: draw_data.mesh_handle.clone(),
+            shader_handle,
+            material_handle: draw_data.material_handle.clone(),
+            skin: draw_data.skin.clone(),
+            lod: draw_data.lod,
+            receives_shadows: draw_data.receives_shadows,
+            color: draw_data.color,
+            model_mat: *modelmat,
+            render_state,
+            sort_key,
+        }
+    }
+
+    // skinned meshes and shaders without instance attributes are drawn one by one
+    fn instanced(&self) -> bool {
+        let supported = match &self.shader_handle {
+            ShaderHandle::Included(included) => included.supports_instancing(),
+            ShaderHandle::Custom(_) => false,
+        };
+        supported && self.skin.is_none()
+    }
+
+    // true when both are drawn by the same instanced draw
+    fn batches_with(&self, other: &DrawCommand) -> bool {
+        self.instanced()
+            && other.instanced()
+            && self.mesh_handle == other.mesh_handle
+            && self.shader_handle == other.shader_handle
+            && self.material_handle == other.material_handle
+            && self.lod == other.lod
+            && self.receives_shadows == other.receives_shadows
+    }
+}
+
+const DEPTH_MAX: u64 = 0xFFFF;
+
+// Opaque commands are grouped by shader, material and mesh, then drawn front to back. Transparent
+// ones form a second pass after them, drawn back to front. Ids are truncated to their field, two shaders sharing a
+// field are only sorted less well.
+//
+// opaque:      0 | shader 15 | material 16 | mesh 16 | depth 16
+// transparent: 1 | far depth 16 | shader 15 | material 16 | mesh 16
+pub fn sort_key(
+    shader_handle: &ShaderHandle,
+    material_handle: Option<&MaterialHandle>,
+    mesh_handle: &MeshHandle,
+    depth: f32,
+    transparent: bool,
+) -> u64 {
+    let (_, far_clip) = CONFIG.config().get_clip();
+    let depth = ((depth / far_clip).clamp(0.0, 1.0) * DEPTH_MAX as f32) as u64;
+    let shader = match shader_handle {
+        ShaderHandle::Included(included) => (*included as u64) << 1,
+        ShaderHandle::Custom(custom) => (custom.id() as u64) << 1 | 1,
+    } & 0x7FFF;
+    let material = material_handle.map_or(0, |material| material.id() as u64 + 1) & 0xFFFF;
+    let mesh = mesh_handle.id() as u64 & 0xFFFF;
+    if transparent {
+        1 << 63 | (DEPTH_MAX - depth) << 47 | shader << 32 | material << 16 | mesh
+    } else {
+        shader << 48 | material << 32 | mesh << 16 | depth
+    }
+}
+
+// what the last executed command left bound
+#[derive(Default)]
+struct DrawState {
+    shader: Option<ShaderHandle>,
+    material: Option<Option<MaterialHandle>>,
+    mesh: Option<MeshHandle>,
+    render_state: Option<RenderState>,
+    // texture units taken by the lights of the shader
+    texture_count: u32,
+}
+
+// Collects the DrawData drawn between begin and execute, sorts them by their key and draws them
+// binding the shaders, materials and meshes only when they change. Commands next to each other
+// with the same state are drawn with a single instanced draw.
+#[derive(Default)]
+pub struct RenderQueue {
+    commands: Vec<DrawCommand>,
+    collecting: bool,
+    instances: InstanceBuffer,
+    instance_data: Vec<Instance>,
+}
+
+impl RenderQueue {
+    pub fn begin(&mut self) {
+        self.collecting = true;
+    }
+
+    pub fn is_collecting(&self) -> bool {
+        self.collecting
+    }
+
+    // Returns false when nothing is collected and the command has to be drawn right away.
+    pub fn submit(&mut self, command: DrawCommand) -> bool {
+        if self.collecting {
+            self.commands.push(command);
+        }
+        self.collecting
+    }
+
+    pub fn push(&mut self, draw_data: &DrawData, shader_handle: ShaderHandle, modelmat: &Mat4, viewmat: &Mat4) -> bool {
+        self.collecting && self.submit(DrawCommand::new(draw_data, shader_handle, modelmat, viewmat))
+    }
+
+    // Draws the collected commands and stops collecting.
+    pub fn execute(&mut self, viewmat: &Mat4, lights: Option<&Lights>) {
+        self.collecting = false;
+        let mut commands = std::mem::take(&mut self.commands);
+        commands.sort_by_key(|command| command.sort_key);
+        let projection = *CONFIG.projection();
+        if let Some(lights) = lights {
+            lights.bind(5);
+        }
+        DRAWABLE_MANAGER.with(|dm| {
+            let draw_manager = dm.borrow();
+            let mut state = DrawState::default();
+            let mut start = 0;
+            while start < commands.len() {
+                let command = &commands[start];
+                let count = 1 + commands[start + 1..]
+                    .iter()
+                    .take_while(|other| command.batches_with(other))
+                    .count();
+                let shader = draw_manager.shader.get(&command.shader_handle).expect("Shader not found");
+                if state.shader.as_ref() != Some(&command.shader_handle) {
+                    shader.use_program();
+                    shader.reset_texture_count();
+                    shader.set_mat4("view_mat", viewmat);
+                    shader.set_mat4("projection_mat", &projection);
+                    if let Some(lights) = lights {
+                        lights.bind_shadow_maps(shader);
+                        lights.bind_environment(shader);
+                    }
+                    state.shader = Some(command.shader_handle.clone());
+                    state.texture_count = shader.texture_count();
+                    state.material = None;
+                }
+                if state.material.as_ref() != Some(&command.material_handle) {
+                    if let Some(material_id) = &command.material_handle {
+                        shader.set_texture_count(state.texture_count);
+                        draw_manager.material.get(material_id).expect("Material not found").set_uniforms(shader);
+                    }
+                    state.material = Some(command.material_handle.clone());
+                }
+                if state.render_state != Some(command.render_state) {
+                    command.render_state.apply();
+                    state.render_state = Some(command.render_state);
+                }
+                shader.set_optional("receives_shadows", &if command.receives_shadows { 1.0f32 } else { 0.0 });
+                let mesh = draw_manager.mesh.get(&command.mesh_handle).expect("Mesh not found");
+                if state.mesh.as_ref() != Some(&command.mesh_handle) {
+                    mesh.bind();
+                    state.mesh = Some(command.mesh_handle.clone());
+                }
+                if command.instanced() {
+                    shader.set_float("instanced", 1.0);
+                    self.instance_data.clear();
+                    self.instance_data.extend(
+                        commands[start..start + count]
+                            .iter()
+                            .map(|command| Instance::new(&command.model_mat, &command.color)),
+                    );
+                    self.instances.bind(&self.instance_data);
+                    mesh.draw_lod_instanced(command.lod, count);
+                    InstanceBuffer::unbind();
+                } else {
+                    shader.set_optional("instanced", &0.0f32);
+                    shader.set_mat4("model_mat", &command.model_mat);
+                    shader.set_optional("color", &command.color);
+                    if let Some(skin) = &command.skin {
+                        skin.bind();
+                        mesh.draw_lod(command.lod);
+                        Skin::unbind();
+                    } else {
+                        mesh.draw_lod(command.lod);
+                    }
+                }
+                start += count;
+            }
+        });
+        Lights::unbind(5);
+        MeshData::unbind();
+        Shader::unbind();
+        RenderState::reset();
+    }
+}
+
+thread_local! {
+    pub static RENDER_QUEUE: Lazy<RefCell<RenderQueue>> = Lazy::new(|| RefCell::new(RenderQueue::default()));
+}
@@ -0,0 +1,29 @@
+use crate::engine::drawable::shader::Shader;
+use crate::result::EngineRenderResult;
+
+// Writes the lit surfaces of the scene into the G-buffer.
+pub fn new_geometry_shader() -> EngineRenderResult<Shader> {
+    Shader::new(
+        Some(include_str!("glsl/lit/basic/vertex_shader.glsl")),
+        Some(concat!(
+            include_str!("glsl/version.glsl"),
+            include_str!("glsl/lit/normal_map.glsl"),
+            include_str!("glsl/deferred/geometry/fragment_shader.glsl")
+        )),
+        None,
+    )
+}
+
+// Lights the G-buffer over a screen quad.
+pub fn new_lighting_shader() -> EngineRenderResult<Shader> {
+    Shader::new(
+        Some(include_str!("glsl/screen/vertex_shader.glsl")),
+        Some(concat!(
+            include_str!("glsl/version.glsl"),
+            include_str!("glsl/lit/lights.glsl"),
+            include_str!("glsl/lit/lighting.glsl"),
+            include_str!("glsl/deferred/lighting/fragment_shader.glsl")
+        )),
+        None,
+    )
+}
@@ -0,0 +1,68 @@
+// Assigns the point and spot lights to the view space clusters their range reaches.
+// One invocation per cluster, a work group covers a row of screen tiles of one depth slice.
+layout (local_size_x = 16, local_size_y = 1, local_size_z = 1) in;
+
+layout (std430, binding = 8) writeonly buffer ClusterGrid {
+    uvec4 cluster_counts[];
+};
+
+layout (std430, binding = 9) writeonly buffer ClusterIndices {
+    uint cluster_indices[];
+};
+
+uniform mat4 view_mat;
+
+float SliceDepth(Clusters clusters, float slice) {
+    return clusters.near * pow(clusters.far / clusters.near, slice / float(clusters.size_z));
+}
+
+bool SphereIntersectsBox(vec3 center, float radius, vec3 boxMin, vec3 boxMax) {
+    vec3 closest = clamp(center, boxMin, boxMax);
+    vec3 delta = center - closest;
+    return dot(delta, delta) <= radius * radius;
+}
+
+void main() {
+    Clusters clusters = light.clusters;
+    ivec3 id = ivec3(gl_GlobalInvocationID);
+    if (id.x >= clusters.size_x || id.y >= clusters.size_y || id.z >= clusters.size_z) {
+        return;
+    }
+    int cluster = id.x + clusters.size_x * (id.y + clusters.size_y * id.z);
+
+    // view space bounds of the cluster, the camera looks down -z
+    vec2 ndcMin = vec2(id.xy) / vec2(clusters.size_x, clusters.size_y) * 2.0 - 1.0;
+    vec2 ndcMax = vec2(id.xy + 1) / vec2(clusters.size_x, clusters.size_y) * 2.0 - 1.0;
+    vec2 scale = vec2(clusters.tan_half_fov * clusters.aspect, clusters.tan_half_fov);
+    float nearDepth = SliceDepth(clusters, float(id.z));
+    float farDepth = SliceDepth(clusters, float(id.z + 1));
+    vec2 xyNearMin = ndcMin * scale * nearDepth;
+    vec2 xyNearMax = ndcMax * scale * nearDepth;
+    vec2 xyFarMin = ndcMin * scale * farDepth;
+    vec2 xyFarMax = ndcMax * scale * farDepth;
+    vec3 boxMin = vec3(min(xyNearMin, xyFarMin), -farDepth);
+    vec3 boxMax = vec3(max(xyNearMax, xyFarMax), -nearDepth);
+
+    uint offset = uint(cluster) * uint(MAX_LIGHTS_PER_CLUSTER);
+    uint count = 0u;
+    uint pointCount = 0u;
+    for (int i = 0; i < light.point_count && count < uint(MAX_LIGHTS_PER_CLUSTER); ++i) {
+        vec3 center = (view_mat * vec4(point_lights[i].position, 1.0)).xyz;
+        if (SphereIntersectsBox(center, point_lights[i].range, boxMin, boxMax)) {
+            cluster_indices[offset + count] = uint(i);
+            count++;
+            pointCount++;
+        }
+    }
+    uint spotCount = 0u;
+    for (int i = 0; i < light.spot_count && count < uint(MAX_LIGHTS_PER_CLUSTER); ++i) {
+        // the cone is bounded by the sphere of its range
+        vec3 center = (view_mat * vec4(spot_lights[i].position, 1.0)).xyz;
+        if (SphereIntersectsBox(center, spot_lights[i].range, boxMin, boxMax)) {
+            cluster_indices[offset + count] = uint(i);
+            count++;
+            spotCount++;
+        }
+    }
+    cluster_counts[cluster] = uvec4(pointCount, spotCount, 0u, 0u);
+}
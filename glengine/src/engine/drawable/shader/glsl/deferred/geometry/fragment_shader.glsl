@@ -0,0 +1,61 @@
+in vec3 Normal;
+in vec3 FragPos;
+in vec2 TexCoords;
+in vec3 Tangent;
+in vec3 Bitangent;
+// tint of the DrawData
+in vec4 Color;
+
+uniform struct Material {
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+    float shininess;
+} material;
+
+uniform sampler2D diffuse_texture;
+uniform sampler2D specular_texture;
+uniform sampler2D normal_texture;
+uniform float has_diffuse_texture;
+uniform float has_specular_texture;
+uniform float has_normal_texture;
+
+uniform float receives_shadows;
+// fragments under it are discarded, 0 unless the material is alpha tested
+uniform float alpha_cutoff;
+
+layout (location = 0) out vec4 g_position;
+layout (location = 1) out vec4 g_normal;
+layout (location = 2) out vec4 g_albedo;
+layout (location = 3) out vec4 g_specular;
+
+const float MIN_SHININESS = 1.0;
+
+void main() {
+    // the alpha of the position marks the covered pixels
+    g_position = vec4(FragPos, 1.0);
+    vec3 normal = normalize(Normal);
+    if (has_normal_texture > 0.5) {
+        normal = NormalFromMap(normal_texture, normal, Tangent, Bitangent, FragPos, TexCoords);
+    }
+    g_normal = vec4(normal, receives_shadows);
+    vec3 diffuseColor = material.diffuse * Color.rgb;
+    float alpha = Color.a;
+    if (has_diffuse_texture > 0.5) {
+        vec4 texel = texture(diffuse_texture, TexCoords);
+        diffuseColor *= texel.rgb;
+        alpha *= texel.a;
+    }
+    if (alpha < alpha_cutoff) {
+        discard;
+    }
+    vec3 specularColor = material.specular;
+    if (has_specular_texture > 0.5) {
+        specularColor *= texture(specular_texture, TexCoords).rgb;
+    }
+    // there is no room left for an ambient color, it is kept as a fraction of the diffuse one
+    float diffuse = max(max(material.diffuse.r, material.diffuse.g), material.diffuse.b);
+    float ambient = max(max(material.ambient.r, material.ambient.g), material.ambient.b);
+    g_albedo = vec4(diffuseColor, diffuse > 0.0 ? ambient / diffuse : 0.0);
+    g_specular = vec4(specularColor, max(material.shininess, MIN_SHININESS));
+}
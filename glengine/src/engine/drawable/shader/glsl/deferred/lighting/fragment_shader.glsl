@@ -0,0 +1,34 @@
+in vec2 TexCoord;
+
+uniform sampler2D g_position;
+uniform sampler2D g_normal;
+uniform sampler2D g_albedo;
+uniform sampler2D g_specular;
+uniform sampler2D g_depth;
+
+out vec4 FragColor;
+
+void main() {
+    vec4 position = texture(g_position, TexCoord);
+    if (position.a == 0.0) {
+        discard;
+    }
+    vec4 normal = texture(g_normal, TexCoord);
+    vec4 albedo = texture(g_albedo, TexCoord);
+    vec4 specular = texture(g_specular, TexCoord);
+
+    Surface surface = Surface(
+        albedo.rgb * albedo.a,
+        albedo.rgb,
+        specular.rgb,
+        specular.a,
+        normal.a > 0.5
+    );
+    vec3 viewPos = vec3(inverse(view_mat)[3]);
+    vec3 viewDir = normalize(viewPos - position.xyz);
+    vec3 result = CalculateLights(surface, normalize(normal.xyz), position.xyz, viewDir);
+
+    // the depth of the geometry pass lets the forward pass draw on top of the lit scene
+    gl_FragDepth = texture(g_depth, TexCoord).r;
+    FragColor = vec4(result, 1.0);
+}
@@ -0,0 +1,61 @@
+#version 310 es
+precision highp float;
+
+in vec2 TexCoord;
+
+out vec4 FragColor;
+
+const float PI = 3.14159265359;
+const uint SAMPLE_COUNT = 1024u;
+
+vec2 Hammersley(uint i, uint n) {
+    float radicalInverse = float(bitfieldReverse(i)) * 2.3283064365386963e-10;
+    return vec2(float(i) / float(n), radicalInverse);
+}
+
+vec3 ImportanceSampleGGX(vec2 xi, vec3 normal, float roughness) {
+    float a = roughness * roughness;
+    float phi = 2.0 * PI * xi.x;
+    float cosTheta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+    float sinTheta = sqrt(1.0 - cosTheta * cosTheta);
+    vec3 halfway = vec3(cos(phi) * sinTheta, sin(phi) * sinTheta, cosTheta);
+
+    vec3 up = abs(normal.z) < 0.999 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(up, normal));
+    vec3 bitangent = cross(normal, tangent);
+    return normalize(tangent * halfway.x + bitangent * halfway.y + normal * halfway.z);
+}
+
+float GeometrySchlickGGX(float NdotV, float roughness) {
+    // the image based lighting k
+    float k = (roughness * roughness) / 2.0;
+    return NdotV / (NdotV * (1.0 - k) + k);
+}
+
+// Scale and bias applied to F0 by the specular part of the split sum, indexed by NdotV and roughness
+void main() {
+    float NdotV = max(TexCoord.x, 0.001);
+    float roughness = TexCoord.y;
+    vec3 view = vec3(sqrt(1.0 - NdotV * NdotV), 0.0, NdotV);
+    vec3 normal = vec3(0.0, 0.0, 1.0);
+
+    float scale = 0.0;
+    float bias = 0.0;
+    for (uint i = 0u; i < SAMPLE_COUNT; ++i) {
+        vec2 xi = Hammersley(i, SAMPLE_COUNT);
+        vec3 halfway = ImportanceSampleGGX(xi, normal, roughness);
+        vec3 lightDir = normalize(2.0 * dot(view, halfway) * halfway - view);
+
+        float NdotL = max(lightDir.z, 0.0);
+        float NdotH = max(halfway.z, 0.0);
+        float VdotH = max(dot(view, halfway), 0.0);
+        if (NdotL > 0.0) {
+            float geometry = GeometrySchlickGGX(NdotV, roughness) * GeometrySchlickGGX(NdotL, roughness);
+            float visibility = (geometry * VdotH) / (NdotH * NdotV);
+            float fresnel = pow(1.0 - VdotH, 5.0);
+            scale += (1.0 - fresnel) * visibility;
+            bias += fresnel * visibility;
+        }
+    }
+    FragColor = vec4(scale / float(SAMPLE_COUNT), bias / float(SAMPLE_COUNT), 0.0, 1.0);
+}
@@ -0,0 +1,13 @@
+#version 310 es
+precision highp float;
+
+layout (location = 0) in vec3 position;
+
+uniform mat4 capture_mat;
+
+out vec3 LocalPos;
+
+void main() {
+    LocalPos = position;
+    gl_Position = capture_mat * vec4(position, 1.0);
+}
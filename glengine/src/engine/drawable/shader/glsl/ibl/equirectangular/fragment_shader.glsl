@@ -0,0 +1,17 @@
+#version 310 es
+precision highp float;
+
+in vec3 LocalPos;
+
+uniform sampler2D equirectangular_map;
+
+out vec4 FragColor;
+
+const vec2 INV_ATAN = vec2(0.1591, 0.3183);
+
+void main() {
+    vec3 direction = normalize(LocalPos);
+    // the image rows are uploaded top first, so up maps to the start of the texture
+    vec2 uv = vec2(atan(direction.z, direction.x), -asin(direction.y)) * INV_ATAN + 0.5;
+    FragColor = vec4(texture(equirectangular_map, uv).rgb, 1.0);
+}
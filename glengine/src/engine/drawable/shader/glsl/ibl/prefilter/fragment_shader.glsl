@@ -0,0 +1,66 @@
+#version 310 es
+precision highp float;
+
+in vec3 LocalPos;
+
+uniform samplerCube environment_map;
+uniform float roughness;
+uniform float resolution;
+
+out vec4 FragColor;
+
+const float PI = 3.14159265359;
+const uint SAMPLE_COUNT = 1024u;
+
+vec2 Hammersley(uint i, uint n) {
+    float radicalInverse = float(bitfieldReverse(i)) * 2.3283064365386963e-10;
+    return vec2(float(i) / float(n), radicalInverse);
+}
+
+vec3 ImportanceSampleGGX(vec2 xi, vec3 normal, float roughness) {
+    float a = roughness * roughness;
+    float phi = 2.0 * PI * xi.x;
+    float cosTheta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+    float sinTheta = sqrt(1.0 - cosTheta * cosTheta);
+    vec3 halfway = vec3(cos(phi) * sinTheta, sin(phi) * sinTheta, cosTheta);
+
+    vec3 up = abs(normal.z) < 0.999 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(up, normal));
+    vec3 bitangent = cross(normal, tangent);
+    return normalize(tangent * halfway.x + bitangent * halfway.y + normal * halfway.z);
+}
+
+float DistributionGGX(float NdotH, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float denominator = NdotH * NdotH * (a2 - 1.0) + 1.0;
+    return a2 / (PI * denominator * denominator);
+}
+
+// Split sum prefiltering of the environment for the roughness of this mip level,
+// the view direction is assumed to be the reflection direction
+void main() {
+    vec3 normal = normalize(LocalPos);
+    vec3 view = normal;
+
+    vec3 color = vec3(0.0);
+    float weight = 0.0;
+    for (uint i = 0u; i < SAMPLE_COUNT; ++i) {
+        vec2 xi = Hammersley(i, SAMPLE_COUNT);
+        vec3 halfway = ImportanceSampleGGX(xi, normal, roughness);
+        vec3 lightDir = normalize(2.0 * dot(view, halfway) * halfway - view);
+        float NdotL = max(dot(normal, lightDir), 0.0);
+        if (NdotL > 0.0) {
+            // sample a blurrier mip where the distribution is sparse to avoid bright dots
+            float NdotH = max(dot(normal, halfway), 0.0);
+            float HdotV = max(dot(halfway, view), 0.0);
+            float pdf = DistributionGGX(NdotH, roughness) * NdotH / (4.0 * HdotV) + 0.0001;
+            float saTexel = 4.0 * PI / (6.0 * resolution * resolution);
+            float saSample = 1.0 / (float(SAMPLE_COUNT) * pdf + 0.0001);
+            float mip = roughness == 0.0 ? 0.0 : 0.5 * log2(saSample / saTexel);
+            color += textureLod(environment_map, lightDir, mip).rgb * NdotL;
+            weight += NdotL;
+        }
+    }
+    FragColor = vec4(color / weight, 1.0);
+}
@@ -4,22 +4,37 @@ precision highp float;
 layout (location = 0) in vec3 position;
 layout (location = 1) in vec3 normal;
 layout (location = 2) in vec2 tex_coords;
+layout (location = 3) in vec3 tangent;
+layout (location = 4) in vec3 bitangent;
+// per instance when drawn instanced
+layout (location = 7) in mat4 instance_model_mat;
+layout (location = 11) in vec4 instance_color;
 
 uniform mat4 model_mat;
 uniform mat4 view_mat;
 uniform mat4 projection_mat;
+uniform vec4 color;
+uniform float instanced;
 
 out vec3 FragPos;
 out vec3 ViewPos;
 out vec3 Normal;
 out vec2 TexCoords;
+out vec3 Tangent;
+out vec3 Bitangent;
+out vec4 Color;
 
 void main() {
-    Normal = mat3(transpose(inverse(model_mat))) * normal;  // Transform the normal to world space TODO:: might want to do this on the CPU
-    vec4 worldPosition = model_mat * vec4(position, 1.0);
+    mat4 model = instanced > 0.5 ? instance_model_mat : model_mat;
+    Color = instanced > 0.5 ? instance_color : color;
+    Normal = mat3(transpose(inverse(model))) * normal;  // Transform the normal to world space TODO:: might want to do this on the CPU
+    vec4 worldPosition = model * vec4(position, 1.0);
     ViewPos = vec3(inverse(view_mat)[3]);
     FragPos = vec3(worldPosition); // Transform the position to world space
     TexCoords = tex_coords;
+    // zero when the mesh has no tangents, the normal mapping then derives its own frame
+    Tangent = mat3(model) * tangent;
+    Bitangent = mat3(model) * bitangent;
 
     gl_Position = projection_mat * view_mat * worldPosition;
 }
\ No newline at end of file
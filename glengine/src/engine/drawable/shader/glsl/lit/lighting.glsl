@@ -0,0 +1,245 @@
+// Shared lighting code, prepended to the shaders that light a surface after the light layout.
+uniform highp sampler2DArrayShadow directional_shadow_map;
+uniform highp sampler2DArrayShadow spot_shadow_maps;
+uniform highp samplerCubeShadow point_shadow_maps[MAX_POINT_SHADOWS];
+
+struct Surface {
+    vec3 ambient;
+    vec3 diffuse;
+    vec3 specular;
+    float shininess;
+    bool receives_shadows;
+};
+
+uniform mat4 view_mat;
+
+// filled by the light culling shader, the point light indices of a cluster come before the spot ones
+layout (std430, binding = 8) readonly buffer ClusterGrid {
+    uvec4 cluster_counts[];
+};
+
+layout (std430, binding = 9) readonly buffer ClusterIndices {
+    uint cluster_indices[];
+};
+
+int ClusterIndex(vec3 fragPos) {
+    Clusters clusters = light.clusters;
+    float depth = -(view_mat * vec4(fragPos, 1.0)).z;
+    int slice = int(floor(log(max(depth, clusters.near) / clusters.near) / log(clusters.far / clusters.near) * float(clusters.size_z)));
+    vec2 screen = gl_FragCoord.xy / vec2(clusters.screen_width, clusters.screen_height);
+    ivec2 tile = ivec2(screen * vec2(clusters.size_x, clusters.size_y));
+    tile = clamp(tile, ivec2(0), ivec2(clusters.size_x - 1, clusters.size_y - 1));
+    slice = clamp(slice, 0, clusters.size_z - 1);
+    return tile.x + clusters.size_x * (tile.y + clusters.size_y * slice);
+}
+
+// Percentage closer filtering over a (2r+1)^2 kernel of one layer of a shadow map array
+float SampleShadowArray(highp sampler2DArrayShadow shadow_map, vec4 lightSpacePos, float layer, float bias, int radius) {
+    vec3 projected = lightSpacePos.xyz / lightSpacePos.w * 0.5 + 0.5;
+    if (projected.z > 1.0) {
+        return 1.0;
+    }
+    vec2 texel = 1.0 / vec2(textureSize(shadow_map, 0).xy);
+    float lit = 0.0;
+    for (int x = -radius; x <= radius; ++x) {
+        for (int y = -radius; y <= radius; ++y) {
+            lit += texture(shadow_map, vec4(projected.xy + vec2(x, y) * texel, layer, projected.z - bias));
+        }
+    }
+    float samples = float((2 * radius + 1) * (2 * radius + 1));
+    return lit / samples;
+}
+
+float DirectionalShadow(DirectionalLight light, vec3 normal, vec3 fragPos) {
+    vec3 lightDir = normalize(-light.direction);
+    vec3 offsetPos = fragPos + normal * light.normal_bias * (1.0 - dot(normal, lightDir));
+    for (int i = 0; i < light.cascade_count; ++i) {
+        vec4 lightSpacePos = light.light_space[i] * vec4(offsetPos, 1.0);
+        vec3 projected = lightSpacePos.xyz / lightSpacePos.w * 0.5 + 0.5;
+        // the cascades are ordered from the camera outwards, the first one containing the fragment is the sharpest
+        if (all(greaterThanEqual(projected, vec3(0.0))) && all(lessThanEqual(projected, vec3(1.0)))) {
+            return SampleShadowArray(directional_shadow_map, lightSpacePos, float(i), light.bias, light.pcf_radius);
+        }
+    }
+    return 1.0;
+}
+
+float SpotShadow(SpotLight light, vec3 normal, vec3 fragPos) {
+    if (light.shadow_index < 0) {
+        return 1.0;
+    }
+    vec3 lightDir = normalize(light.position - fragPos);
+    vec3 offsetPos = fragPos + normal * light.normal_bias * (1.0 - dot(normal, lightDir));
+    vec4 lightSpacePos = light.light_space * vec4(offsetPos, 1.0);
+    if (lightSpacePos.w <= 0.0) {
+        return 1.0;
+    }
+    return SampleShadowArray(spot_shadow_maps, lightSpacePos, float(light.shadow_index), light.bias, light.pcf_radius);
+}
+
+float SamplePointShadowMap(int index, vec4 coords) {
+    // sampler arrays may only be indexed with constant expressions
+    switch (index) {
+        case 0: return texture(point_shadow_maps[0], coords);
+        case 1: return texture(point_shadow_maps[1], coords);
+        case 2: return texture(point_shadow_maps[2], coords);
+        case 3: return texture(point_shadow_maps[3], coords);
+    }
+    return 1.0;
+}
+
+float PointShadow(PointLight light, vec3 normal, vec3 fragPos) {
+    if (light.shadow_index < 0) {
+        return 1.0;
+    }
+    vec3 lightDir = normalize(light.position - fragPos);
+    vec3 offsetPos = fragPos + normal * light.normal_bias * (1.0 - dot(normal, lightDir));
+    vec3 toFrag = offsetPos - light.position;
+    // the depth stored in the cube face is the perspective depth along the major axis
+    vec3 absolute = abs(toFrag);
+    float major = max(absolute.x, max(absolute.y, absolute.z));
+    if (major >= light.far) {
+        return 1.0;
+    }
+    float ndc = (light.far + light.near) / (light.far - light.near)
+        - (2.0 * light.far * light.near) / ((light.far - light.near) * major);
+    float depth = ndc * 0.5 + 0.5 - light.bias;
+
+    // the kernel is spread on the plane of the cube face
+    vec3 tangent = normalize(cross(toFrag, abs(toFrag.y) < 0.99 * length(toFrag) ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0)));
+    vec3 bitangent = cross(normalize(toFrag), tangent);
+    float texel = 2.0 * major / float(textureSize(point_shadow_maps[0], 0).x);
+    float lit = 0.0;
+    for (int x = -light.pcf_radius; x <= light.pcf_radius; ++x) {
+        for (int y = -light.pcf_radius; y <= light.pcf_radius; ++y) {
+            vec3 direction = toFrag + (tangent * float(x) + bitangent * float(y)) * texel;
+            lit += SamplePointShadowMap(light.shadow_index, vec4(direction, depth));
+        }
+    }
+    float samples = float((2 * light.pcf_radius + 1) * (2 * light.pcf_radius + 1));
+    return lit / samples;
+}
+
+// Function to calculate point light contribution
+vec3 CalculatePointLight(PointLight light, Surface surface, vec3 normal, vec3 fragPos, vec3 viewDir, float shadow) {
+    vec3 lightDir = normalize(light.position - fragPos); // Direction from fragment to light
+
+    // Diffuse shading
+    float diff = max(dot(normal, lightDir), 0.0);
+
+    // Specular shading
+    vec3 reflectDir = reflect(-lightDir, normal);
+    vec3 halfwayDir = normalize(lightDir + viewDir);
+    float spec = pow(max(dot(normal, halfwayDir), 0.0), surface.shininess);
+
+    // Attenuation
+    float distance = length(light.position - fragPos);
+    float attenuation = 1.0 / (light.constant + light.linear * distance + light.quadratic * (distance * distance));
+
+    // Final light intensity
+    vec3 ambient = light.color * surface.ambient * attenuation * light.intensity;
+    vec3 diffuse = light.color * diff * surface.diffuse * attenuation * light.intensity;
+    vec3 specular = light.color * spec * surface.specular * attenuation * light.intensity;
+
+    return ambient + (diffuse + specular) * shadow;
+}
+
+// Function to calculate spot light contribution
+vec3 CalculateSpotLight(SpotLight light, Surface surface, vec3 normal, vec3 fragPos, vec3 viewDir, float shadow) {
+    // Calculate light direction
+    vec3 lightDir = normalize(light.position - fragPos);
+
+    // Calculate spotlight direction
+    vec3 spotDir = normalize(-light.direction);
+
+    // Calculate cosine of the angle between the light direction and the spotlight direction
+    float theta = dot(lightDir, spotDir);
+
+    // Convert cutoff angles from degrees to radians
+    float outer_cut_off_rad = radians(light.outer_cut_off);
+    float cut_off_rad = radians(light.cut_off);
+
+    // Check if the fragment is inside the spotlight cone
+    float intensity = smoothstep(cos(outer_cut_off_rad), cos(cut_off_rad), theta);
+
+    // Calculate diffuse shading
+    float diff = max(dot(normal, lightDir), 0.0);
+
+    // Calculate specular shading using the halfway vector
+    vec3 halfwayDir = normalize(lightDir + viewDir); // Correct normalization
+
+    // Calculate the specular strength
+    float specularStrength = 0.0;
+    if (diff > 0.0) {
+        specularStrength = pow(max(dot(normal, halfwayDir), 0.0), surface.shininess);
+    }
+
+    // Calculate attenuation
+    float distance = length(light.position - fragPos);
+    float attenuation = 1.0 / (light.constant + light.linear * distance + light.quadratic * (distance * distance));
+
+    // Calculate final light intensity
+    vec3 ambient = light.color * surface.ambient * light.intensity * attenuation * intensity;
+    vec3 diffuse = light.color * surface.diffuse * diff * light.intensity * attenuation * intensity;
+    vec3 specular = light.color * surface.specular * specularStrength * light.intensity * attenuation * intensity;
+
+    return ambient + (diffuse + specular) * shadow;
+}
+
+
+// Function to calculate directional light contribution
+vec3 CalculateDirectionalLight(DirectionalLight light, Surface surface, vec3 normal, vec3 viewDir, float shadow) {
+    vec3 lightDir = normalize(-light.direction); // Direction from light source
+
+    // Diffuse shading
+    float diff = max(dot(normal, lightDir), 0.0);
+
+    // Specular shading
+    vec3 reflectDir = reflect(-lightDir, normal);
+    float spec = pow(max(dot(viewDir, reflectDir), 0.0), surface.shininess);
+
+    // Final light intensity
+    vec3 ambient = light.color * light.intensity * surface.ambient;
+    vec3 diffuse = light.color * diff * surface.diffuse * light.intensity;
+    vec3 specular = light.color * spec * surface.specular * light.intensity;
+
+    return ambient + (diffuse + specular) * shadow;
+}
+
+
+vec3 CalculateLights(Surface surface, vec3 normal, vec3 fragPos, vec3 viewDir){
+    vec3 result = vec3(0.0);
+
+    // Calculate directional light
+    if (light.is_directional) {
+        float angle = dot(normal, -light.directional_light.direction);
+        float smooth_angle = smoothstep(0.0, 0.3, angle);
+        float shadow = surface.receives_shadows ? DirectionalShadow(light.directional_light, normal, fragPos) : 1.0;
+        result += CalculateDirectionalLight(light.directional_light, surface, normal, viewDir, shadow) * smooth_angle;
+    }
+
+    // Only the lights reaching the cluster of the fragment are evaluated
+    int cluster = ClusterIndex(fragPos);
+    uvec4 counts = cluster_counts[cluster];
+    uint offset = uint(cluster) * uint(MAX_LIGHTS_PER_CLUSTER);
+
+    // Calculate point lights
+    for (uint i = 0u; i < counts.x; ++i) {
+        PointLight point_light = point_lights[cluster_indices[offset + i]];
+        float angle = dot(normal, normalize(point_light.position - fragPos));
+        float smooth_angle = smoothstep(0.0, 0.1, angle);
+        float shadow = surface.receives_shadows ? PointShadow(point_light, normal, fragPos) : 1.0;
+        result += CalculatePointLight(point_light, surface, normal, fragPos, viewDir, shadow) * smooth_angle;
+    }
+
+    // Calculate spot lights
+    for (uint i = 0u; i < counts.y; ++i) {
+        SpotLight spot_light = spot_lights[cluster_indices[offset + counts.x + i]];
+        float angle = dot(normal, normalize(spot_light.position - fragPos));
+        float smooth_angle = smoothstep(-0.1, 0.1, angle);
+        float shadow = surface.receives_shadows ? SpotShadow(spot_light, normal, fragPos) : 1.0;
+        result += CalculateSpotLight(spot_light, surface, normal, fragPos, viewDir, shadow) * smooth_angle;
+    }
+
+    return result;
+}
@@ -0,0 +1,82 @@
+// Light layout shared by the lighting and light culling shaders, prepended after the version header.
+#define SHADOW_CASCADES 3
+#define MAX_SPOT_SHADOWS 4
+#define MAX_POINT_SHADOWS 4
+#define MAX_LIGHTS_PER_CLUSTER 128
+
+struct DirectionalLight {
+    float intensity;
+    vec3 color;
+    vec3 direction;
+    int cascade_count;
+    float bias;
+    float normal_bias;
+    int pcf_radius;
+    mat4 light_space[SHADOW_CASCADES];
+};
+
+struct PointLight {
+    float intensity;
+    vec3 color;
+    vec3 position;
+    float constant;
+    float linear;
+    float quadratic;
+    float range;
+    int shadow_index;
+    float bias;
+    float normal_bias;
+    int pcf_radius;
+    float near;
+    float far;
+};
+
+struct SpotLight {
+    float intensity;
+    vec3 color;
+    vec3 position;
+    vec3 direction;
+    float constant;
+    float linear;
+    float quadratic;
+    float cut_off;
+    float outer_cut_off;
+    float range;
+    int shadow_index;
+    float bias;
+    float normal_bias;
+    int pcf_radius;
+    mat4 light_space;
+};
+
+struct Clusters {
+    int size_x;
+    int size_y;
+    int size_z;
+    float near;
+    float far;
+    float tan_half_fov;
+    float aspect;
+    float screen_width;
+    float screen_height;
+};
+
+struct Light {
+    bool is_directional;
+    DirectionalLight directional_light;
+    int point_count;
+    int spot_count;
+    Clusters clusters;
+};
+
+layout (std140, binding = 5) uniform Lights {
+    Light light;
+};
+
+layout (std140, binding = 6) readonly buffer PointLights {
+    PointLight point_lights[];
+};
+
+layout (std140, binding = 7) readonly buffer SpotLights {
+    SpotLight spot_lights[];
+};
@@ -0,0 +1,25 @@
+// Perturbs the normal with a tangent space normal map. Meshes imported without tangents
+// fall back to a tangent frame derived from the screen space derivatives.
+vec3 NormalFromMap(sampler2D normal_map, vec3 normal, vec3 tangent, vec3 bitangent, vec3 fragPos, vec2 uv) {
+    // derivatives are taken before branching, they are undefined in non uniform control flow
+    vec3 dp1 = dFdx(fragPos);
+    vec3 dp2 = dFdy(fragPos);
+    vec2 duv1 = dFdx(uv);
+    vec2 duv2 = dFdy(uv);
+    vec3 mapped = texture(normal_map, uv).xyz * 2.0 - 1.0;
+
+    mat3 tbn;
+    if (dot(tangent, tangent) > 0.0 && dot(bitangent, bitangent) > 0.0) {
+        vec3 t = normalize(tangent - normal * dot(normal, tangent));
+        vec3 b = normalize(bitangent);
+        tbn = mat3(t, b, normal);
+    } else {
+        vec3 dp2perp = cross(dp2, normal);
+        vec3 dp1perp = cross(normal, dp1);
+        vec3 t = dp2perp * duv1.x + dp1perp * duv2.x;
+        vec3 b = dp2perp * duv1.y + dp1perp * duv2.y;
+        float invmax = inversesqrt(max(max(dot(t, t), dot(b, b)), 1e-12));
+        tbn = mat3(t * invmax, b * invmax, normal);
+    }
+    return normalize(tbn * mapped);
+}
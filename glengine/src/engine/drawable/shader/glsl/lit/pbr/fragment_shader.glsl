@@ -0,0 +1,178 @@
+in vec3 Normal;
+in vec3 FragPos;
+in vec3 ViewPos;
+in vec2 TexCoords;
+in vec3 Tangent;
+in vec3 Bitangent;
+// tint of the DrawData or instance
+in vec4 Color;
+
+uniform struct Material {
+    vec4 base_color;
+    float metallic;
+    float roughness;
+    float ao;
+    vec3 emissive;
+} material;
+
+uniform sampler2D base_color_texture;
+uniform sampler2D metallic_roughness_texture;
+uniform sampler2D ao_texture;
+uniform sampler2D emissive_texture;
+uniform sampler2D normal_texture;
+uniform float has_base_color_texture;
+uniform float has_metallic_roughness_texture;
+uniform float has_ao_texture;
+uniform float has_emissive_texture;
+uniform float has_normal_texture;
+
+// image based lighting, bound by the lights of the scene when an environment is set
+uniform samplerCube irradiance_map;
+uniform samplerCube prefiltered_map;
+uniform sampler2D brdf_lut;
+uniform float has_environment;
+uniform float environment_intensity;
+uniform float prefiltered_mips;
+
+uniform float receives_shadows;
+// fragments under it are discarded, 0 unless the material is alpha tested
+uniform float alpha_cutoff;
+
+out vec4 FragColor;
+
+const float PI = 3.14159265359;
+const float MIN_ROUGHNESS = 0.04;
+
+vec3 SrgbToLinear(vec3 color) {
+    return pow(color, vec3(2.2));
+}
+
+float DistributionGGX(float NdotH, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float denominator = NdotH * NdotH * (a2 - 1.0) + 1.0;
+    return a2 / (PI * denominator * denominator);
+}
+
+float GeometrySmith(float NdotV, float NdotL, float roughness) {
+    float r = roughness + 1.0;
+    float k = (r * r) / 8.0;
+    float ggxV = NdotV / (NdotV * (1.0 - k) + k);
+    float ggxL = NdotL / (NdotL * (1.0 - k) + k);
+    return ggxV * ggxL;
+}
+
+vec3 FresnelSchlick(float cosTheta, vec3 F0) {
+    return F0 + (1.0 - F0) * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
+}
+
+vec3 FresnelSchlickRoughness(float cosTheta, vec3 F0, float roughness) {
+    return F0 + (max(vec3(1.0 - roughness), F0) - F0) * pow(clamp(1.0 - cosTheta, 0.0, 1.0), 5.0);
+}
+
+// Cook-Torrance radiance reflected towards the viewer from a light arriving from lightDir
+vec3 CookTorrance(vec3 albedo, float metallic, float roughness, vec3 F0, vec3 normal, vec3 viewDir, vec3 lightDir, vec3 radiance) {
+    vec3 halfwayDir = normalize(viewDir + lightDir);
+    float NdotL = max(dot(normal, lightDir), 0.0);
+    float NdotV = max(dot(normal, viewDir), 0.0);
+    float NdotH = max(dot(normal, halfwayDir), 0.0);
+
+    float D = DistributionGGX(NdotH, roughness);
+    float G = GeometrySmith(NdotV, NdotL, roughness);
+    vec3 F = FresnelSchlick(max(dot(halfwayDir, viewDir), 0.0), F0);
+
+    vec3 specular = D * G * F / (4.0 * NdotV * NdotL + 0.0001);
+    vec3 diffuse = (vec3(1.0) - F) * (1.0 - metallic) * albedo / PI;
+    return (diffuse + specular) * radiance * NdotL;
+}
+
+void main() {
+    vec4 baseColor = material.base_color * Color;
+    if (has_base_color_texture > 0.5) {
+        vec4 texel = texture(base_color_texture, TexCoords);
+        baseColor *= vec4(SrgbToLinear(texel.rgb), texel.a);
+    }
+    if (baseColor.a < alpha_cutoff) {
+        discard;
+    }
+    vec3 albedo = baseColor.rgb;
+    float metallic = material.metallic;
+    float roughness = material.roughness;
+    if (has_metallic_roughness_texture > 0.5) {
+        // glTF packing, roughness in green and metalness in blue
+        vec4 texel = texture(metallic_roughness_texture, TexCoords);
+        roughness *= texel.g;
+        metallic *= texel.b;
+    }
+    roughness = clamp(roughness, MIN_ROUGHNESS, 1.0);
+    metallic = clamp(metallic, 0.0, 1.0);
+    float ao = material.ao;
+    if (has_ao_texture > 0.5) {
+        ao *= texture(ao_texture, TexCoords).r;
+    }
+    vec3 emissive = material.emissive;
+    if (has_emissive_texture > 0.5) {
+        emissive *= SrgbToLinear(texture(emissive_texture, TexCoords).rgb);
+    }
+
+    vec3 normal = normalize(Normal);
+    if (has_normal_texture > 0.5) {
+        normal = NormalFromMap(normal_texture, normal, Tangent, Bitangent, FragPos, TexCoords);
+    }
+    vec3 viewDir = normalize(ViewPos - FragPos);
+    vec3 F0 = mix(vec3(0.04), albedo, metallic);
+    bool shadowed = receives_shadows > 0.5;
+
+    vec3 result = vec3(0.0);
+
+    if (light.is_directional) {
+        DirectionalLight directional = light.directional_light;
+        float shadow = shadowed ? DirectionalShadow(directional, normal, FragPos) : 1.0;
+        vec3 radiance = directional.color * directional.intensity * shadow;
+        result += CookTorrance(albedo, metallic, roughness, F0, normal, viewDir, normalize(-directional.direction), radiance);
+    }
+
+    // Only the lights reaching the cluster of the fragment are evaluated
+    int cluster = ClusterIndex(FragPos);
+    uvec4 counts = cluster_counts[cluster];
+    uint offset = uint(cluster) * uint(MAX_LIGHTS_PER_CLUSTER);
+
+    for (uint i = 0u; i < counts.x; ++i) {
+        PointLight point_light = point_lights[cluster_indices[offset + i]];
+        float distance = length(point_light.position - FragPos);
+        float attenuation = 1.0 / (point_light.constant + point_light.linear * distance + point_light.quadratic * (distance * distance));
+        float shadow = shadowed ? PointShadow(point_light, normal, FragPos) : 1.0;
+        vec3 radiance = point_light.color * point_light.intensity * attenuation * shadow;
+        result += CookTorrance(albedo, metallic, roughness, F0, normal, viewDir, normalize(point_light.position - FragPos), radiance);
+    }
+
+    for (uint i = 0u; i < counts.y; ++i) {
+        SpotLight spot_light = spot_lights[cluster_indices[offset + counts.x + i]];
+        vec3 lightDir = normalize(spot_light.position - FragPos);
+        float theta = dot(lightDir, normalize(-spot_light.direction));
+        float cone = smoothstep(cos(radians(spot_light.outer_cut_off)), cos(radians(spot_light.cut_off)), theta);
+        float distance = length(spot_light.position - FragPos);
+        float attenuation = 1.0 / (spot_light.constant + spot_light.linear * distance + spot_light.quadratic * (distance * distance));
+        float shadow = shadowed ? SpotShadow(spot_light, normal, FragPos) : 1.0;
+        vec3 radiance = spot_light.color * spot_light.intensity * attenuation * cone * shadow;
+        result += CookTorrance(albedo, metallic, roughness, F0, normal, viewDir, lightDir, radiance);
+    }
+
+    vec3 ambient;
+    if (has_environment > 0.5) {
+        // split sum approximation, diffuse from the irradiance map and specular from the prefiltered map
+        float NdotV = max(dot(normal, viewDir), 0.0);
+        vec3 F = FresnelSchlickRoughness(NdotV, F0, roughness);
+        vec3 kD = (1.0 - F) * (1.0 - metallic);
+        vec3 diffuse = texture(irradiance_map, normal).rgb * albedo;
+        vec3 reflected = reflect(-viewDir, normal);
+        vec3 prefiltered = textureLod(prefiltered_map, reflected, roughness * prefiltered_mips).rgb;
+        vec2 brdf = texture(brdf_lut, vec2(NdotV, roughness)).rg;
+        vec3 specular = prefiltered * (F * brdf.x + brdf.y);
+        ambient = (kD * diffuse + specular) * ao * environment_intensity;
+    } else {
+        ambient = vec3(0.03) * albedo * ao;
+    }
+
+    FragColor = vec4(result + ambient + emissive, baseColor.a);
+}
@@ -0,0 +1,55 @@
+#version 310 es
+precision highp float;
+
+#define MAX_BONES 128
+
+layout (location = 0) in vec3 position;
+layout (location = 1) in vec3 normal;
+layout (location = 2) in vec2 tex_coords;
+layout (location = 3) in vec3 tangent;
+layout (location = 4) in vec3 bitangent;
+layout (location = 5) in uvec4 bone_ids;
+layout (location = 6) in vec4 bone_weights;
+
+uniform mat4 model_mat;
+uniform mat4 view_mat;
+uniform mat4 projection_mat;
+uniform vec4 color;
+
+layout (std140, binding = 6) uniform Bones {
+    mat4 bone_mats[MAX_BONES];
+};
+
+out vec3 FragPos;
+out vec3 ViewPos;
+out vec3 Normal;
+out vec2 TexCoords;
+out vec3 Tangent;
+out vec3 Bitangent;
+out vec4 Color;
+
+// Blends the bone matrices of the vertex, vertices without weights stay in the bind pose
+mat4 SkinMatrix() {
+    if (dot(bone_weights, vec4(1.0)) <= 0.0) {
+        return mat4(1.0);
+    }
+    mat4 skin = mat4(0.0);
+    for (int i = 0; i < 4; ++i) {
+        skin += bone_mats[min(bone_ids[i], uint(MAX_BONES - 1))] * bone_weights[i];
+    }
+    return skin;
+}
+
+void main() {
+    mat4 skinned_mat = model_mat * SkinMatrix();
+    Normal = mat3(transpose(inverse(skinned_mat))) * normal;
+    vec4 worldPosition = skinned_mat * vec4(position, 1.0);
+    ViewPos = vec3(inverse(view_mat)[3]);
+    FragPos = vec3(worldPosition);
+    TexCoords = tex_coords;
+    Color = color;
+    Tangent = mat3(skinned_mat) * tangent;
+    Bitangent = mat3(skinned_mat) * bitangent;
+
+    gl_Position = projection_mat * view_mat * worldPosition;
+}
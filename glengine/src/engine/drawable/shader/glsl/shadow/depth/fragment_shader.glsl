@@ -0,0 +1,6 @@
+#version 310 es
+precision highp float;
+
+// only the depth is written
+void main() {
+}
@@ -0,0 +1,27 @@
+#version 310 es
+precision highp float;
+
+#define MAX_BONES 128
+
+layout (location = 0) in vec3 position;
+layout (location = 5) in uvec4 bone_ids;
+layout (location = 6) in vec4 bone_weights;
+
+uniform mat4 model_mat;
+uniform mat4 light_space_mat;
+uniform float skinned;
+
+layout (std140, binding = 6) uniform Bones {
+    mat4 bone_mats[MAX_BONES];
+};
+
+void main() {
+    mat4 skin = mat4(1.0);
+    if (skinned > 0.5 && dot(bone_weights, vec4(1.0)) > 0.0) {
+        skin = mat4(0.0);
+        for (int i = 0; i < 4; ++i) {
+            skin += bone_mats[min(bone_ids[i], uint(MAX_BONES - 1))] * bone_weights[i];
+        }
+    }
+    gl_Position = light_space_mat * model_mat * skin * vec4(position, 1.0);
+}
@@ -2,9 +2,10 @@
 precision highp float;
 
 in vec3 fragCol;
+in vec4 fragTint;
 
 out vec4 FragColor;
 
 void main() {
-    FragColor = vec4(fragCol, 1.0);
+    FragColor = vec4(fragCol, 1.0) * fragTint;
 }
\ No newline at end of file
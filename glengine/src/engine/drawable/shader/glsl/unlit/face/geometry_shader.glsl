@@ -7,8 +7,10 @@ layout (triangles) in;
 layout (triangle_strip, max_vertices = 3) out;
 
 in vec3 Normal[];
+in vec4 Color[];
 
 out vec3 fragCol; // Output normal to fragment shader
+out vec4 fragTint;
 
 void main() {
     // Calculate the normal of the triangle
@@ -17,6 +19,7 @@ void main() {
     vec3 n2 = Normal[2];
     vec3 face_normal = normalize(n0 + n1 + n2);
     fragCol = face_normal;
+    fragTint = Color[0];
 
     // Emit the vertices
     for (int i = 0; i < gl_in.length(); ++i) {
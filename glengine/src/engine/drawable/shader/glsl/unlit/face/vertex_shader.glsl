@@ -3,15 +3,23 @@ precision highp float;
 
 layout (location = 0) in vec3 position;
 layout (location = 1) in vec3 normal;
+// per instance when drawn instanced
+layout (location = 7) in mat4 instance_model_mat;
+layout (location = 11) in vec4 instance_color;
 
 out vec3 Normal;
+out vec4 Color;
 
 uniform mat4 model_mat;
 uniform mat4 view_mat;
 uniform mat4 projection_mat;
+uniform vec4 color;
+uniform float instanced;
 
 void main() {
-    gl_Position = projection_mat * view_mat * model_mat * vec4(position, 1.0);
+    mat4 model = instanced > 0.5 ? instance_model_mat : model_mat;
+    Color = instanced > 0.5 ? instance_color : color;
+    gl_Position = projection_mat * view_mat * model * vec4(position, 1.0);
     // Transform vertex position
     Normal = normal;
 }
\ No newline at end of file
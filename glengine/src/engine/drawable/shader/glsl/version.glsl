@@ -0,0 +1,3 @@
+#version 310 es
+precision highp float;
+
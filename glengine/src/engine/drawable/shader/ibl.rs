@@ -0,0 +1,38 @@
+use crate::engine::drawable::shader::Shader;
+use crate::result::EngineRenderResult;
+
+// Projects an equirectangular map onto the faces of a cube map.
+pub fn new_equirectangular_shader() -> EngineRenderResult<Shader> {
+    Shader::new(
+        Some(include_str!("glsl/ibl/cube/vertex_shader.glsl")),
+        Some(include_str!("glsl/ibl/equirectangular/fragment_shader.glsl")),
+        None,
+    )
+}
+
+// Convolves the environment into the diffuse irradiance map.
+pub fn new_irradiance_shader() -> EngineRenderResult<Shader> {
+    Shader::new(
+        Some(include_str!("glsl/ibl/cube/vertex_shader.glsl")),
+        Some(include_str!("glsl/ibl/irradiance/fragment_shader.glsl")),
+        None,
+    )
+}
+
+// Prefilters the environment for one roughness level of the specular map.
+pub fn new_prefilter_shader() -> EngineRenderResult<Shader> {
+    Shader::new(
+        Some(include_str!("glsl/ibl/cube/vertex_shader.glsl")),
+        Some(include_str!("glsl/ibl/prefilter/fragment_shader.glsl")),
+        None,
+    )
+}
+
+// Integrates the BRDF lookup table over a screen quad.
+pub fn new_brdf_shader() -> EngineRenderResult<Shader> {
+    Shader::new(
+        Some(include_str!("glsl/screen/vertex_shader.glsl")),
+        Some(include_str!("glsl/ibl/brdf/fragment_shader.glsl")),
+        None,
+    )
+}
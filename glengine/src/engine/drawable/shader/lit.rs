@@ -7,7 +7,66 @@ use std::rc::Rc;
 pub fn new_basic_shader() -> EngineRenderResult<Shader> {
     Shader::new(
         Some(include_str!("glsl/lit/basic/vertex_shader.glsl")),
-        Some(include_str!("glsl/lit/basic/fragment_shader.glsl")),
+        Some(concat!(
+            include_str!("glsl/version.glsl"),
+            include_str!("glsl/lit/lights.glsl"),
+            include_str!("glsl/lit/lighting.glsl"),
+            include_str!("glsl/lit/normal_map.glsl"),
+            include_str!("glsl/lit/basic/fragment_shader.glsl")
+        )),
         None,
     )
-}
\ No newline at end of file
+}
+
+// Sorts the lights into the view space clusters.
+pub fn new_light_culling_shader() -> EngineRenderResult<Shader> {
+    Shader::new_compute(concat!(
+        include_str!("glsl/version.glsl"),
+        include_str!("glsl/lit/lights.glsl"),
+        include_str!("glsl/cluster/compute_shader.glsl")
+    ))
+}
+
+// Metallic/roughness material lit by the scene lights and the image based lighting of the environment.
+pub fn new_pbr_shader() -> EngineRenderResult<Shader> {
+    Shader::new(
+        Some(include_str!("glsl/lit/basic/vertex_shader.glsl")),
+        Some(concat!(
+            include_str!("glsl/version.glsl"),
+            include_str!("glsl/lit/lights.glsl"),
+            include_str!("glsl/lit/lighting.glsl"),
+            include_str!("glsl/lit/normal_map.glsl"),
+            include_str!("glsl/lit/pbr/fragment_shader.glsl")
+        )),
+        None,
+    )
+}
+
+// Lit variants skinning their vertices with the bone matrices of the mesh.
+pub fn new_skinned_shader() -> EngineRenderResult<Shader> {
+    Shader::new(
+        Some(include_str!("glsl/lit/skinned/vertex_shader.glsl")),
+        Some(concat!(
+            include_str!("glsl/version.glsl"),
+            include_str!("glsl/lit/lights.glsl"),
+            include_str!("glsl/lit/lighting.glsl"),
+            include_str!("glsl/lit/normal_map.glsl"),
+            include_str!("glsl/lit/basic/fragment_shader.glsl")
+        )),
+        None,
+    )
+}
+
+pub fn new_skinned_pbr_shader() -> EngineRenderResult<Shader> {
+    Shader::new(
+        Some(include_str!("glsl/lit/skinned/vertex_shader.glsl")),
+        Some(concat!(
+            include_str!("glsl/version.glsl"),
+            include_str!("glsl/lit/lights.glsl"),
+            include_str!("glsl/lit/lighting.glsl"),
+            include_str!("glsl/lit/normal_map.glsl"),
+            include_str!("glsl/lit/pbr/fragment_shader.glsl")
+        )),
+        None,
+    )
+}
@@ -2,17 +2,45 @@ use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 use std::rc::{Rc, Weak};
 use once_cell::unsync::Lazy;
-use crate::engine::drawable::shader::{lit, unlit, Shader};
+use crate::engine::drawable::shader::{deferred, lit, shadow, unlit, Shader};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum IncludedShaderHandle {
     Basic,
     LitColor,
     UnlitFace,
-    UnlitQuad
+    UnlitQuad,
+    ShadowDepth,
+    DeferredGeometry,
+    DeferredLighting,
+    LightCulling,
+    Pbr,
+    LitSkinned,
+    PbrSkinned
 }
 
-#[derive(Clone)]
+impl IncludedShaderHandle {
+    // the variant of a lit shader drawing skinned meshes
+    pub fn skinned(self) -> Self {
+        match self {
+            IncludedShaderHandle::Pbr | IncludedShaderHandle::PbrSkinned => IncludedShaderHandle::PbrSkinned,
+            _ => IncludedShaderHandle::LitSkinned,
+        }
+    }
+
+    // shaders reading the model matrix and color of instanced draws from vertex attributes
+    pub fn supports_instancing(self) -> bool {
+        matches!(
+            self,
+            IncludedShaderHandle::LitColor
+                | IncludedShaderHandle::Pbr
+                | IncludedShaderHandle::UnlitFace
+                | IncludedShaderHandle::DeferredGeometry
+        )
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub enum ShaderHandle{
     Included(IncludedShaderHandle),
     Custom(CustomShaderHandle)
@@ -36,6 +64,32 @@ pub struct CustomShaderHandle{
     handle:usize
 }
 
+// handles are equal when they refer to the same shader
+impl PartialEq for CustomShaderHandle{
+    fn eq(&self, other: &Self) -> bool{
+        self.handle == other.handle
+    }
+}
+
+impl Eq for CustomShaderHandle{}
+
+impl Hash for CustomShaderHandle{
+    fn hash<H: Hasher>(&self, state: &mut H){
+        self.handle.hash(state);
+    }
+}
+
+impl CustomShaderHandle{
+    // index of the shader in the manager
+    pub(crate) fn id(&self)->usize{
+        self.handle
+    }
+    // true when no other handle to the shader exists
+    pub fn is_unique(&self)->bool{
+        Rc::strong_count(&self.rc) == 1
+    }
+}
+
 #[derive(Clone)]
 struct CustomShaderWeakHandle{
     weak: Weak<()>
@@ -99,6 +153,12 @@ impl ShaderManager {
         handle
     }
 
+    // Swaps the shader behind the handle, returning the previous one.
+    pub fn replace(&mut self, handle: &CustomShaderHandle, shader: Shader) -> Option<Shader>{
+        let (old,_) = self.custom.get_mut(&handle.handle)?;
+        Some(std::mem::replace(old, shader))
+    }
+
     pub fn clean(&mut self){
         self.custom.retain(|_,(_,weak)|weak.weak.upgrade().is_some());
     }
@@ -115,6 +175,13 @@ impl Default for ShaderManager {
         included.insert(IncludedShaderHandle::LitColor, lit::new_basic_shader().unwrap());
         included.insert(IncludedShaderHandle::UnlitFace, unlit::new_face_shader().unwrap());
         included.insert(IncludedShaderHandle::UnlitQuad, unlit::new_quad_shader().unwrap());
+        included.insert(IncludedShaderHandle::ShadowDepth, shadow::new_depth_shader().unwrap());
+        included.insert(IncludedShaderHandle::DeferredGeometry, deferred::new_geometry_shader().unwrap());
+        included.insert(IncludedShaderHandle::DeferredLighting, deferred::new_lighting_shader().unwrap());
+        included.insert(IncludedShaderHandle::LightCulling, lit::new_light_culling_shader().unwrap());
+        included.insert(IncludedShaderHandle::Pbr, lit::new_pbr_shader().unwrap());
+        included.insert(IncludedShaderHandle::LitSkinned, lit::new_skinned_shader().unwrap());
+        included.insert(IncludedShaderHandle::PbrSkinned, lit::new_skinned_pbr_shader().unwrap());
         Self{
             included,
             custom: HashMap::new(),
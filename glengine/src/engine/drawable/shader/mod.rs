@@ -1,50 +1,81 @@
 use gl;
 use gl::types::GLenum;
-use glam::Mat4;
+use glam::{Mat3, Mat4, Vec2, Vec3, Vec4};
+use std::collections::{HashMap, HashSet};
 use std::ops::AddAssign;
 use std::sync::RwLock;
+use uniform::Uniform;
 use crate::result::{EngineRenderResult, ShaderError};
 
 pub mod lit;
 pub mod unlit;
 pub mod manager;
+pub mod shadow;
+pub mod deferred;
+pub mod ibl;
+pub mod uniform;
 
 pub struct Shader {
     id: u32,
     texture_count: RwLock<u32>,
+    // locations of the active uniforms found when linking, elements of arrays are added on first use,
+    // with -1 for the ones the program lacks
+    uniforms: RwLock<HashMap<String, i32>>,
+    uniform_blocks: HashMap<String, u32>,
+    // uniforms set without being active, reported once
+    missing: RwLock<HashSet<String>>,
 }
 
 impl Shader {
+    fn create() -> Shader {
+        Shader {
+            id: unsafe { gl::CreateProgram() },
+            texture_count: RwLock::new(0),
+            uniforms: RwLock::new(HashMap::new()),
+            uniform_blocks: HashMap::new(),
+            missing: RwLock::new(HashSet::new()),
+        }
+    }
+
     pub fn new(
         vertex_shader: Option<&str>,
         fragment_shader: Option<&str>,
         geometry_shader: Option<&str>,
     ) -> EngineRenderResult<Shader> {
         // Link shaders
-        let shader = Shader {
-            id: unsafe { gl::CreateProgram() },
-            texture_count: RwLock::new(0),
-        };
+        let mut shader = Shader::create();
+        if let Some(vertex_shader_path) = vertex_shader {
+            shader.compile_and_attach_shader(vertex_shader_path, gl::VERTEX_SHADER)?;
+        }
+        if let Some(fragment_shader_path) = fragment_shader {
+            shader.compile_and_attach_shader(fragment_shader_path, gl::FRAGMENT_SHADER)?;
+        }
+        if let Some(geometry_shader_path) = geometry_shader {
+            shader.compile_and_attach_shader(geometry_shader_path, gl::GEOMETRY_SHADER)?;
+        }
+        shader.link()?;
+        Ok(shader)
+    }
+
+    pub fn new_compute(compute_shader: &str) -> EngineRenderResult<Shader> {
+        let mut shader = Shader::create();
+        shader.compile_and_attach_shader(compute_shader, gl::COMPUTE_SHADER)?;
+        shader.link()?;
+        Ok(shader)
+    }
+
+    fn link(&mut self) -> EngineRenderResult<()> {
         unsafe {
-            if let Some(vertex_shader_path) = vertex_shader {
-                shader.compile_and_attach_shader(vertex_shader_path, gl::VERTEX_SHADER)?;
-            }
-            if let Some(fragment_shader_path) = fragment_shader {
-                shader.compile_and_attach_shader(fragment_shader_path, gl::FRAGMENT_SHADER)?;
-            }
-            if let Some(geometry_shader_path) = geometry_shader {
-                shader.compile_and_attach_shader(geometry_shader_path, gl::GEOMETRY_SHADER)?;
-            }
-            gl::LinkProgram(shader.id);
+            gl::LinkProgram(self.id);
             //check error
             let mut success = 0;
-            gl::GetProgramiv(shader.id, gl::LINK_STATUS, &mut success);
+            gl::GetProgramiv(self.id, gl::LINK_STATUS, &mut success);
             if success == 0 {
                 let mut len = 0;
-                gl::GetProgramiv(shader.id, gl::INFO_LOG_LENGTH, &mut len);
+                gl::GetProgramiv(self.id, gl::INFO_LOG_LENGTH, &mut len);
                 let mut buffer = vec![0; len as usize];
                 gl::GetProgramInfoLog(
-                    shader.id,
+                    self.id,
                     len,
                     std::ptr::null_mut(),
                     buffer.as_mut_ptr() as *mut i8,
@@ -56,8 +87,67 @@ impl Shader {
                 return Err(ShaderError::LinkError.into());
             }
         }
-        Ok(shader)
+        self.introspect();
+        Ok(())
     }
+
+    // Caches the locations of the active uniforms and the indices of the uniform blocks.
+    fn introspect(&mut self) {
+        let mut uniforms = HashMap::new();
+        let mut uniform_blocks = HashMap::new();
+        unsafe {
+            let mut count = 0;
+            let mut max_length = 0;
+            gl::GetProgramiv(self.id, gl::ACTIVE_UNIFORMS, &mut count);
+            gl::GetProgramiv(self.id, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_length);
+            let mut buffer = vec![0u8; max_length.max(1) as usize];
+            for index in 0..count.max(0) as u32 {
+                let (mut length, mut size, mut kind) = (0, 0, 0);
+                gl::GetActiveUniform(
+                    self.id,
+                    index,
+                    buffer.len() as i32,
+                    &mut length,
+                    &mut size,
+                    &mut kind,
+                    buffer.as_mut_ptr() as *mut i8,
+                );
+                let name = String::from_utf8_lossy(&buffer[..length as usize]).into_owned();
+                let name_cstring = std::ffi::CString::new(name.as_str()).unwrap();
+                let location = gl::GetUniformLocation(self.id, name_cstring.as_ptr());
+                // members of uniform blocks have no location
+                if location < 0 {
+                    continue;
+                }
+                // arrays are listed by their first element, setting them by name starts there too
+                if let Some(array) = name.strip_suffix("[0]") {
+                    uniforms.insert(array.to_string(), location);
+                }
+                uniforms.insert(name, location);
+            }
+
+            gl::GetProgramiv(self.id, gl::ACTIVE_UNIFORM_BLOCKS, &mut count);
+            gl::GetProgramiv(self.id, gl::ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH, &mut max_length);
+            let mut buffer = vec![0u8; max_length.max(1) as usize];
+            for index in 0..count.max(0) as u32 {
+                let mut length = 0;
+                gl::GetActiveUniformBlockName(
+                    self.id,
+                    index,
+                    buffer.len() as i32,
+                    &mut length,
+                    buffer.as_mut_ptr() as *mut i8,
+                );
+                let name = String::from_utf8_lossy(&buffer[..length as usize]).into_owned();
+                uniform_blocks.insert(name, index);
+            }
+        }
+        if let Ok(mut cached) = self.uniforms.try_write() {
+            *cached = uniforms;
+        }
+        self.uniform_blocks = uniform_blocks;
+    }
+
     fn compile_shader(source: &str, shader_type: GLenum) -> EngineRenderResult<u32> {
         let id = unsafe { gl::CreateShader(shader_type) };
         unsafe {
@@ -116,17 +206,66 @@ impl Shader {
         }
     }
 
+    // Location of an active uniform, elements of arrays past the first are looked up once, found or not.
+    fn location(&self, name: &str) -> Option<i32> {
+        if let Some(location) = self.uniforms.try_read().ok().and_then(|uniforms| uniforms.get(name).copied()) {
+            return (location >= 0).then_some(location);
+        }
+        if !name.contains('[') {
+            return None;
+        }
+        let name_cstring = std::ffi::CString::new(name).unwrap();
+        let location = unsafe { gl::GetUniformLocation(self.id, name_cstring.as_ptr()) };
+        if let Ok(mut uniforms) = self.uniforms.try_write() {
+            uniforms.insert(name.to_string(), location.max(-1));
+        }
+        (location >= 0).then_some(location)
+    }
+
+    fn report_missing(&self, name: &str) {
+        if let Ok(mut missing) = self.missing.try_write() {
+            if missing.insert(name.to_string()) {
+                println!("Shader {} has no active uniform {}", self.id, name);
+            }
+        }
+    }
+
+    pub fn has_uniform(&self, name: &str) -> bool {
+        self.location(name).is_some()
+    }
+
+    // Sets a uniform of the program in use, a uniform the shader does not have is reported once.
+    pub fn set<T: Uniform + ?Sized>(&self, name: &str, value: &T) {
+        match self.location(name) {
+            Some(location) => value.upload(location),
+            None => self.report_missing(name),
+        }
+    }
+
+    // For uniforms only some of the shaders drawing a value declare, returns whether it was set.
+    pub fn set_optional<T: Uniform + ?Sized>(&self, name: &str, value: &T) -> bool {
+        match self.location(name) {
+            Some(location) => {
+                value.upload(location);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn set_texture(&self, name: &str, texture: u32, index: u32, texture_type: GLenum) {
         unsafe {
-            let name_cstring = std::ffi::CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.id, name_cstring.as_ptr());
             gl::ActiveTexture(gl::TEXTURE0 + index);
             gl::BindTexture(texture_type, texture);
-            gl::Uniform1i(location, index as i32);
         }
+        self.set_int(name, index as i32);
     }
 
+    // Binds the texture to the next unit, samplers the shader does not have take no unit.
     pub fn add_texture(&self, name: &str, texture: u32, texture_type: GLenum) {
+        if !self.has_uniform(name) {
+            return;
+        }
         if let Ok(mut count) = self.texture_count.try_write(){
             self.set_texture(name, texture, *count, texture_type);
             count.add_assign(1);
@@ -134,40 +273,76 @@ impl Shader {
     }
     
     pub fn reset_texture_count(&self) {
+        self.set_texture_count(0);
+    }
+
+    // units below the count keep their textures, add_texture binds from it
+    pub fn texture_count(&self) -> u32 {
+        self.texture_count.try_read().map(|count| *count).unwrap_or(0)
+    }
+
+    pub fn set_texture_count(&self, value: u32) {
         if let Ok(mut count) = self.texture_count.try_write(){
-            *count = 0;
+            *count = value;
         }
     }
 
     pub fn set_float(&self, name: &str, value: f32) {
-        unsafe {
-            let name_cstring = std::ffi::CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.id, name_cstring.as_ptr());
-            gl::Uniform1f(location, value);
-        }
+        self.set(name, &value);
     }
 
-    pub fn set_vec3(&self, name: &str, vec: &glam::Vec3) {
-        unsafe {
-            let name_cstring = std::ffi::CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.id, name_cstring.as_ptr());
-            gl::Uniform3fv(location, 1, vec.as_ref().as_ptr());
-        }
+    pub fn set_int(&self, name: &str, value: i32) {
+        self.set(name, &value);
+    }
+
+    pub fn set_bool(&self, name: &str, value: bool) {
+        self.set(name, &value);
+    }
+
+    pub fn set_vec2(&self, name: &str, vec: &Vec2) {
+        self.set(name, vec);
+    }
+
+    pub fn set_vec3(&self, name: &str, vec: &Vec3) {
+        self.set(name, vec);
+    }
+
+    pub fn set_vec4(&self, name: &str, vec: &Vec4) {
+        self.set(name, vec);
+    }
+
+    pub fn set_mat3(&self, name: &str, mat: &Mat3) {
+        self.set(name, mat);
     }
 
     pub fn set_mat4(&self, name: &str, mat: &Mat4) {
-        unsafe {
-            let name_cstring = std::ffi::CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.id, name_cstring.as_ptr());
-            gl::UniformMatrix4fv(location, 1, gl::FALSE, mat.as_ref().as_ptr());
-        }
+        self.set(name, mat);
+    }
+
+    pub fn set_float_array(&self, name: &str, values: &[f32]) {
+        self.set(name, values);
+    }
+
+    pub fn set_int_array(&self, name: &str, values: &[i32]) {
+        self.set(name, values);
+    }
+
+    pub fn set_vec3_array(&self, name: &str, values: &[Vec3]) {
+        self.set(name, values);
+    }
+
+    pub fn set_vec4_array(&self, name: &str, values: &[Vec4]) {
+        self.set(name, values);
+    }
+
+    pub fn set_mat4_array(&self, name: &str, mats: &[Mat4]) {
+        self.set(name, mats);
     }
 
     pub fn set_uniform_block(&self, name: &str, binding: u32) {
-        unsafe {
-            let name_cstring = std::ffi::CString::new(name).unwrap();
-            let index = gl::GetUniformBlockIndex(self.id, name_cstring.as_ptr());
-            gl::UniformBlockBinding(self.id, index, binding);
+        match self.uniform_blocks.get(name) {
+            Some(index) => unsafe { gl::UniformBlockBinding(self.id, *index, binding) },
+            None => self.report_missing(name),
         }
     }
 }
@@ -0,0 +1,10 @@
+use crate::engine::drawable::shader::Shader;
+use crate::result::EngineRenderResult;
+
+pub fn new_depth_shader() -> EngineRenderResult<Shader> {
+    Shader::new(
+        Some(include_str!("glsl/shadow/depth/vertex_shader.glsl")),
+        Some(include_str!("glsl/shadow/depth/fragment_shader.glsl")),
+        None,
+    )
+}
@@ -0,0 +1,99 @@
+use gl;
+use glam::{Mat3, Mat4, Vec2, Vec3, Vec4};
+
+// Values Shader::set can upload, slices set arrays from their first element.
+pub trait Uniform {
+    // the location is one of the program in use
+    fn upload(&self, location: i32);
+}
+
+impl Uniform for f32 {
+    fn upload(&self, location: i32) {
+        unsafe { gl::Uniform1f(location, *self) }
+    }
+}
+
+impl Uniform for i32 {
+    fn upload(&self, location: i32) {
+        unsafe { gl::Uniform1i(location, *self) }
+    }
+}
+
+impl Uniform for u32 {
+    fn upload(&self, location: i32) {
+        unsafe { gl::Uniform1ui(location, *self) }
+    }
+}
+
+// GLSL bools are set as ints
+impl Uniform for bool {
+    fn upload(&self, location: i32) {
+        unsafe { gl::Uniform1i(location, *self as i32) }
+    }
+}
+
+impl Uniform for Vec2 {
+    fn upload(&self, location: i32) {
+        unsafe { gl::Uniform2fv(location, 1, self.as_ref().as_ptr()) }
+    }
+}
+
+impl Uniform for Vec3 {
+    fn upload(&self, location: i32) {
+        unsafe { gl::Uniform3fv(location, 1, self.as_ref().as_ptr()) }
+    }
+}
+
+impl Uniform for Vec4 {
+    fn upload(&self, location: i32) {
+        unsafe { gl::Uniform4fv(location, 1, self.as_ref().as_ptr()) }
+    }
+}
+
+impl Uniform for Mat3 {
+    fn upload(&self, location: i32) {
+        unsafe { gl::UniformMatrix3fv(location, 1, gl::FALSE, self.as_ref().as_ptr()) }
+    }
+}
+
+impl Uniform for Mat4 {
+    fn upload(&self, location: i32) {
+        unsafe { gl::UniformMatrix4fv(location, 1, gl::FALSE, self.as_ref().as_ptr()) }
+    }
+}
+
+impl Uniform for [f32] {
+    fn upload(&self, location: i32) {
+        unsafe { gl::Uniform1fv(location, self.len() as i32, self.as_ptr()) }
+    }
+}
+
+impl Uniform for [i32] {
+    fn upload(&self, location: i32) {
+        unsafe { gl::Uniform1iv(location, self.len() as i32, self.as_ptr()) }
+    }
+}
+
+impl Uniform for [Vec2] {
+    fn upload(&self, location: i32) {
+        unsafe { gl::Uniform2fv(location, self.len() as i32, self.as_ptr() as *const f32) }
+    }
+}
+
+impl Uniform for [Vec3] {
+    fn upload(&self, location: i32) {
+        unsafe { gl::Uniform3fv(location, self.len() as i32, self.as_ptr() as *const f32) }
+    }
+}
+
+impl Uniform for [Vec4] {
+    fn upload(&self, location: i32) {
+        unsafe { gl::Uniform4fv(location, self.len() as i32, self.as_ptr() as *const f32) }
+    }
+}
+
+impl Uniform for [Mat4] {
+    fn upload(&self, location: i32) {
+        unsafe { gl::UniformMatrix4fv(location, self.len() as i32, gl::FALSE, self.as_ptr() as *const f32) }
+    }
+}
@@ -0,0 +1,105 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use gl::types::{GLsizeiptr, GLuint};
+use glam::Mat4;
+
+// must match the skinned vertex shaders
+pub const MAX_BONES: usize = 128;
+pub const BONES_BINDING: u32 = 6;
+
+pub struct Bone {
+    // name of the node moving the bone
+    pub name: String,
+    // from the mesh space to the bone space in the bind pose
+    pub offset: Mat4,
+}
+
+struct SkinPose {
+    matrices: Vec<Mat4>,
+    ubo: GLuint,
+    dirty: bool,
+}
+
+impl Drop for SkinPose {
+    fn drop(&mut self) {
+        if self.ubo != 0 {
+            unsafe {
+                gl::DeleteBuffers(1, &self.ubo);
+            }
+        }
+    }
+}
+
+// Bones of a skinned mesh and their current pose, shared by the clones of its DrawData.
+#[derive(Clone)]
+pub struct Skin {
+    pub bones: Rc<Vec<Bone>>,
+    pose: Rc<RefCell<SkinPose>>,
+}
+
+impl Skin {
+    pub fn new(bones: Vec<Bone>) -> Self {
+        if bones.len() > MAX_BONES {
+            println!("Skin has {} bones, only the first {} are animated", bones.len(), MAX_BONES);
+        }
+        Self::shared(Rc::new(bones))
+    }
+
+    // Shares the bones of another skin but has its own bind pose, unlike clones which are posed together.
+    pub fn shared(bones: Rc<Vec<Bone>>) -> Self {
+        let pose = SkinPose {
+            matrices: vec![Mat4::IDENTITY; bones.len().min(MAX_BONES)],
+            ubo: 0,
+            dirty: true,
+        };
+        Self {
+            bones,
+            pose: Rc::new(RefCell::new(pose)),
+        }
+    }
+
+    // Sets the matrix of every bone from the mesh space of the bind pose to the posed mesh space,
+    // bones without a matrix keep the bind pose.
+    pub fn set_pose<F: Fn(&Bone) -> Option<Mat4>>(&self, bone_matrix: F) {
+        let mut pose = self.pose.borrow_mut();
+        for (matrix, bone) in pose.matrices.iter_mut().zip(self.bones.iter()) {
+            *matrix = bone_matrix(bone).unwrap_or(Mat4::IDENTITY);
+        }
+        pose.dirty = true;
+    }
+
+    // Uploads the pose when it changed and binds it to the bones uniform block.
+    pub fn bind(&self) {
+        let mut pose = self.pose.borrow_mut();
+        unsafe {
+            if pose.ubo == 0 {
+                gl::GenBuffers(1, &mut pose.ubo);
+                gl::BindBuffer(gl::UNIFORM_BUFFER, pose.ubo);
+                gl::BufferData(
+                    gl::UNIFORM_BUFFER,
+                    (MAX_BONES * std::mem::size_of::<Mat4>()) as GLsizeiptr,
+                    std::ptr::null(),
+                    gl::DYNAMIC_DRAW,
+                );
+            }
+            if pose.dirty {
+                gl::BindBuffer(gl::UNIFORM_BUFFER, pose.ubo);
+                gl::BufferSubData(
+                    gl::UNIFORM_BUFFER,
+                    0,
+                    (pose.matrices.len() * std::mem::size_of::<Mat4>()) as GLsizeiptr,
+                    pose.matrices.as_ptr() as *const _,
+                );
+                pose.dirty = false;
+            }
+            gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+            gl::BindBufferBase(gl::UNIFORM_BUFFER, BONES_BINDING, pose.ubo);
+        }
+    }
+
+    pub fn unbind() {
+        unsafe {
+            gl::BindBufferBase(gl::UNIFORM_BUFFER, BONES_BINDING, 0);
+        }
+    }
+}
@@ -1,7 +1,8 @@
+use crate::engine::config::{RenderPath, CONFIG};
 use crate::engine::drawable;
 use crate::engine::drawable::DrawData;
 use crate::engine::scene::Scene;
-use gl::types::GLuint;
+use gl::types::{GLenum, GLuint};
 
 pub struct Fbo {
     pub fbo: GLuint,
@@ -158,24 +159,170 @@ impl Drop for Fbo {
     }
 }
 
+// Single sampled render targets of the deferred geometry pass.
+pub struct GBuffer {
+    pub fbo: GLuint,
+    pub position_texture: GLuint,
+    pub normal_texture: GLuint,
+    pub albedo_texture: GLuint,
+    pub specular_texture: GLuint,
+    pub depth_stencil_texture: GLuint,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl GBuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        let mut fbo = 0;
+        let mut depth_stencil_texture = 0;
+        let color_texture = |attachment: GLenum| {
+            let mut texture = 0;
+            unsafe {
+                gl::GenTextures(1, &mut texture);
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::TexImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    gl::RGBA16F as i32,
+                    width as i32,
+                    height as i32,
+                    0,
+                    gl::RGBA,
+                    gl::FLOAT,
+                    std::ptr::null(),
+                );
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, attachment, gl::TEXTURE_2D, texture, 0);
+            }
+            texture
+        };
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+        }
+        let position_texture = color_texture(gl::COLOR_ATTACHMENT0);
+        let normal_texture = color_texture(gl::COLOR_ATTACHMENT1);
+        let albedo_texture = color_texture(gl::COLOR_ATTACHMENT2);
+        let specular_texture = color_texture(gl::COLOR_ATTACHMENT3);
+        unsafe {
+            let draw_buffers = [
+                gl::COLOR_ATTACHMENT0,
+                gl::COLOR_ATTACHMENT1,
+                gl::COLOR_ATTACHMENT2,
+                gl::COLOR_ATTACHMENT3,
+            ];
+            gl::DrawBuffers(draw_buffers.len() as i32, draw_buffers.as_ptr());
+
+            gl::GenTextures(1, &mut depth_stencil_texture);
+            gl::BindTexture(gl::TEXTURE_2D, depth_stencil_texture);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::DEPTH32F_STENCIL8 as i32,
+                width as i32,
+                height as i32,
+                0,
+                gl::DEPTH_STENCIL,
+                gl::FLOAT_32_UNSIGNED_INT_24_8_REV,
+                std::ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::DEPTH_STENCIL_ATTACHMENT, gl::TEXTURE_2D, depth_stencil_texture, 0);
+
+            if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                panic!("Framebuffer is not complete");
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        Self {
+            fbo,
+            position_texture,
+            normal_texture,
+            albedo_texture,
+            specular_texture,
+            depth_stencil_texture,
+            width,
+            height,
+        }
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.width as i32, self.height as i32);
+        }
+    }
+
+    // clears every target to zero regardless of the clear color, pixels without geometry keep a zero coverage
+    pub fn clear(&self) {
+        let zero = [0.0f32; 4];
+        unsafe {
+            for draw_buffer in 0..4 {
+                gl::ClearBufferfv(gl::COLOR, draw_buffer, zero.as_ptr());
+            }
+            gl::Clear(gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
+        }
+    }
+}
+
+impl Drop for GBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteTextures(1, &self.position_texture);
+            gl::DeleteTextures(1, &self.normal_texture);
+            gl::DeleteTextures(1, &self.albedo_texture);
+            gl::DeleteTextures(1, &self.specular_texture);
+            gl::DeleteTextures(1, &self.depth_stencil_texture);
+        }
+    }
+}
+
+pub struct DeferredFbo {
+    pub gbuffer: GBuffer,
+    pub draw_data: DrawData,
+}
+
+impl DeferredFbo {
+    pub fn new(width: u32, height: u32) -> DeferredFbo {
+        let gbuffer = GBuffer::new(width, height);
+        let draw_data = drawable::lighting_quad(&gbuffer);
+        DeferredFbo {
+            gbuffer,
+            draw_data,
+        }
+    }
+}
+
 pub struct ScreenFbo {
     pub fbo:Fbo,
-    pub draw_data:DrawData
+    pub draw_data:DrawData,
+    pub deferred:Option<DeferredFbo>
 }
 impl ScreenFbo{
     pub fn new(width:u32,height:u32,samples:i32)->ScreenFbo{
         let fbo = Fbo::new(width, height,samples); // Limit multi-sampling to supported max samples
         let draw_data = drawable::screenquad(&fbo);
+        let deferred = match CONFIG.config().get_render_path() {
+            RenderPath::Deferred => Some(DeferredFbo::new(width, height)),
+            RenderPath::Forward => None,
+        };
         ScreenFbo{
             fbo,
-            draw_data
+            draw_data,
+            deferred
         }
     }
-    pub fn render(&self, scene:&mut Scene) {
+    pub fn render(&mut self, scene:&mut Scene) {
         unsafe {
             self.fbo.bind();
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
-            scene .render();
+            match &mut self.deferred {
+                Some(deferred) => scene.render_deferred(deferred, &self.fbo),
+                None => scene.render(),
+            }
             gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo.fbo);
             gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, 0);
             self.fbo.blit();
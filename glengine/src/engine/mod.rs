@@ -9,12 +9,15 @@ use glfw::{
 
 
 use crate::engine::config::CONFIG;
+use crate::engine::drawable::asset_server::ASSET_SERVER;
 use crate::engine::drawable::Drawable;
+use crate::engine::drawable::importer::loader::ASSET_LOADER;
 use crate::engine::events::EngineInputsState;
 use crate::engine::events::EngineWindowEvent;
 use crate::engine::fbo::ScreenFbo;
 use crate::engine::physics::PhysicsData;
 use crate::engine::scene::gameobject::GameObject;
+use crate::engine::scene::lights::cluster::check_storage_blocks;
 use crate::engine::scene::Scene;
 use crate::engine::timedelta::ToFps;
 use crate::result::{EngineRenderResult, EngineRunError, EngineRunResult, EngineStepResult};
@@ -28,7 +31,7 @@ pub mod timedelta;
 pub mod scene;
 pub mod transform;
 mod fps;
-mod physics;
+pub mod physics;
 // lazy_static! {
 //     pub static ref vr_context: Mutex<openvr::Context> =
 //         unsafe { Mutex::new(openvr::init(openvr::ApplicationType::Scene).unwrap()) };
@@ -39,6 +42,7 @@ mod physics;
 
 pub struct GameState {
     pub input_state: EngineInputsState,
+    pub physics: PhysicsData,
     delta: Duration,
 }
 
@@ -79,6 +83,7 @@ impl Default for GameData {
             scene: None,
             state: GameState {
                 input_state: EngineInputsState::new(),
+                physics: PhysicsData::default(),
                 delta: Duration::new(0, 0),
             },
             should_close: false,
@@ -90,13 +95,12 @@ pub struct Engine {
     game: GameData,
     events: GlfwReceiver<(f64, WindowEvent)>,
     glfw: Glfw,
-    physics:PhysicsData,
 }
 
 impl Engine {
     pub fn new() -> Self {
         let mut glfw = glfw::init(glfw::fail_on_errors).unwrap();
-        glfw.window_hint(WindowHint::ContextVersion(3, 0));
+        glfw.window_hint(WindowHint::ContextVersion(3, 1));
         glfw.window_hint(WindowHint::RefreshRate(None));
         glfw.window_hint(WindowHint::CocoaGraphicsSwitching(false));
         glfw.window_hint(WindowHint::OpenGlForwardCompat(true));
@@ -175,7 +179,6 @@ impl Engine {
             game,
             events,
             glfw,
-            physics:PhysicsData::default(),
         }
     }
 
@@ -201,9 +204,20 @@ impl Engine {
             self.step(delta)
                 .map_err(|err|EngineRunError::StepError(err))?;
             fps.push(delta.to_fps());
-            self.window.set_title(&format!("FPS: {:.2}", fps.average()));
+            let title = match &self.game.scene {
+                Some(scene) if CONFIG.config().get_frustum_culling() => format!(
+                    "FPS: {:.2} drawn: {} culled: {}",
+                    fps.average(),
+                    scene.culling.drawn,
+                    scene.culling.culled
+                ),
+                _ => format!("FPS: {:.2}", fps.average()),
+            };
+            self.window.set_title(&title);
             self.fixed_step(fixed_step_interval,&mut fixed_step_elapsed)
                 .map_err(|err|EngineRunError::FixedStepError(err))?;
+            ASSET_LOADER.with(|loader| loader.borrow_mut().update());
+            ASSET_SERVER.with(|server| server.borrow_mut().update());
             self.render(&mut mainfbo, &mut render_ctx);
             if self.game.should_close {
                 break;
@@ -215,13 +229,14 @@ impl Engine {
         &mut self,
     ) -> EngineRenderResult<()> {
         self.window.make_current();
+        check_storage_blocks()?;
         if let Some(scene) = &mut self.game.scene{
             scene.init_gl()?;
         }
         unsafe {
             // gl::Enable(gl::MULTISAMPLE); // Enable multi-sampling
-            gl::Enable(gl::BLEND); // Enable blending for better anti-aliasing
-            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA); // Set blending function
+            // blending, depth writes and culling are set per material, these are the opaque defaults
+            gl::Disable(gl::BLEND);
             gl::Enable(gl::DEPTH_TEST);
             gl::Enable(gl::CULL_FACE);
             gl::CullFace(gl::BACK);
@@ -275,7 +290,7 @@ impl Engine {
             let mut rigid_bodies = vec![];
             self.game.fixed_step(fixed_step_interval,&mut rigid_bodies)?;
             *fixed_step_elapsed += fixed_step_interval;
-            // self.physics.step(&mut rigid_bodies);
+            self.game.state.physics.step(&rigid_bodies)?;
         }
         Ok(())
     }
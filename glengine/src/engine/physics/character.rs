@@ -0,0 +1,38 @@
+use glam::Vec3;
+use rapier3d::control::{EffectiveCharacterMovement, KinematicCharacterController};
+use rapier3d::prelude::*;
+use crate::engine::physics::query::QueryOptions;
+use crate::engine::physics::PhysicsData;
+
+impl PhysicsData {
+    pub fn rigid_body(&self, handle: RigidBodyHandle) -> Option<&RigidBody> {
+        self.rigid_body_set.get(handle)
+    }
+
+    pub fn collider(&self, handle: ColliderHandle) -> Option<&Collider> {
+        self.collider_set.get(handle)
+    }
+
+    // Resolves the desired movement of the collider against the world without moving it.
+    pub fn move_character(
+        &self,
+        controller: &KinematicCharacterController,
+        dt: f32,
+        collider: ColliderHandle,
+        desired_translation: Vec3,
+        options: &QueryOptions,
+    ) -> Option<EffectiveCharacterMovement> {
+        let collider = self.collider_set.get(collider)?;
+        Some(controller.move_shape(
+            dt,
+            &self.rigid_body_set,
+            &self.collider_set,
+            &self.query_pipeline,
+            collider.shape(),
+            collider.position(),
+            desired_translation.into(),
+            options.filter(),
+            |_| {},
+        ))
+    }
+}
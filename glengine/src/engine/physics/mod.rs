@@ -0,0 +1,417 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::{Rc, Weak};
+use std::sync::Mutex;
+use glam::{Mat4, Vec3};
+use rapier3d::na::Vector3;
+use rapier3d::prelude::*;
+use crate::engine::config::CONFIG;
+use crate::engine::scene::gameobject::base::BaseGameObject;
+use crate::engine::scene::gameobject::components::collider::ColliderComponent;
+use crate::engine::scene::gameobject::components::joint::{JointComponent, JointHandle};
+use crate::engine::scene::gameobject::components::rigidbody::RigidBodyComponent;
+use crate::engine::scene::gameobject::GameObject;
+use crate::engine::transform::Transform;
+use crate::result::EngineFixedStepResult;
+
+pub mod character;
+pub mod query;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ContactPoint {
+    pub point: Vec3,
+    // points from the object receiving the event towards the other object
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl ContactPoint {
+    fn flipped(&self) -> Self {
+        Self {
+            normal: -self.normal,
+            ..*self
+        }
+    }
+}
+
+pub struct Collision {
+    pub other: GameObject,
+    pub contacts: Vec<ContactPoint>,
+}
+
+pub struct ContactForce {
+    pub other: GameObject,
+    pub total_force: Vec3,
+    pub total_force_magnitude: f32,
+    pub max_force_direction: Vec3,
+    pub max_force_magnitude: f32,
+}
+
+pub enum PhysicsEvent {
+    CollisionEnter(Collision),
+    CollisionExit(Collision),
+    TriggerEnter(GameObject),
+    TriggerExit(GameObject),
+    ContactForce(ContactForce),
+}
+
+fn contact_points(contact_pair: &ContactPair) -> Vec<ContactPoint> {
+    contact_pair
+        .manifolds
+        .iter()
+        .flat_map(|manifold| {
+            let normal: Vec3 = manifold.data.normal.into();
+            manifold
+                .data
+                .solver_contacts
+                .iter()
+                .map(move |contact| ContactPoint {
+                    point: contact.point.coords.into(),
+                    normal,
+                    distance: contact.dist,
+                })
+        })
+        .collect()
+}
+
+// Rapier may call the handler from its worker threads, events are buffered
+// here and dispatched to the components once the step is done.
+#[derive(Default)]
+struct PhysicsEventCollector {
+    collisions: Mutex<Vec<(CollisionEvent, Vec<ContactPoint>)>>,
+    contact_forces: Mutex<Vec<ContactForceEvent>>,
+}
+
+impl EventHandler for PhysicsEventCollector {
+    fn handle_collision_event(
+        &self,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        event: CollisionEvent,
+        contact_pair: Option<&ContactPair>,
+    ) {
+        let contacts = contact_pair.map(contact_points).unwrap_or_default();
+        self.collisions.lock().unwrap().push((event, contacts));
+    }
+
+    fn handle_contact_force_event(
+        &self,
+        dt: Real,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        contact_pair: &ContactPair,
+        total_force_magnitude: Real,
+    ) {
+        self.contact_forces
+            .lock()
+            .unwrap()
+            .push(ContactForceEvent::from_contact_pair(dt, contact_pair, total_force_magnitude));
+    }
+}
+
+pub struct PhysicsData {
+    gravity: Vector3<f32>,
+    physics_pipeline: PhysicsPipeline,
+    integration_parameters: IntegrationParameters,
+    island_manager: IslandManager,
+    broad_phase: BroadPhaseMultiSap,
+    narrow_phase: NarrowPhase,
+    rigid_body_set: RigidBodySet,
+    collider_set: ColliderSet,
+    impulse_joint_set: ImpulseJointSet,
+    multibody_joint_set: MultibodyJointSet,
+    ccd_solver: CCDSolver,
+    query_pipeline: QueryPipeline,
+    objects: HashMap<RigidBodyHandle, Weak<RefCell<BaseGameObject>>>,
+    event_collector: PhysicsEventCollector,
+}
+
+fn set_body_transform(rigid_body: &mut RigidBody, transform: &Transform) {
+    let position = Isometry::from_parts(transform.position.into(), transform.rotation.into());
+    if rigid_body.is_kinematic() {
+        rigid_body.set_next_kinematic_position(position);
+    } else {
+        rigid_body.set_position(position, true);
+    }
+}
+
+impl PhysicsData {
+    pub fn step(&mut self, game_objects: &Vec<GameObject>) -> EngineFixedStepResult<()> {
+        let mapped = game_objects
+            .iter()
+            .map(|obj| (self.sync_object(obj), obj))
+            .collect::<Vec<_>>();
+        self.remove_absent(&mapped.iter().map(|(handle, _)| *handle).collect());
+        for (_, obj) in mapped.iter() {
+            self.sync_joints(obj);
+        }
+        self.physics_pipeline.step(
+            &self.gravity,
+            &self.integration_parameters,
+            &mut self.island_manager,
+            &mut self.broad_phase,
+            &mut self.narrow_phase,
+            &mut self.rigid_body_set,
+            &mut self.collider_set,
+            &mut self.impulse_joint_set,
+            &mut self.multibody_joint_set,
+            &mut self.ccd_solver,
+            Some(&mut self.query_pipeline),
+            &(),
+            &self.event_collector,
+        );
+        for (rigid_body_handle, obj) in mapped {
+            let Some(rigid_body) = self.rigid_body_set.get(rigid_body_handle) else {
+                continue;
+            };
+            if rigid_body.is_fixed() || rigid_body.is_sleeping() {
+                continue;
+            }
+            let (parent_mat, scale) = {
+                let base = obj.base.borrow();
+                let parent_mat = base
+                    .data
+                    .parent
+                    .as_ref()
+                    .map(|parent| parent.global_mat())
+                    .unwrap_or(Mat4::IDENTITY);
+                (parent_mat, base.data.transform.scale)
+            };
+            // the body has no scale, the one of the object is put back before going to local space
+            let global_scale = (parent_mat * Mat4::from_scale(scale)).to_scale_rotation_translation().0;
+            let global_mat = Mat4::from_scale_rotation_translation(
+                global_scale,
+                rigid_body.rotation().quaternion().clone().into(),
+                rigid_body.translation().clone().into(),
+            );
+            let local: Transform = (parent_mat.inverse() * global_mat).into();
+
+            let mut base = obj.base.borrow_mut();
+            let BaseGameObject { data, components } = &mut *base;
+            if let Some(comp) = components.get_component::<RigidBodyComponent>() {
+                comp.borrow_mut().rigid_body.set_position(*rigid_body.position(), false);
+            }
+            // the scale is left as it was set
+            data.transform.position = local.position;
+            data.transform.rotation = local.rotation;
+            components.sync_transform_hash(data);
+        }
+        self.dispatch_events()
+    }
+
+    fn object_of(&self, collider: ColliderHandle) -> Option<GameObject> {
+        let rigid_body = self.collider_set.get(collider)?.parent()?;
+        let base = self.objects.get(&rigid_body)?.upgrade()?;
+        Some(GameObject { base })
+    }
+
+    fn dispatch_events(&mut self) -> EngineFixedStepResult<()> {
+        let collisions = std::mem::take(&mut *self.event_collector.collisions.lock().unwrap());
+        for (event, contacts) in collisions {
+            let (Some(obj1), Some(obj2)) = (self.object_of(event.collider1()), self.object_of(event.collider2())) else {
+                continue;
+            };
+            let flipped = contacts.iter().map(ContactPoint::flipped).collect();
+            let collision1 = Collision { other: obj2.clone(), contacts };
+            let collision2 = Collision { other: obj1.clone(), contacts: flipped };
+            let (event1, event2) = match (event.started(), event.sensor()) {
+                (true, true) => (PhysicsEvent::TriggerEnter(obj2.clone()), PhysicsEvent::TriggerEnter(obj1.clone())),
+                (false, true) => (PhysicsEvent::TriggerExit(obj2.clone()), PhysicsEvent::TriggerExit(obj1.clone())),
+                (true, false) => (PhysicsEvent::CollisionEnter(collision1), PhysicsEvent::CollisionEnter(collision2)),
+                (false, false) => (PhysicsEvent::CollisionExit(collision1), PhysicsEvent::CollisionExit(collision2)),
+            };
+            obj1.on_physics_event(&event1)?;
+            obj2.on_physics_event(&event2)?;
+        }
+
+        let contact_forces = std::mem::take(&mut *self.event_collector.contact_forces.lock().unwrap());
+        for event in contact_forces {
+            let (Some(obj1), Some(obj2)) = (self.object_of(event.collider1), self.object_of(event.collider2)) else {
+                continue;
+            };
+            let total_force: Vec3 = event.total_force.into();
+            let max_force_direction: Vec3 = event.max_force_direction.into();
+            obj1.on_physics_event(&PhysicsEvent::ContactForce(ContactForce {
+                other: obj2.clone(),
+                total_force,
+                total_force_magnitude: event.total_force_magnitude,
+                max_force_direction,
+                max_force_magnitude: event.max_force_magnitude,
+            }))?;
+            obj2.on_physics_event(&PhysicsEvent::ContactForce(ContactForce {
+                other: obj1,
+                total_force: -total_force,
+                total_force_magnitude: event.total_force_magnitude,
+                max_force_direction: -max_force_direction,
+                max_force_magnitude: event.max_force_magnitude,
+            }))?;
+        }
+        Ok(())
+    }
+
+    // Registers the object in the world on first sight, otherwise pushes
+    // transforms that were changed outside the simulation.
+    fn sync_object(&mut self, obj: &GameObject) -> RigidBodyHandle {
+        let global: Transform = obj.global_mat().into();
+        let base = obj.base.borrow();
+        let rigid_body_comp = base.components.get_component::<RigidBodyComponent>().expect("RigidBodyComponent not found");
+        let mut rigid_body_comp = rigid_body_comp.borrow_mut();
+        if let Some(handle) = rigid_body_comp.handle() {
+            if rigid_body_comp.take_dirty() {
+                if let Some(rigid_body) = self.rigid_body_set.get_mut(handle) {
+                    set_body_transform(rigid_body, &global);
+                }
+            }
+            return handle;
+        }
+
+        let mut rigid_body = rigid_body_comp.rigid_body.clone();
+        rigid_body.set_position(
+            Isometry::from_parts(global.position.into(), global.rotation.into()),
+            true,
+        );
+        let handle = self.rigid_body_set.insert(rigid_body);
+        rigid_body_comp.set_handle(handle);
+
+        let collider_comp = base.components.get_component::<ColliderComponent>().expect("ColliderComponent not found");
+        let mut collider_comp = collider_comp.borrow_mut();
+        let collider_handles = collider_comp
+            .colliders
+            .iter()
+            .map(|collider| {
+                // contact force events stay opt-in through the collider's own active events,
+                // they are reported every step while bodies touch
+                let mut collider = collider.clone();
+                collider.set_active_events(collider.active_events() | ActiveEvents::COLLISION_EVENTS);
+                self.collider_set
+                    .insert_with_parent(collider, handle, &mut self.rigid_body_set)
+            })
+            .collect();
+        collider_comp.set_handles(collider_handles);
+
+        self.objects.insert(handle, Rc::downgrade(&obj.base));
+        handle
+    }
+
+    fn rigid_body_of(object: &BaseGameObject) -> Option<RigidBodyHandle> {
+        object.components.get_component::<RigidBodyComponent>()?.borrow().handle()
+    }
+
+    // Inserts joints once both bodies are in the world and drops the ones rapier removed
+    // together with one of their bodies.
+    fn sync_joints(&mut self, obj: &GameObject) {
+        let base = obj.base.borrow();
+        let Some(joint_comp) = base.components.get_component::<JointComponent>() else {
+            return;
+        };
+        let Some(rigid_body) = Self::rigid_body_of(&base) else {
+            return;
+        };
+        let mut joint_comp = joint_comp.borrow_mut();
+        joint_comp.joints.retain_mut(|joint| {
+            let Some(target) = joint.target.upgrade() else {
+                match joint.handle {
+                    Some(JointHandle::Impulse(handle)) => {
+                        self.impulse_joint_set.remove(handle, true);
+                    }
+                    Some(JointHandle::Multibody(handle)) => {
+                        self.multibody_joint_set.remove(handle, true);
+                    }
+                    None => {}
+                }
+                return false;
+            };
+            match joint.handle {
+                Some(JointHandle::Impulse(handle)) => {
+                    let Some(impulse_joint) = self.impulse_joint_set.get_mut(handle) else {
+                        return false;
+                    };
+                    if std::mem::replace(&mut joint.dirty, false) {
+                        impulse_joint.data = joint.data;
+                        let (body1, body2) = (impulse_joint.body1, impulse_joint.body2);
+                        self.island_manager.wake_up(&mut self.rigid_body_set, body1, true);
+                        self.island_manager.wake_up(&mut self.rigid_body_set, body2, true);
+                    }
+                    true
+                }
+                Some(JointHandle::Multibody(handle)) => self.multibody_joint_set.get(handle).is_some(),
+                None => {
+                    let Some(target_body) = Self::rigid_body_of(&target.borrow()) else {
+                        return true;
+                    };
+                    joint.dirty = false;
+                    joint.handle = if joint.multibody {
+                        self.multibody_joint_set
+                            .insert(rigid_body, target_body, joint.data, true)
+                            .map(JointHandle::Multibody)
+                    } else {
+                        Some(JointHandle::Impulse(
+                            self.impulse_joint_set.insert(rigid_body, target_body, joint.data, true),
+                        ))
+                    };
+                    // a multibody joint that would close a loop is rejected by rapier
+                    joint.handle.is_some()
+                }
+            }
+        });
+    }
+
+    // Removes the bodies of the objects that left the scene. Waiting for them to be dropped is not
+    // enough, parents and children hold each other and a removed object with children never drops.
+    fn remove_absent(&mut self, present: &HashSet<RigidBodyHandle>) {
+        let absent = self
+            .objects
+            .keys()
+            .filter(|handle| !present.contains(handle))
+            .copied()
+            .collect::<Vec<_>>();
+        for handle in absent {
+            if let Some(base) = self.objects.remove(&handle).and_then(|weak| weak.upgrade()) {
+                // added back to the scene it gets a new body
+                let base = base.borrow();
+                if let Some(rigid_body_comp) = base.components.get_component::<RigidBodyComponent>() {
+                    rigid_body_comp.borrow_mut().clear_handle();
+                }
+                if let Some(collider_comp) = base.components.get_component::<ColliderComponent>() {
+                    collider_comp.borrow_mut().set_handles(vec![]);
+                }
+            }
+            self.rigid_body_set.remove(
+                handle,
+                &mut self.island_manager,
+                &mut self.collider_set,
+                &mut self.impulse_joint_set,
+                &mut self.multibody_joint_set,
+                true,
+            );
+        }
+    }
+}
+
+
+impl Default for PhysicsData{
+    fn default()->Self{
+        let dt = CONFIG.config().get_fixed_step().as_millis() as f32/1000.0;
+        let integration_parameters = IntegrationParameters{
+            dt,
+            min_ccd_dt:dt/100.0,
+            ..IntegrationParameters::default()
+        };
+        Self{
+            gravity: Vector3::new(0.0, -9.81, 0.0),
+            physics_pipeline: PhysicsPipeline::new(),
+            integration_parameters,
+            island_manager: IslandManager::new(),
+            broad_phase: BroadPhaseMultiSap::new(),
+            narrow_phase: NarrowPhase::new(),
+            rigid_body_set: RigidBodySet::new(),
+            collider_set: ColliderSet::new(),
+            impulse_joint_set: ImpulseJointSet::new(),
+            multibody_joint_set: MultibodyJointSet::new(),
+            ccd_solver: CCDSolver::new(),
+            query_pipeline: QueryPipeline::new(),
+            objects: HashMap::new(),
+            event_collector: PhysicsEventCollector::default(),
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use glam::{Quat, Vec3};
+use rapier3d::prelude::*;
+use crate::engine::physics::PhysicsData;
+use crate::engine::scene::gameobject::GameObject;
+
+#[derive(Debug, Clone, Copy)]
+pub struct QueryOptions {
+    pub groups: Option<InteractionGroups>,
+    pub exclude_rigid_body: Option<RigidBodyHandle>,
+    pub exclude_sensors: bool,
+    pub solid: bool,
+}
+
+impl QueryOptions {
+    pub fn with_groups(mut self, memberships: u32, filter: u32) -> Self {
+        self.groups = Some(InteractionGroups::new(
+            Group::from_bits_truncate(memberships),
+            Group::from_bits_truncate(filter),
+        ));
+        self
+    }
+
+    pub fn excluding(mut self, rigid_body: RigidBodyHandle) -> Self {
+        self.exclude_rigid_body = Some(rigid_body);
+        self
+    }
+
+    pub fn without_sensors(mut self) -> Self {
+        self.exclude_sensors = true;
+        self
+    }
+
+    pub fn with_solid(mut self, solid: bool) -> Self {
+        self.solid = solid;
+        self
+    }
+
+    pub(crate) fn filter(&self) -> QueryFilter {
+        let mut filter = QueryFilter::default();
+        if let Some(groups) = self.groups {
+            filter = filter.groups(groups);
+        }
+        if let Some(rigid_body) = self.exclude_rigid_body {
+            filter = filter.exclude_rigid_body(rigid_body);
+        }
+        if self.exclude_sensors {
+            filter = filter.exclude_sensors();
+        }
+        filter
+    }
+}
+
+impl Default for QueryOptions {
+    fn default() -> Self {
+        Self {
+            groups: None,
+            exclude_rigid_body: None,
+            exclude_sensors: false,
+            solid: true,
+        }
+    }
+}
+
+pub struct QueryHit {
+    pub object: GameObject,
+    pub collider: ColliderHandle,
+    pub point: Vec3,
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl PhysicsData {
+    // Colliders of objects not in the world anymore, until their body is removed, are skipped so
+    // the next collider is hit instead.
+    fn is_mapped(&self, collider: ColliderHandle, _: &Collider) -> bool {
+        self.object_of(collider).is_some()
+    }
+
+    // None for a zero direction
+    pub fn raycast(&self, origin: Vec3, direction: Vec3, max_distance: f32, options: &QueryOptions) -> Option<QueryHit> {
+        if direction.length_squared() == 0.0 {
+            return None;
+        }
+        let ray = Ray::new(origin.into(), direction.normalize().into());
+        let mapped = |collider, shape: &Collider| self.is_mapped(collider, shape);
+        let (collider, intersection) = self.query_pipeline.cast_ray_and_get_normal(
+            &self.rigid_body_set,
+            &self.collider_set,
+            &ray,
+            max_distance,
+            options.solid,
+            options.filter().predicate(&mapped),
+        )?;
+        Some(QueryHit {
+            object: self.object_of(collider)?,
+            collider,
+            point: ray.point_at(intersection.time_of_impact).coords.into(),
+            normal: intersection.normal.into(),
+            distance: intersection.time_of_impact,
+        })
+    }
+
+    // sorted from the closest hit to the farthest, empty for a zero direction
+    pub fn raycast_all(&self, origin: Vec3, direction: Vec3, max_distance: f32, options: &QueryOptions) -> Vec<QueryHit> {
+        if direction.length_squared() == 0.0 {
+            return vec![];
+        }
+        let ray = Ray::new(origin.into(), direction.normalize().into());
+        let mut hits = vec![];
+        self.query_pipeline.intersections_with_ray(
+            &self.rigid_body_set,
+            &self.collider_set,
+            &ray,
+            max_distance,
+            options.solid,
+            options.filter(),
+            |collider, intersection| {
+                if let Some(object) = self.object_of(collider) {
+                    hits.push(QueryHit {
+                        object,
+                        collider,
+                        point: ray.point_at(intersection.time_of_impact).coords.into(),
+                        normal: intersection.normal.into(),
+                        distance: intersection.time_of_impact,
+                    });
+                }
+                true
+            },
+        );
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+
+    // closest point on any collider, the distance is zero when the point is inside and solid is set
+    pub fn project_point(&self, point: Vec3, options: &QueryOptions) -> Option<QueryHit> {
+        let mapped = |collider, shape: &Collider| self.is_mapped(collider, shape);
+        let (collider, projection) = self.query_pipeline.project_point(
+            &self.rigid_body_set,
+            &self.collider_set,
+            &point.into(),
+            options.solid,
+            options.filter().predicate(&mapped),
+        )?;
+        let projected: Vec3 = projection.point.coords.into();
+        let normal = if projection.is_inside {
+            (projected - point).normalize_or_zero()
+        } else {
+            (point - projected).normalize_or_zero()
+        };
+        Some(QueryHit {
+            object: self.object_of(collider)?,
+            collider,
+            point: projected,
+            normal,
+            distance: if projection.is_inside { 0.0 } else { projected.distance(point) },
+        })
+    }
+
+    // None for a zero direction
+    pub fn shape_cast(
+        &self,
+        shape: &dyn Shape,
+        position: Vec3,
+        rotation: Quat,
+        direction: Vec3,
+        max_distance: f32,
+        options: &QueryOptions,
+    ) -> Option<QueryHit> {
+        if direction.length_squared() == 0.0 {
+            return None;
+        }
+        let shape_pos = Isometry::from_parts(position.into(), rotation.into());
+        let direction = direction.normalize();
+        let mapped = |collider, shape: &Collider| self.is_mapped(collider, shape);
+        let (collider, hit) = self.query_pipeline.cast_shape(
+            &self.rigid_body_set,
+            &self.collider_set,
+            &shape_pos,
+            &direction.into(),
+            shape,
+            ShapeCastOptions {
+                max_time_of_impact: max_distance,
+                target_distance: 0.0,
+                stop_at_penetration: options.solid,
+                compute_impact_geometry_on_penetration: true,
+            },
+            options.filter().predicate(&mapped),
+        )?;
+        // the witness and normal are expressed in the local space of the hit collider
+        let collider_pos = self.collider_set.get(collider)?.position();
+        Some(QueryHit {
+            object: self.object_of(collider)?,
+            collider,
+            point: (collider_pos * hit.witness1).coords.into(),
+            normal: (collider_pos * hit.normal1.into_inner()).into(),
+            distance: hit.time_of_impact,
+        })
+    }
+
+    pub fn overlap_aabb(&self, min: Vec3, max: Vec3, options: &QueryOptions) -> Vec<GameObject> {
+        let aabb = Aabb::new(min.into(), max.into());
+        let filter = options.filter();
+        let mut objects: Vec<GameObject> = vec![];
+        self.query_pipeline.colliders_with_aabb_intersecting_aabb(&aabb, |handle| {
+            let Some(collider) = self.collider_set.get(*handle) else {
+                return true;
+            };
+            if !filter.test(&self.rigid_body_set, *handle, collider) {
+                return true;
+            }
+            if let Some(object) = self.object_of(*handle) {
+                if !objects.iter().any(|other| std::rc::Rc::ptr_eq(&other.base, &object.base)) {
+                    objects.push(object);
+                }
+            }
+            true
+        });
+        objects
+    }
+}
@@ -25,6 +25,10 @@ impl Camera {
         }
     }
 
+    pub fn new_w_gameobject(game_object: GameObject) -> Self {
+        Self { game_object }
+    }
+
     pub fn frustum(&self) -> Mat4 {
         let perspective = *CONFIG.projection();
         perspective * self.game_object.global_mat().inverse()
@@ -0,0 +1,38 @@
+use glam::{Vec3, Vec4};
+use crate::engine::drawable::bounds::{Aabb, Bounds};
+use crate::engine::scene::camera::Camera;
+
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    pub fn new(camera: &Camera) -> Self {
+        Self { planes: camera.frustum_planes() }
+    }
+
+    // A box is outside when its corner furthest along the normal of a plane is behind it,
+    // boxes near the frustum corners may be kept.
+    pub fn intersects(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            let normal = plane.truncate();
+            let corner = Vec3::select(normal.cmpge(Vec3::ZERO), aabb.max, aabb.min);
+            normal.dot(corner) + plane.w >= 0.0
+        })
+    }
+
+    pub fn contains(&self, bounds: &Bounds) -> bool {
+        match bounds {
+            Bounds::Empty => false,
+            Bounds::Finite(aabb) => self.intersects(aabb),
+            Bounds::Unbounded => true,
+        }
+    }
+}
+
+// Objects with a DrawableComponent drawn and skipped by the last render, for profiling.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CullingStats {
+    pub drawn: usize,
+    pub culled: usize,
+}
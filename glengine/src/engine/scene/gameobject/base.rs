@@ -1,33 +1,52 @@
 use glam::Mat4;
+use crate::engine::drawable::bounds::Bounds;
 use crate::engine::drawable::Drawable;
+use crate::engine::drawable::shader::Shader;
 use crate::engine::scene::gameobject::components::{Component, ComponentMap};
 use crate::engine::scene::gameobject::{GameObject};
 use crate::engine::GameState;
+use crate::engine::physics::PhysicsEvent;
 use crate::engine::scene::gameobject::components::drawable::DrawableComponent;
+use crate::engine::scene::gameobject::components::lod::LodComponent;
+use crate::engine::scene::culling::{CullingStats, Frustum};
 use crate::engine::scene::lights::Lights;
 use crate::engine::transform::Transform;
-use crate::result::EngineStepResult;
+use crate::result::{EngineFixedStepResult, EngineStepResult};
 
 pub struct GameObjectData {
+    pub name: String,
     pub parent: Option<GameObject>,
     pub children: Vec<GameObject>,
     pub transform: Transform,
+    // world space bounds of the drawable and of the whole subtree, from the last update_bounds
+    pub(crate) bounds: Bounds,
+    pub(crate) subtree_bounds: Bounds,
+    // objects with a DrawableComponent in the subtree
+    pub(crate) subtree_drawables: usize,
 }
 
 impl GameObjectData {
     pub fn new(parent: Option<GameObject>) -> Self {
         Self {
+            name: String::new(),
             parent,
             children: Vec::new(),
             transform: Transform::default(),
+            bounds: Bounds::Empty,
+            subtree_bounds: Bounds::Empty,
+            subtree_drawables: 0,
         }
     }
 
     pub fn new_w_transform(parent:Option<GameObject>,transform:Transform)->Self{
         Self{
+            name: String::new(),
             parent,
             children:Vec::new(),
-            transform
+            transform,
+            bounds: Bounds::Empty,
+            subtree_bounds: Bounds::Empty,
+            subtree_drawables: 0,
         }
     }
 }
@@ -53,7 +72,11 @@ impl BaseGameObject {
         }
         Ok(())
     }
-    
+
+    pub fn on_physics_event(&mut self, event: &PhysicsEvent) -> EngineFixedStepResult<()> {
+        self.components.on_physics_event(&mut self.data, event)
+    }
+
     pub fn add_component<T: Component+'static >(&mut self, component: T) {
         self.components.add_component(component, &mut self.data);
     }
@@ -68,21 +91,112 @@ impl BaseGameObject {
         }
         transform
     }
+
+    // Level of detail from the distance between the object origin and the camera.
+    fn select_lod(&self, modelmat: &Mat4, viewmat: &Mat4, drawable: &mut DrawableComponent) {
+        if let Some(lod) = self.components.get_component::<LodComponent>() {
+            let distance = (*viewmat * *modelmat).w_axis.truncate().length();
+            drawable.set_lod(lod.borrow().level(distance));
+        }
+    }
+
+    // Updates the world space bounds of the object and its subtree for draw_culled.
+    pub(crate) fn update_bounds(&mut self, modelmat: &Mat4) {
+        let newmodelmat = *modelmat * Mat4::from(self.data.transform);
+        let drawable = self.components.get_component::<DrawableComponent>();
+        self.data.bounds = match &drawable {
+            Some(drawable) => drawable.borrow().bounds().transform(&newmodelmat),
+            None => Bounds::Empty,
+        };
+        let mut subtree_bounds = self.data.bounds;
+        let mut subtree_drawables = if drawable.is_some() { 1 } else { 0 };
+        for child in &self.data.children {
+            let mut child = child.base.borrow_mut();
+            child.update_bounds(&newmodelmat);
+            subtree_bounds = subtree_bounds.union(&child.data.subtree_bounds);
+            subtree_drawables += child.data.subtree_drawables;
+        }
+        self.data.subtree_bounds = subtree_bounds;
+        self.data.subtree_drawables = subtree_drawables;
+    }
+
+    // Passes the drawables intersecting the frustum to draw with their model matrix, subtrees
+    // outside of it are skipped at once.
+    pub(crate) fn draw_culled<F: FnMut(&mut DrawableComponent, &Mat4)>(
+        &mut self,
+        modelmat: &Mat4,
+        viewmat: &Mat4,
+        frustum: &Frustum,
+        stats: &mut CullingStats,
+        draw: &mut F,
+    ) {
+        if !frustum.contains(&self.data.subtree_bounds) {
+            stats.culled += self.data.subtree_drawables;
+            return;
+        }
+        let newmodelmat = *modelmat * Mat4::from(self.data.transform);
+        if let Some(drawable) = self.components.get_component::<DrawableComponent>() {
+            if frustum.contains(&self.data.bounds) {
+                let mut drawable = drawable.borrow_mut();
+                self.select_lod(&newmodelmat, viewmat, &mut drawable);
+                draw(&mut **drawable, &newmodelmat);
+                stats.drawn += 1;
+            } else {
+                stats.culled += 1;
+            }
+        }
+
+        for child in &self.data.children {
+            child.base
+                .borrow_mut()
+                .draw_culled(&newmodelmat, viewmat, frustum, stats, draw);
+        }
+    }
 }
 
 impl Drawable for BaseGameObject {
     fn draw(&mut self, modelmat: &Mat4, viewmat: &Mat4, lights: Option<&Lights>) {
         let newmodelmat = *modelmat * Mat4::from(self.data.transform);
         if let Some(drawable) = self.components.get_component::<DrawableComponent>() {
-            drawable
+            let mut drawable = drawable.borrow_mut();
+            self.select_lod(&newmodelmat, viewmat, &mut drawable);
+            drawable.draw(&newmodelmat, viewmat, lights);
+        }
+
+        for child in &self.data.children {
+            child.base
                 .borrow_mut()
                 .draw(&newmodelmat, viewmat, lights);
         }
+    }
+
+    fn draw_shadow(&mut self, modelmat: &Mat4, shader: &Shader) {
+        let newmodelmat = *modelmat * Mat4::from(self.data.transform);
+        if let Some(drawable) = self.components.get_component::<DrawableComponent>() {
+            drawable
+                .borrow_mut()
+                .draw_shadow(&newmodelmat, shader);
+        }
 
         for child in &self.data.children {
             child.base
                 .borrow_mut()
-                .draw(&newmodelmat, viewmat, lights);
+                .draw_shadow(&newmodelmat, shader);
+        }
+    }
+
+    fn draw_geometry(&mut self, modelmat: &Mat4, viewmat: &Mat4) {
+        let newmodelmat = *modelmat * Mat4::from(self.data.transform);
+        if let Some(drawable) = self.components.get_component::<DrawableComponent>() {
+            let mut drawable = drawable.borrow_mut();
+            self.select_lod(&newmodelmat, viewmat, &mut drawable);
+            drawable.draw_geometry(&newmodelmat, viewmat);
+        }
+
+        for child in &self.data.children {
+            child.base
+                .borrow_mut()
+                .draw_geometry(&newmodelmat, viewmat);
         }
     }
 }
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use glam::{Mat4, Quat, Vec3};
+use glengine_mdl::models::{AnimationStruct, ChannelStruct};
+use crate::engine::drawable::skin::Skin;
+use crate::engine::scene::gameobject::base::GameObjectData;
+use crate::engine::scene::gameobject::components::{Component, ComponentMap};
+use crate::engine::scene::gameobject::GameObject;
+use crate::engine::transform::Transform;
+use crate::engine::GameState;
+use crate::result::EngineStepResult;
+
+pub struct AnimationChannel {
+    pub positions: Vec<(f32, Vec3)>,
+    pub rotations: Vec<(f32, Quat)>,
+    pub scales: Vec<(f32, Vec3)>,
+}
+
+impl AnimationChannel {
+    // Samples the channel at the given time, keys missing from the channel come from the rest transform
+    fn sample(&self, time: f32, rest: &Transform) -> Transform {
+        Transform {
+            position: sample_keys(&self.positions, time, Vec3::lerp).unwrap_or(rest.position),
+            rotation: sample_keys(&self.rotations, time, Quat::slerp).unwrap_or(rest.rotation),
+            scale: sample_keys(&self.scales, time, Vec3::lerp).unwrap_or(rest.scale),
+        }
+    }
+}
+
+fn sample_keys<T: Copy>(keys: &[(f32, T)], time: f32, interpolate: fn(T, T, f32) -> T) -> Option<T> {
+    let first = keys.first()?;
+    if time <= first.0 {
+        return Some(first.1);
+    }
+    let next = keys.iter().position(|(key_time, _)| *key_time > time);
+    match next {
+        Some(next) => {
+            let (start_time, start) = keys[next - 1];
+            let (end_time, end) = keys[next];
+            let factor = (time - start_time) / (end_time - start_time);
+            Some(interpolate(start, end, factor))
+        }
+        None => keys.last().map(|(_, value)| *value),
+    }
+}
+
+impl From<&ChannelStruct> for AnimationChannel {
+    fn from(channel: &ChannelStruct) -> Self {
+        Self {
+            positions: channel.positions.iter().map(|(t, p)| (*t, Vec3::from_array(*p))).collect(),
+            rotations: channel.rotations.iter().map(|(t, r)| (*t, Quat::from_array(*r).normalize())).collect(),
+            scales: channel.scales.iter().map(|(t, s)| (*t, Vec3::from_array(*s))).collect(),
+        }
+    }
+}
+
+pub struct AnimationClip {
+    pub name: String,
+    // in seconds
+    pub duration: f32,
+    // keyed by node name
+    pub channels: HashMap<String, AnimationChannel>,
+}
+
+impl From<&AnimationStruct> for AnimationClip {
+    fn from(animation: &AnimationStruct) -> Self {
+        Self {
+            name: animation.name.clone(),
+            duration: animation.duration,
+            channels: animation.channels.iter().map(|c| (c.node.clone(), AnimationChannel::from(c))).collect(),
+        }
+    }
+}
+
+struct PlayState {
+    clip: usize,
+    time: f32,
+    looping: bool,
+}
+
+impl PlayState {
+    fn advance(&mut self, clips: &[AnimationClip], delta: f32) {
+        let duration = clips[self.clip].duration;
+        self.time += delta;
+        if duration <= 0.0 {
+            self.time = 0.0;
+        } else if self.looping {
+            self.time %= duration;
+        } else {
+            self.time = self.time.min(duration);
+        }
+    }
+
+    fn finished(&self, clips: &[AnimationClip]) -> bool {
+        !self.looping && self.time >= clips[self.clip].duration
+    }
+}
+
+struct Crossfade {
+    from: PlayState,
+    elapsed: f32,
+    duration: f32,
+}
+
+// Plays the animations of an imported model on the node hierarchy below its GameObject
+// and poses the skins of its meshes.
+pub struct AnimatorComponent {
+    clips: Vec<AnimationClip>,
+    // mesh node name and the skin drawn by it
+    skins: Vec<(String, Skin)>,
+    // rest transforms of the animated nodes, to return to when an animation stops
+    rest: HashMap<String, Transform>,
+    current: Option<PlayState>,
+    crossfade: Option<Crossfade>,
+    pub speed: f32,
+}
+
+impl AnimatorComponent {
+    pub fn new(clips: Vec<AnimationClip>) -> Self {
+        Self {
+            clips,
+            skins: vec![],
+            rest: HashMap::new(),
+            current: None,
+            crossfade: None,
+            speed: 1.0,
+        }
+    }
+
+    pub fn add_skin(&mut self, node: &str, skin: Skin) {
+        self.skins.push((node.to_string(), skin));
+    }
+
+    pub fn clip_names(&self) -> Vec<&str> {
+        self.clips.iter().map(|clip| clip.name.as_str()).collect()
+    }
+
+    // Starts the named animation from the beginning, returns false if the model has no such animation
+    pub fn play(&mut self, name: &str, looping: bool) -> bool {
+        let Some(clip) = self.clips.iter().position(|clip| clip.name == name) else {
+            return false;
+        };
+        self.current = Some(PlayState { clip, time: 0.0, looping });
+        self.crossfade = None;
+        true
+    }
+
+    // Blends from the playing animation to the named one over duration seconds
+    pub fn crossfade(&mut self, name: &str, duration: f32, looping: bool) -> bool {
+        let Some(clip) = self.clips.iter().position(|clip| clip.name == name) else {
+            return false;
+        };
+        let next = PlayState { clip, time: 0.0, looping };
+        match self.current.replace(next) {
+            Some(from) if duration > 0.0 => {
+                self.crossfade = Some(Crossfade { from, elapsed: 0.0, duration });
+            }
+            _ => self.crossfade = None,
+        }
+        true
+    }
+
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.crossfade = None;
+    }
+
+    // false once a non looping animation reached its end, its last frame is held until stop or play
+    pub fn is_playing(&self) -> bool {
+        self.current.as_ref().is_some_and(|current| !current.finished(&self.clips))
+    }
+
+    fn sample(&self, node: &str, rest: &Transform) -> Transform {
+        let sample_state = |state: &PlayState| {
+            self.clips[state.clip]
+                .channels
+                .get(node)
+                .map(|channel| channel.sample(state.time, rest))
+                .unwrap_or(*rest)
+        };
+        let Some(current) = &self.current else {
+            return *rest;
+        };
+        let target = sample_state(current);
+        match &self.crossfade {
+            Some(crossfade) => {
+                let from = sample_state(&crossfade.from);
+                let factor = (crossfade.elapsed / crossfade.duration).clamp(0.0, 1.0);
+                Transform {
+                    position: from.position.lerp(target.position, factor),
+                    rotation: from.rotation.slerp(target.rotation, factor),
+                    scale: from.scale.lerp(target.scale, factor),
+                }
+            }
+            None => target,
+        }
+    }
+
+    fn is_animated(&self, node: &str) -> bool {
+        let animates = |state: &PlayState| self.clips[state.clip].channels.contains_key(node);
+        self.current.as_ref().is_some_and(animates)
+            || self.crossfade.as_ref().is_some_and(|crossfade| animates(&crossfade.from))
+    }
+
+    fn pose_node(&mut self, name: &str, transform: &mut Transform) {
+        if self.is_animated(name) {
+            let rest = *self.rest.entry(name.to_string()).or_insert(*transform);
+            *transform = self.sample(name, &rest);
+        } else if let Some(rest) = self.rest.remove(name) {
+            *transform = rest;
+        }
+    }
+
+    // Poses the children recursively and records the transform of every node relative to the animator parent
+    fn pose_children(&mut self, children: &[GameObject], parent_mat: Mat4, globals: &mut HashMap<String, Mat4>) {
+        for child in children {
+            let mut base = child.base.borrow_mut();
+            let name = base.data.name.clone();
+            self.pose_node(&name, &mut base.data.transform);
+            let global = parent_mat * Mat4::from(base.data.transform);
+            globals.insert(name, global);
+            let grand_children = base.data.children.clone();
+            drop(base);
+            self.pose_children(&grand_children, global, globals);
+        }
+    }
+}
+
+impl Component for AnimatorComponent {
+    fn step(
+        &mut self,
+        object: &mut GameObjectData,
+        _components: &ComponentMap,
+        state: &GameState,
+    ) -> EngineStepResult<()> {
+        let delta = state.delta.as_secs_f32() * self.speed;
+        if let Some(current) = &mut self.current {
+            current.advance(&self.clips, delta);
+        }
+        if let Some(crossfade) = &mut self.crossfade {
+            crossfade.from.advance(&self.clips, delta);
+            crossfade.elapsed += delta;
+            if crossfade.elapsed >= crossfade.duration {
+                self.crossfade = None;
+            }
+        }
+
+        let name = object.name.clone();
+        self.pose_node(&name, &mut object.transform);
+        let mut globals = HashMap::new();
+        let root = Mat4::from(object.transform);
+        globals.insert(name, root);
+        self.pose_children(&object.children, root, &mut globals);
+
+        for (node, skin) in &self.skins {
+            let Some(mesh_global) = globals.get(node) else { continue };
+            let mesh_inverse = mesh_global.inverse();
+            skin.set_pose(|bone| {
+                globals.get(&bone.name).map(|bone_global| mesh_inverse * *bone_global * bone.offset)
+            });
+        }
+        Ok(())
+    }
+}
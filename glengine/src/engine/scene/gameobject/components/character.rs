@@ -0,0 +1,139 @@
+use glam::{Quat, Vec3};
+use glfw::Key;
+use rapier3d::control::{CharacterAutostep, CharacterLength, KinematicCharacterController};
+use rapier3d::prelude::*;
+
+use crate::engine::physics::query::QueryOptions;
+use crate::engine::scene::gameobject::base::GameObjectData;
+use crate::engine::scene::gameobject::components::collider::ColliderComponent;
+use crate::engine::scene::gameobject::components::rigidbody::RigidBodyComponent;
+use crate::engine::scene::gameobject::components::{Component, ComponentMap};
+use crate::engine::scene::gameobject::GameObject;
+use crate::engine::GameState;
+use crate::result::EngineStepResult;
+
+// Walks the object around with WASD and jumps with space. Needs a kinematic position based
+// rigid body and a collider on the same object. Mouse yaw turns the object itself while the
+// pitch goes to the optional head object, which should be a child holding the camera.
+pub struct CharacterControllerComponent {
+    pub controller: KinematicCharacterController,
+    pub head: Option<GameObject>,
+    pub speed: f32,
+    pub jump_speed: f32,
+    pub gravity: f32,
+    pub mouse_sensitivity: f32,
+    vertical_velocity: f32,
+    pitch: f32,
+    grounded: bool,
+}
+
+impl CharacterControllerComponent {
+    pub fn new(head: Option<GameObject>) -> Self {
+        Self {
+            controller: KinematicCharacterController {
+                up: Vector::y_axis(),
+                offset: CharacterLength::Absolute(0.02),
+                slide: true,
+                autostep: Some(CharacterAutostep {
+                    max_height: CharacterLength::Absolute(0.4),
+                    min_width: CharacterLength::Absolute(0.2),
+                    include_dynamic_bodies: false,
+                }),
+                max_slope_climb_angle: 45.0_f32.to_radians(),
+                min_slope_slide_angle: 30.0_f32.to_radians(),
+                snap_to_ground: Some(CharacterLength::Absolute(0.3)),
+                ..KinematicCharacterController::default()
+            },
+            head,
+            speed: 6.0,
+            jump_speed: 6.0,
+            gravity: 9.81,
+            mouse_sensitivity: 0.1,
+            vertical_velocity: 0.0,
+            pitch: 0.0,
+            grounded: false,
+        }
+    }
+
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+}
+
+impl Component for CharacterControllerComponent {
+    fn step(
+        &mut self,
+        object: &mut GameObjectData,
+        _components: &ComponentMap,
+        state: &GameState,
+    ) -> EngineStepResult<()> {
+        let rotation_speed = self.mouse_sensitivity * state.delta.as_secs_f32();
+        object.transform.rotation = Quat::from_rotation_y(rotation_speed * -state.input_state.mouse_delta.0 as f32)
+            * object.transform.rotation;
+        self.pitch = (self.pitch + rotation_speed * state.input_state.mouse_delta.1 as f32)
+            .clamp(-std::f32::consts::FRAC_PI_2, std::f32::consts::FRAC_PI_2);
+        if let Some(head) = &self.head {
+            head.base.borrow_mut().data.transform.rotation = Quat::from_rotation_x(self.pitch);
+        }
+        Ok(())
+    }
+
+    fn fixed_step(
+        &mut self,
+        object: &mut GameObjectData,
+        components: &ComponentMap,
+        state: &GameState,
+    ) -> EngineStepResult<()> {
+        let Some(rigid_body) = components
+            .get_component::<RigidBodyComponent>()
+            .and_then(|rigid_body| rigid_body.borrow().handle())
+        else {
+            return Ok(());
+        };
+        let Some(collider) = components
+            .get_component::<ColliderComponent>()
+            .and_then(|collider| collider.borrow().handles().first().copied())
+        else {
+            return Ok(());
+        };
+
+        let dt = state.delta.as_secs_f32();
+        let forward = (object.transform.forward() * Vec3::new(1.0, 0.0, 1.0)).normalize_or_zero();
+        let right = (object.transform.right() * Vec3::new(1.0, 0.0, 1.0)).normalize_or_zero();
+        let keyboard = &state.input_state.keyboard;
+        let mut direction = Vec3::ZERO;
+        if keyboard.is_held(Key::W) {
+            direction += forward;
+        }
+        if keyboard.is_held(Key::S) {
+            direction -= forward;
+        }
+        if keyboard.is_held(Key::D) {
+            direction += right;
+        }
+        if keyboard.is_held(Key::A) {
+            direction -= right;
+        }
+
+        if self.grounded {
+            self.vertical_velocity = 0.0;
+            if keyboard.is_held(Key::Space) {
+                self.vertical_velocity = self.jump_speed;
+            }
+        }
+        self.vertical_velocity -= self.gravity * dt;
+
+        let desired = direction.normalize_or_zero() * self.speed * dt + Vec3::Y * self.vertical_velocity * dt;
+        let options = QueryOptions::default().excluding(rigid_body).without_sensors();
+        let Some(movement) = state
+            .physics
+            .move_character(&self.controller, dt, collider, desired, &options)
+        else {
+            return Ok(());
+        };
+        self.grounded = movement.grounded;
+        // the movement is in world space, the object is expected to have an untransformed parent
+        object.transform.position += Vec3::from(movement.translation);
+        Ok(())
+    }
+}
@@ -14,23 +14,32 @@ fn get_mesh_indices(mesh:&MeshStruct) -> Vec<[u32;3]>{
     }).collect()
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct ColliderComponent {
-    pub colliders: Vec<Collider>
+    pub colliders: Vec<Collider>,
+    handles: Vec<ColliderHandle>,
 }
 
 impl ColliderComponent {
+    pub fn handles(&self) -> &Vec<ColliderHandle> {
+        &self.handles
+    }
+
+    pub(crate) fn set_handles(&mut self, handles: Vec<ColliderHandle>) {
+        self.handles = handles;
+    }
+
     pub fn trimesh_from_mesh(mesh: &MeshStruct,scale:f32) -> Self {
         let vertices = get_mesh_vertices(mesh,scale);
         let indices: Vec<[u32;3]> = get_mesh_indices(mesh);
         let collider = ColliderBuilder::trimesh(vertices,indices).build();
-        Self{ colliders:vec![collider]}
+        Self::from(collider)
     }
     
     pub fn hull_from_mesh(mesh: &MeshStruct,scale:f32) -> Self {
         let vertices = get_mesh_vertices(mesh,scale);
         let collider = ColliderBuilder::convex_hull(&vertices).unwrap().build();
-        Self{ colliders:vec![collider]}
+        Self::from(collider)
     }
     
     pub fn trimesh_from_meshvec(meshes: &Vec<MeshStruct>,scale:f32) -> Self {
@@ -41,7 +50,7 @@ impl ColliderComponent {
             let collider = ColliderBuilder::trimesh(vertices,indices).build();
             colliders.push(collider);
         }
-        Self{ colliders }
+        Self::from(colliders)
     }
     
     pub fn hull_from_meshvec(meshes: &Vec<MeshStruct>,scale:f32) -> Self {
@@ -51,19 +60,26 @@ impl ColliderComponent {
             let collider = ColliderBuilder::convex_hull(&vertices).unwrap().build();
             colliders.push(collider);
         }
-        Self{ colliders }
+        Self::from(colliders)
     }
 }
 
 impl From<Vec<Collider>> for ColliderComponent {
     fn from(collider: Vec<Collider>) -> Self {
-        Self{ colliders: collider }
+        Self{ colliders: collider, handles: vec![] }
+    }
+}
+
+// A clone describes new colliders, it must not share the world handles of the original.
+impl Clone for ColliderComponent {
+    fn clone(&self) -> Self {
+        Self::from(self.colliders.clone())
     }
 }
 
 impl From<Collider> for ColliderComponent {
     fn from(collider: Collider) -> Self {
-        Self{ colliders: vec![collider]}
+        Self{ colliders: vec![collider], handles: vec![] }
     }
 }
 
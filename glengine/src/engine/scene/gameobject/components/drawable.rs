@@ -1,23 +1,74 @@
 use glam::Mat4;
 
+use crate::engine::drawable::base::BaseDrawable;
+use crate::engine::drawable::bounds::Bounds;
+use crate::engine::drawable::importer::loader::{AssetHandle, ModelAsset};
 use crate::engine::drawable::Drawable;
+use crate::engine::drawable::shader::Shader;
 use crate::engine::scene::gameobject::components::Component;
 use crate::engine::scene::lights::Lights;
 
 pub struct DrawableComponent {
     pub drawable: Box<dyn Drawable>,
+    pending: Option<AssetHandle<ModelAsset>>,
 }
 
 impl DrawableComponent {
     pub fn new(drawable: Box<dyn Drawable>) -> Self {
-        Self { drawable }
+        Self { drawable, pending: None }
+    }
+
+    // Draws nothing until the model is loaded.
+    pub fn loading(handle: AssetHandle<ModelAsset>) -> Self {
+        Self::with_placeholder(handle, Box::new(BaseDrawable { draw_data: vec![] }))
+    }
+
+    // Draws the placeholder until the model is loaded, it is kept if loading fails.
+    pub fn with_placeholder(handle: AssetHandle<ModelAsset>, placeholder: Box<dyn Drawable>) -> Self {
+        Self { drawable: placeholder, pending: Some(handle) }
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    fn resolve(&mut self) {
+        let Some(handle) = &self.pending else { return };
+        if let Some(asset) = handle.get() {
+            self.drawable = Box::new(asset.drawable.clone());
+        } else if let Some(error) = handle.error() {
+            println!("{}", error);
+        } else {
+            return;
+        }
+        self.pending = None;
     }
 }
 
 impl Drawable for DrawableComponent {
     fn draw(&mut self,modelmat: &Mat4, viewmat: &Mat4, lights: Option<&Lights>) {
+        self.resolve();
         self.drawable.draw(modelmat, viewmat, lights);
     }
+
+    fn draw_shadow(&mut self, modelmat: &Mat4, shader: &Shader) {
+        self.resolve();
+        self.drawable.draw_shadow(modelmat, shader);
+    }
+
+    fn draw_geometry(&mut self, modelmat: &Mat4, viewmat: &Mat4) {
+        self.resolve();
+        self.drawable.draw_geometry(modelmat, viewmat);
+    }
+
+    fn set_lod(&mut self, lod: usize) {
+        self.drawable.set_lod(lod);
+    }
+
+    // the placeholder bounds until the model is loaded
+    fn bounds(&self) -> Bounds {
+        self.drawable.bounds()
+    }
 }
 
 impl Component for DrawableComponent {
@@ -0,0 +1,153 @@
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use glam::Vec3;
+use rapier3d::prelude::*;
+use crate::engine::scene::gameobject::base::BaseGameObject;
+use crate::engine::scene::gameobject::components::Component;
+use crate::engine::scene::gameobject::GameObject;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JointHandle {
+    Impulse(ImpulseJointHandle),
+    Multibody(MultibodyJointHandle),
+}
+
+pub(crate) struct Joint {
+    pub(crate) target: Weak<RefCell<BaseGameObject>>,
+    pub(crate) data: GenericJoint,
+    pub(crate) multibody: bool,
+    pub(crate) handle: Option<JointHandle>,
+    pub(crate) dirty: bool,
+    main_axis: JointAxis,
+}
+
+// Links the rigid body of the owning object to the rigid bodies of other objects.
+// Anchors are in the local space of each body, joints are removed together with either body.
+pub struct JointComponent {
+    pub(crate) joints: Vec<Joint>,
+}
+
+impl JointComponent {
+    pub fn new() -> Self {
+        Self { joints: vec![] }
+    }
+
+    fn with(mut self, target: &GameObject, data: GenericJoint, main_axis: JointAxis, multibody: bool) -> Self {
+        self.joints.push(Joint {
+            target: Rc::downgrade(&target.base),
+            data,
+            multibody,
+            handle: None,
+            dirty: false,
+            main_axis,
+        });
+        self
+    }
+
+    pub fn with_joint(self, target: &GameObject, joint: impl Into<GenericJoint>) -> Self {
+        self.with(target, joint.into(), JointAxis::AngX, false)
+    }
+
+    // multibody joints are solved in reduced coordinates, better suited for ragdolls and long chains
+    pub fn with_multibody_joint(self, target: &GameObject, joint: impl Into<GenericJoint>) -> Self {
+        self.with(target, joint.into(), JointAxis::AngX, true)
+    }
+
+    pub fn with_fixed(self, target: &GameObject, anchor: Vec3, target_anchor: Vec3) -> Self {
+        let joint = FixedJointBuilder::new()
+            .local_anchor1(anchor.into())
+            .local_anchor2(target_anchor.into())
+            .build();
+        self.with(target, joint.into(), JointAxis::AngX, false)
+    }
+
+    pub fn with_revolute(self, target: &GameObject, axis: Vec3, anchor: Vec3, target_anchor: Vec3) -> Self {
+        let joint = RevoluteJointBuilder::new(UnitVector::new_normalize(axis.into()))
+            .local_anchor1(anchor.into())
+            .local_anchor2(target_anchor.into())
+            .build();
+        self.with(target, joint.into(), JointAxis::AngX, false)
+    }
+
+    pub fn with_prismatic(self, target: &GameObject, axis: Vec3, anchor: Vec3, target_anchor: Vec3) -> Self {
+        let joint = PrismaticJointBuilder::new(UnitVector::new_normalize(axis.into()))
+            .local_anchor1(anchor.into())
+            .local_anchor2(target_anchor.into())
+            .build();
+        self.with(target, joint.into(), JointAxis::LinX, false)
+    }
+
+    pub fn with_spherical(self, target: &GameObject, anchor: Vec3, target_anchor: Vec3) -> Self {
+        let joint = SphericalJointBuilder::new()
+            .local_anchor1(anchor.into())
+            .local_anchor2(target_anchor.into())
+            .build();
+        self.with(target, joint.into(), JointAxis::AngX, false)
+    }
+
+    pub fn with_rope(self, target: &GameObject, max_distance: f32, anchor: Vec3, target_anchor: Vec3) -> Self {
+        let joint = RopeJointBuilder::new(max_distance)
+            .local_anchor1(anchor.into())
+            .local_anchor2(target_anchor.into())
+            .build();
+        self.with(target, joint.into(), JointAxis::LinX, false)
+    }
+
+    // limits, motors and the joint data apply to the last added joint
+    pub fn with_limits(mut self, min: f32, max: f32) -> Self {
+        self.set_limits(self.joints.len().saturating_sub(1), min, max);
+        self
+    }
+
+    pub fn with_motor_velocity(mut self, target_velocity: f32, factor: f32) -> Self {
+        self.set_motor_velocity(self.joints.len().saturating_sub(1), target_velocity, factor);
+        self
+    }
+
+    pub fn with_motor_position(mut self, target_position: f32, stiffness: f32, damping: f32) -> Self {
+        self.set_motor_position(self.joints.len().saturating_sub(1), target_position, stiffness, damping);
+        self
+    }
+
+    pub fn handle(&self, index: usize) -> Option<JointHandle> {
+        self.joints.get(index)?.handle
+    }
+
+    pub fn len(&self) -> usize {
+        self.joints.len()
+    }
+
+    pub fn data(&self, index: usize) -> Option<&GenericJoint> {
+        self.joints.get(index).map(|joint| &joint.data)
+    }
+
+    // changes are pushed to the physics world on the next fixed step, only for impulse joints
+    pub fn data_mut(&mut self, index: usize) -> Option<&mut GenericJoint> {
+        let joint = self.joints.get_mut(index)?;
+        joint.dirty = true;
+        Some(&mut joint.data)
+    }
+
+    pub fn set_limits(&mut self, index: usize, min: f32, max: f32) {
+        if let Some(joint) = self.joints.get_mut(index) {
+            joint.data.set_limits(joint.main_axis, [min, max]);
+            joint.dirty = true;
+        }
+    }
+
+    pub fn set_motor_velocity(&mut self, index: usize, target_velocity: f32, factor: f32) {
+        if let Some(joint) = self.joints.get_mut(index) {
+            joint.data.set_motor_velocity(joint.main_axis, target_velocity, factor);
+            joint.dirty = true;
+        }
+    }
+
+    pub fn set_motor_position(&mut self, index: usize, target_position: f32, stiffness: f32, damping: f32) {
+        if let Some(joint) = self.joints.get_mut(index) {
+            joint.data.set_motor_position(joint.main_axis, target_position, stiffness, damping);
+            joint.dirty = true;
+        }
+    }
+}
+
+impl Component for JointComponent {}
@@ -0,0 +1,20 @@
+use crate::engine::scene::gameobject::components::Component;
+
+// Picks the level of detail of the meshes drawn by the DrawableComponent of the same object
+// from its distance to the camera, meshes with fewer levels draw their coarsest one.
+pub struct LodComponent {
+    // distance at which each level after the first one starts, in increasing order
+    pub distances: Vec<f32>,
+}
+
+impl LodComponent {
+    pub fn new(distances: Vec<f32>) -> Self {
+        Self { distances }
+    }
+
+    pub fn level(&self, distance: f32) -> usize {
+        self.distances.iter().take_while(|start| distance >= **start).count()
+    }
+}
+
+impl Component for LodComponent {}
@@ -4,15 +4,21 @@ use std::collections::HashMap;
 use std::hash::{DefaultHasher, Hash, Hasher};
 use std::rc::Rc;
 use crate::engine::GameState;
+use crate::engine::physics::{Collision, ContactForce, PhysicsEvent};
 use crate::engine::scene::gameobject::base::GameObjectData;
 use crate::engine::scene::gameobject::components::rigidbody::RigidBodyComponent;
-use crate::result::EngineStepResult;
+use crate::engine::scene::gameobject::GameObject;
+use crate::result::{EngineFixedStepResult, EngineStepResult};
 
 pub mod freecam;
 pub mod drawable;
 pub mod rotating;
 pub mod rigidbody;
 pub mod collider;
+pub mod joint;
+pub mod character;
+pub mod animator;
+pub mod lod;
 
 pub trait Component{
     fn setup(&mut self, _object: &mut GameObjectData, _components: &ComponentMap){}
@@ -30,6 +36,41 @@ pub trait Component{
         _state: &GameState,
     ) -> EngineStepResult<()> {
         Ok(())}
+    fn on_collision_enter(
+        &mut self,
+        _object: &mut GameObjectData,
+        _components: &ComponentMap,
+        _collision: &Collision,
+    ) -> EngineFixedStepResult<()> {
+        Ok(())}
+    fn on_collision_exit(
+        &mut self,
+        _object: &mut GameObjectData,
+        _components: &ComponentMap,
+        _collision: &Collision,
+    ) -> EngineFixedStepResult<()> {
+        Ok(())}
+    fn on_trigger_enter(
+        &mut self,
+        _object: &mut GameObjectData,
+        _components: &ComponentMap,
+        _other: &GameObject,
+    ) -> EngineFixedStepResult<()> {
+        Ok(())}
+    fn on_trigger_exit(
+        &mut self,
+        _object: &mut GameObjectData,
+        _components: &ComponentMap,
+        _other: &GameObject,
+    ) -> EngineFixedStepResult<()> {
+        Ok(())}
+    fn on_contact_force(
+        &mut self,
+        _object: &mut GameObjectData,
+        _components: &ComponentMap,
+        _force: &ContactForce,
+    ) -> EngineFixedStepResult<()> {
+        Ok(())}
 }
 
 pub struct ComponentMap {
@@ -67,7 +108,11 @@ impl ComponentMap {
         }
     }
 
-    fn calculate_transform_hash(object:&mut GameObjectData) -> u64 {
+    pub(crate) fn sync_transform_hash(&mut self, object: &GameObjectData) {
+        self.transform_hash = Self::calculate_transform_hash(object);
+    }
+
+    fn calculate_transform_hash(object:&GameObjectData) -> u64 {
         let mut hasher = DefaultHasher::new();
         object.transform.hash(&mut hasher);
         hasher.finish()
@@ -90,4 +135,17 @@ impl ComponentMap {
         self.apply_transform_to_physics(object);
         Ok(())
     }
+    pub fn on_physics_event(&mut self, object: &mut GameObjectData, event: &PhysicsEvent) -> EngineFixedStepResult<()> {
+        for (_, component) in self.elements.iter() {
+            let mut component = component.borrow_mut();
+            match event {
+                PhysicsEvent::CollisionEnter(collision) => component.on_collision_enter(object, self, collision),
+                PhysicsEvent::CollisionExit(collision) => component.on_collision_exit(object, self, collision),
+                PhysicsEvent::TriggerEnter(other) => component.on_trigger_enter(object, self, other),
+                PhysicsEvent::TriggerExit(other) => component.on_trigger_exit(object, self, other),
+                PhysicsEvent::ContactForce(force) => component.on_contact_force(object, self, force),
+            }?
+        }
+        Ok(())
+    }
 }
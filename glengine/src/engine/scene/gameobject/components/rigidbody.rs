@@ -4,29 +4,56 @@ use crate::engine::scene::gameobject::base::GameObjectData;
 use crate::engine::scene::gameobject::components::{Component, ComponentMap};
 use crate::engine::transform::Transform;
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct RigidBodyComponent {
-    pub rigid_body: RigidBody
+    pub rigid_body: RigidBody,
+    handle: Option<RigidBodyHandle>,
+    dirty: bool,
 }
 
 impl RigidBodyComponent {
     pub fn set_transform(&mut self, transform:&Transform) {
         self.rigid_body.set_translation(transform.position.into(),true);
         self.rigid_body.set_rotation(transform.rotation.into(),true);
+        self.dirty = true;
     }
-    
+
     pub fn get_rotation(&self)->Quat{
         self.rigid_body.rotation().quaternion().clone().into()
     }
-    
+
     pub fn get_position(&self)->Vec3{
         self.rigid_body.translation().clone().into()
     }
+
+    pub fn handle(&self) -> Option<RigidBodyHandle> {
+        self.handle
+    }
+
+    pub(crate) fn set_handle(&mut self, handle: RigidBodyHandle) {
+        self.handle = Some(handle);
+        self.dirty = false;
+    }
+
+    pub(crate) fn clear_handle(&mut self) {
+        self.handle = None;
+    }
+
+    pub(crate) fn take_dirty(&mut self) -> bool {
+        std::mem::replace(&mut self.dirty, false)
+    }
+}
+
+// A clone describes a new body, it must not share the world handle of the original.
+impl Clone for RigidBodyComponent {
+    fn clone(&self) -> Self {
+        Self::from(self.rigid_body.clone())
+    }
 }
 
 impl From<RigidBody> for RigidBodyComponent {
     fn from(rigid_body: RigidBody) -> Self {
-        Self{rigid_body}
+        Self{rigid_body, handle: None, dirty: false}
     }
 }
 
@@ -3,12 +3,13 @@ use std::rc::Rc;
 use glam::Mat4;
 use crate::engine::drawable::Drawable;
 use crate::engine::GameState;
+use crate::engine::physics::PhysicsEvent;
 use crate::engine::transform::Transform;
 use crate::engine::scene::gameobject::base::{BaseGameObject, GameObjectData};
 use crate::engine::scene::gameobject::components::collider::ColliderComponent;
 use crate::engine::scene::gameobject::components::Component;
 use crate::engine::scene::gameobject::components::rigidbody::RigidBodyComponent;
-use crate::result::EngineStepResult;
+use crate::result::{EngineFixedStepResult, EngineStepResult};
 
 pub mod base;
 pub mod components;
@@ -62,6 +63,10 @@ impl GameObject{
         }
         base.fixed_step(state,physics_components)
     }
+    pub fn on_physics_event(&self, event: &PhysicsEvent) -> EngineFixedStepResult<()> {
+        self.base.borrow_mut().on_physics_event(event)
+    }
+
     pub fn add_component<T: Component+'static >(&mut self, component: T) {
         self.base.borrow_mut().add_component(component);
     }
@@ -69,4 +74,22 @@ impl GameObject{
     pub fn global_mat(&self) -> Mat4 {
         self.base.borrow().global_mat()
     }
+
+    pub fn with_name(self, name: &str) -> Self {
+        self.base.borrow_mut().data.name = name.to_string();
+        self
+    }
+
+    pub fn name(&self) -> String {
+        self.base.borrow().data.name.clone()
+    }
+
+    // Depth first search of this object and its descendants
+    pub fn find(&self, name: &str) -> Option<GameObject> {
+        let base = self.base.borrow();
+        if base.data.name == name {
+            return Some(self.clone());
+        }
+        base.data.children.iter().find_map(|child| child.find(name))
+    }
 }
@@ -0,0 +1,189 @@
+use std::mem;
+
+use gl::types::{GLsizeiptr, GLuint};
+use glam::Mat4;
+use glsl_layout::{float, int, Uniform};
+
+use crate::engine::config::CONFIG;
+use crate::engine::drawable::manager::DRAWABLE_MANAGER;
+use crate::engine::drawable::shader::manager::IncludedShaderHandle;
+use crate::engine::drawable::shader::Shader;
+use crate::result::{EngineRenderError, EngineRenderResult};
+
+// the view frustum is split in screen tiles and exponential depth slices
+pub const CLUSTERS_X: u32 = 16;
+pub const CLUSTERS_Y: u32 = 9;
+pub const CLUSTERS_Z: u32 = 24;
+pub const MAX_LIGHTS_PER_CLUSTER: u32 = 128;
+
+pub const POINT_LIGHTS_BINDING: u32 = 6;
+pub const SPOT_LIGHTS_BINDING: u32 = 7;
+pub const CLUSTER_GRID_BINDING: u32 = 8;
+pub const CLUSTER_INDICES_BINDING: u32 = 9;
+// the lit fragment shaders read the lights, the cluster grid and the cluster indices
+pub const FRAGMENT_STORAGE_BLOCKS: i32 = 4;
+
+// OpenGL ES 3.1 only guarantees storage buffers in compute shaders, the fragment stage may have none.
+pub fn check_storage_blocks() -> EngineRenderResult<()> {
+    let mut max_blocks = 0;
+    unsafe {
+        gl::GetIntegerv(gl::MAX_FRAGMENT_SHADER_STORAGE_BLOCKS, &mut max_blocks);
+    }
+    if max_blocks < FRAGMENT_STORAGE_BLOCKS {
+        return Err(EngineRenderError::UnsupportedContext(format!(
+            "clustered lighting needs {} fragment shader storage blocks, the driver supports {}",
+            FRAGMENT_STORAGE_BLOCKS, max_blocks
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Copy, Default, Clone, Uniform)]
+pub struct ClusterData {
+    pub size_x: int,
+    pub size_y: int,
+    pub size_z: int,
+    pub near: float,
+    pub far: float,
+    pub tan_half_fov: float,
+    pub aspect: float,
+    pub screen_width: float,
+    pub screen_height: float,
+}
+
+impl ClusterData {
+    pub fn from_config() -> Self {
+        let config = CONFIG.config();
+        let (near, far) = config.get_clip();
+        let (width, height) = config.get_resolution();
+        Self {
+            size_x: CLUSTERS_X as i32,
+            size_y: CLUSTERS_Y as i32,
+            size_z: CLUSTERS_Z as i32,
+            near,
+            far,
+            tan_half_fov: (config.get_fov().to_radians() * 0.5).tan(),
+            aspect: width as f32 / height as f32,
+            screen_width: width as f32,
+            screen_height: height as f32,
+        }
+    }
+}
+
+pub(crate) fn as_bytes<T>(data: &[T]) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u8, mem::size_of_val(data)) }
+}
+
+// A shader storage buffer that grows to fit the data uploaded to it.
+pub struct StorageBuffer {
+    pub id: GLuint,
+    capacity: usize,
+}
+
+impl StorageBuffer {
+    pub fn new(capacity: usize) -> Self {
+        let mut id = 0;
+        // an empty buffer can not be bound, keep at least one vec4 around
+        let capacity = capacity.max(16);
+        unsafe {
+            gl::GenBuffers(1, &mut id);
+            gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, id);
+            gl::BufferData(
+                gl::SHADER_STORAGE_BUFFER,
+                capacity as GLsizeiptr,
+                std::ptr::null(),
+                gl::DYNAMIC_DRAW,
+            );
+            gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, 0);
+        }
+        Self { id, capacity }
+    }
+
+    pub fn upload(&mut self, data: &[u8]) {
+        unsafe {
+            gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, self.id);
+            if data.len() > self.capacity {
+                self.capacity = data.len().next_power_of_two();
+                gl::BufferData(
+                    gl::SHADER_STORAGE_BUFFER,
+                    self.capacity as GLsizeiptr,
+                    std::ptr::null(),
+                    gl::DYNAMIC_DRAW,
+                );
+            }
+            if !data.is_empty() {
+                gl::BufferSubData(
+                    gl::SHADER_STORAGE_BUFFER,
+                    0,
+                    data.len() as GLsizeiptr,
+                    data.as_ptr() as *const _,
+                );
+            }
+            gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, 0);
+        }
+    }
+
+    pub fn bind(&self, binding: u32) {
+        unsafe {
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, binding, self.id);
+        }
+    }
+
+    pub fn unbind(binding: u32) {
+        unsafe {
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, binding, 0);
+        }
+    }
+}
+
+impl Drop for StorageBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.id);
+        }
+    }
+}
+
+// Per cluster light lists, filled on the GPU every frame by the light culling compute shader.
+pub struct LightClusters {
+    // point and spot light counts of every cluster
+    grid: StorageBuffer,
+    // MAX_LIGHTS_PER_CLUSTER indices per cluster, the point lights first
+    indices: StorageBuffer,
+}
+
+impl LightClusters {
+    pub fn new() -> Self {
+        let clusters = (CLUSTERS_X * CLUSTERS_Y * CLUSTERS_Z) as usize;
+        Self {
+            grid: StorageBuffer::new(clusters * mem::size_of::<[u32; 4]>()),
+            indices: StorageBuffer::new(clusters * MAX_LIGHTS_PER_CLUSTER as usize * mem::size_of::<u32>()),
+        }
+    }
+
+    // The lights uniform and storage buffers have to be bound before culling.
+    pub fn cull(&self, viewmat: &Mat4) {
+        self.bind();
+        DRAWABLE_MANAGER.with(|dm| {
+            let draw_manager = dm.borrow();
+            let shader = draw_manager.shader.get_included(&IncludedShaderHandle::LightCulling);
+            shader.use_program();
+            shader.set_mat4("view_mat", viewmat);
+            unsafe {
+                gl::DispatchCompute(1, CLUSTERS_Y, CLUSTERS_Z);
+                gl::MemoryBarrier(gl::SHADER_STORAGE_BARRIER_BIT);
+            }
+        });
+        Shader::unbind();
+    }
+
+    pub fn bind(&self) {
+        self.grid.bind(CLUSTER_GRID_BINDING);
+        self.indices.bind(CLUSTER_INDICES_BINDING);
+    }
+
+    pub fn unbind() {
+        StorageBuffer::unbind(CLUSTER_GRID_BINDING);
+        StorageBuffer::unbind(CLUSTER_INDICES_BINDING);
+    }
+}
@@ -1,18 +1,24 @@
 use std::cell::RefCell;
 use std::rc::{Rc, Weak};
 
-use glam::Vec3;
-use glsl_layout::{float, vec3, Uniform};
+use glam::{Mat4, Vec3};
+use glsl_layout::{float, int, mat4, vec3, Uniform};
 
 use crate::engine::scene::gameobject::components::ComponentMap;
 use crate::engine::scene::gameobject::{GameObject};
 use crate::engine::scene::gameobject::base::{BaseGameObject, GameObjectData};
+use crate::engine::scene::lights::shadow::{ShadowSettings, SHADOW_CASCADES};
 
 #[derive(Debug, Copy, Default, Clone, Uniform)]
 pub struct DirectionalLightData {
     pub intensity: float,
     pub color: vec3,
     pub direction: vec3,
+    pub cascade_count: int,
+    pub bias: float,
+    pub normal_bias: float,
+    pub pcf_radius: int,
+    pub light_space: [mat4; SHADOW_CASCADES],
 }
 
 impl DirectionalLightData {
@@ -21,6 +27,11 @@ impl DirectionalLightData {
             intensity: 0.0,
             color: vec3::from([0.0, 0.0, 0.0]),
             direction: vec3::from([0.0, 0.0, 0.0]),
+            cascade_count: 0,
+            bias: 0.0,
+            normal_bias: 0.0,
+            pcf_radius: 0,
+            light_space: [mat4::default(); SHADOW_CASCADES],
         }
     }
 }
@@ -29,6 +40,8 @@ pub struct DirectionalLight {
     pub game_object: Weak<RefCell<BaseGameObject>>,
     pub intensity: f32,
     pub color: Vec3,
+    pub shadow: ShadowSettings,
+    pub(crate) cascades: Option<[Mat4; SHADOW_CASCADES]>,
 }
 impl DirectionalLight {
     pub fn new(parent: Option<GameObject>, intensity: f32, color: Vec3) -> Self {
@@ -40,6 +53,8 @@ impl DirectionalLight {
             game_object: Rc::downgrade(&game_object.base),
             intensity,
             color,
+            shadow: ShadowSettings::default(),
+            cascades: None,
         };
         if let Some(parent) = parent {
             parent.base
@@ -56,16 +71,33 @@ impl DirectionalLight {
             game_object: Rc::downgrade(&game_object.base),
             intensity,
             color,
+            shadow: ShadowSettings::default(),
+            cascades: None,
         };
         light
     }
 
+    pub fn with_shadow(mut self, shadow: ShadowSettings) -> Self {
+        self.shadow = shadow;
+        self
+    }
+
+    pub fn direction(&self) -> Option<Vec3> {
+        Some(self.game_object.upgrade()?.borrow().data.transform.forward())
+    }
+
     pub fn light_data(&self) -> Option<DirectionalLightData> {
-        let direction = self.game_object.upgrade()?.borrow().data.transform.forward();
+        let direction = self.direction()?;
+        let cascades = self.cascades.unwrap_or([Mat4::IDENTITY; SHADOW_CASCADES]);
         Some(DirectionalLightData {
             intensity: self.intensity,
             color: vec3::from([self.color.x, self.color.y, self.color.z]),
             direction: vec3::from([direction.x, direction.y, direction.z]),
+            cascade_count: if self.cascades.is_some() { SHADOW_CASCADES as i32 } else { 0 },
+            bias: self.shadow.bias,
+            normal_bias: self.shadow.normal_bias,
+            pcf_radius: self.shadow.pcf_radius,
+            light_space: cascades.map(|cascade| cascade.to_cols_array_2d().into()),
         })
     }
 }
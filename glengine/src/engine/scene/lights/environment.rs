@@ -0,0 +1,271 @@
+use gl::types::{GLenum, GLint, GLuint};
+use glam::{Mat4, Vec3};
+
+use crate::engine::drawable::manager::DRAWABLE_MANAGER;
+use crate::engine::drawable::mesh::manager::MeshHandle;
+use crate::engine::drawable::mesh::{cube, screenquad, MeshData};
+use crate::engine::drawable::shader::{ibl, Shader};
+use crate::engine::scene::lights::shadow::cube_face_spaces;
+use crate::result::{EngineRenderError, EngineRenderResult};
+
+const ENVIRONMENT_RESOLUTION: u32 = 512;
+const IRRADIANCE_RESOLUTION: u32 = 32;
+const PREFILTERED_RESOLUTION: u32 = 128;
+pub const PREFILTERED_MIPS: u32 = 5;
+const BRDF_LUT_RESOLUTION: u32 = 512;
+
+// Image based lighting of the scene. The irradiance, prefiltered specular and BRDF lookup
+// textures are precomputed on the GPU once, when the environment is loaded.
+pub struct Environment {
+    pub environment: GLuint,
+    pub irradiance: GLuint,
+    pub prefiltered: GLuint,
+    pub brdf_lut: GLuint,
+    pub intensity: f32,
+}
+
+impl Environment {
+    // Loads an equirectangular (usually .hdr) image, must be called with a current GL context.
+    pub fn from_equirectangular(path: &str) -> EngineRenderResult<Self> {
+        let image = image::open(path)
+            .map_err(|e| EngineRenderError::TextureLoadError(format!("{}: {}", path, e)))?
+            .into_rgb32f();
+        let mut equirectangular = 0;
+        unsafe {
+            gl::GenTextures(1, &mut equirectangular);
+            gl::BindTexture(gl::TEXTURE_2D, equirectangular);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGB16F as i32,
+                image.width() as i32,
+                image.height() as i32,
+                0,
+                gl::RGB,
+                gl::FLOAT,
+                image.as_raw().as_ptr() as *const std::ffi::c_void,
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+        let environment = Self::generate(equirectangular);
+        unsafe {
+            gl::DeleteTextures(1, &equirectangular);
+        }
+        environment
+    }
+
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    fn generate(equirectangular: GLuint) -> EngineRenderResult<Self> {
+        let equirectangular_shader = ibl::new_equirectangular_shader()?;
+        let irradiance_shader = ibl::new_irradiance_shader()?;
+        let prefilter_shader = ibl::new_prefilter_shader()?;
+        let brdf_shader = ibl::new_brdf_shader()?;
+
+        let environment = cube_texture(ENVIRONMENT_RESOLUTION, mip_levels(ENVIRONMENT_RESOLUTION));
+        let irradiance = cube_texture(IRRADIANCE_RESOLUTION, 1);
+        let prefiltered = cube_texture(PREFILTERED_RESOLUTION, PREFILTERED_MIPS);
+        let brdf_lut = unsafe {
+            let mut texture = 0;
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::TexStorage2D(gl::TEXTURE_2D, 1, gl::RG16F, BRDF_LUT_RESOLUTION as i32, BRDF_LUT_RESOLUTION as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            texture
+        };
+
+        let capture = Capture::new();
+        let cube = cube::new();
+        let faces = cube_face_spaces(Vec3::ZERO, 0.1, 10.0);
+
+        equirectangular_shader.use_program();
+        equirectangular_shader.set_texture("equirectangular_map", equirectangular, 0, gl::TEXTURE_2D);
+        capture.render_cube(&cube, &equirectangular_shader, &faces, environment, ENVIRONMENT_RESOLUTION, 0);
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, environment);
+            gl::GenerateMipmap(gl::TEXTURE_CUBE_MAP);
+        }
+
+        irradiance_shader.use_program();
+        irradiance_shader.set_texture("environment_map", environment, 0, gl::TEXTURE_CUBE_MAP);
+        capture.render_cube(&cube, &irradiance_shader, &faces, irradiance, IRRADIANCE_RESOLUTION, 0);
+
+        prefilter_shader.use_program();
+        prefilter_shader.set_texture("environment_map", environment, 0, gl::TEXTURE_CUBE_MAP);
+        prefilter_shader.set_float("resolution", ENVIRONMENT_RESOLUTION as f32);
+        for mip in 0..PREFILTERED_MIPS {
+            let roughness = mip as f32 / (PREFILTERED_MIPS - 1) as f32;
+            prefilter_shader.set_float("roughness", roughness);
+            capture.render_cube(&cube, &prefilter_shader, &faces, prefiltered, PREFILTERED_RESOLUTION >> mip, mip);
+        }
+
+        brdf_shader.use_program();
+        capture.render_quad(brdf_lut, BRDF_LUT_RESOLUTION);
+
+        Shader::unbind();
+        MeshData::unbind();
+        drop(capture);
+
+        Ok(Self {
+            environment,
+            irradiance,
+            prefiltered,
+            brdf_lut,
+            intensity: 1.0,
+        })
+    }
+
+    // only the shaders with image based lighting have these uniforms
+    pub fn bind(&self, shader: &Shader) {
+        shader.add_texture("irradiance_map", self.irradiance, gl::TEXTURE_CUBE_MAP);
+        shader.add_texture("prefiltered_map", self.prefiltered, gl::TEXTURE_CUBE_MAP);
+        shader.add_texture("brdf_lut", self.brdf_lut, gl::TEXTURE_2D);
+        shader.set_optional("has_environment", &1.0f32);
+        shader.set_optional("environment_intensity", &self.intensity);
+        shader.set_optional("prefiltered_mips", &((PREFILTERED_MIPS - 1) as f32));
+    }
+
+    // Without an environment the samplers still get their own units, see MATERIAL_TEXTURES.
+    pub fn bind_empty(shader: &Shader) {
+        shader.add_texture("irradiance_map", 0, gl::TEXTURE_CUBE_MAP);
+        shader.add_texture("prefiltered_map", 0, gl::TEXTURE_CUBE_MAP);
+        shader.add_texture("brdf_lut", 0, gl::TEXTURE_2D);
+        shader.set_optional("has_environment", &0.0f32);
+    }
+}
+
+impl Drop for Environment {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.environment);
+            gl::DeleteTextures(1, &self.irradiance);
+            gl::DeleteTextures(1, &self.prefiltered);
+            gl::DeleteTextures(1, &self.brdf_lut);
+        }
+    }
+}
+
+fn mip_levels(resolution: u32) -> u32 {
+    32 - resolution.leading_zeros()
+}
+
+fn cube_texture(resolution: u32, levels: u32) -> GLuint {
+    let mut texture = 0;
+    let min_filter = if levels > 1 { gl::LINEAR_MIPMAP_LINEAR } else { gl::LINEAR };
+    unsafe {
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, texture);
+        gl::TexStorage2D(gl::TEXTURE_CUBE_MAP, levels as i32, gl::RGBA16F, resolution as i32, resolution as i32);
+        gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MIN_FILTER, min_filter as i32);
+        gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+    }
+    texture
+}
+
+// Framebuffer the precomputation renders into, the previous framebuffer, viewport
+// and render state are restored when it is dropped.
+struct Capture {
+    fbo: GLuint,
+    previous_fbo: GLint,
+    previous_viewport: [GLint; 4],
+    cull_face: bool,
+    depth_test: bool,
+    blend: bool,
+}
+
+impl Capture {
+    fn new() -> Self {
+        let mut capture = Self {
+            fbo: 0,
+            previous_fbo: 0,
+            previous_viewport: [0; 4],
+            cull_face: false,
+            depth_test: false,
+            blend: false,
+        };
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut capture.previous_fbo);
+            gl::GetIntegerv(gl::VIEWPORT, capture.previous_viewport.as_mut_ptr());
+            capture.cull_face = gl::IsEnabled(gl::CULL_FACE) == gl::TRUE;
+            capture.depth_test = gl::IsEnabled(gl::DEPTH_TEST) == gl::TRUE;
+            capture.blend = gl::IsEnabled(gl::BLEND) == gl::TRUE;
+            // the cube is seen from the inside and every face covers the whole target
+            gl::Disable(gl::CULL_FACE);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Disable(gl::BLEND);
+            gl::GenFramebuffers(1, &mut capture.fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, capture.fbo);
+        }
+        capture
+    }
+
+    fn attach(&self, target: GLenum, texture: GLuint, resolution: u32, mip: u32) {
+        unsafe {
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, target, texture, mip as i32);
+            gl::Viewport(0, 0, resolution as i32, resolution as i32);
+        }
+    }
+
+    fn render_cube(&self, cube: &MeshHandle, shader: &Shader, faces: &[Mat4; 6], texture: GLuint, resolution: u32, mip: u32) {
+        DRAWABLE_MANAGER.with(|dm| {
+            let draw_manager = dm.borrow();
+            let mesh = draw_manager.mesh.get(cube).expect("Mesh not found");
+            mesh.bind();
+            for (face, capture_mat) in faces.iter().enumerate() {
+                self.attach(gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as GLenum, texture, resolution, mip);
+                shader.set_mat4("capture_mat", capture_mat);
+                mesh.draw();
+            }
+        });
+    }
+
+    fn render_quad(&self, texture: GLuint, resolution: u32) {
+        let quad = screenquad::new();
+        self.attach(gl::TEXTURE_2D, texture, resolution, 0);
+        DRAWABLE_MANAGER.with(|dm| {
+            let draw_manager = dm.borrow();
+            let mesh = draw_manager.mesh.get(&quad).expect("Mesh not found");
+            mesh.bind();
+            mesh.draw();
+        });
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.previous_fbo as GLuint);
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::Viewport(
+                self.previous_viewport[0],
+                self.previous_viewport[1],
+                self.previous_viewport[2],
+                self.previous_viewport[3],
+            );
+            if self.cull_face {
+                gl::Enable(gl::CULL_FACE);
+            }
+            if self.depth_test {
+                gl::Enable(gl::DEPTH_TEST);
+            }
+            if self.blend {
+                gl::Enable(gl::BLEND);
+            }
+        }
+    }
+}
@@ -1,27 +1,51 @@
 use gl::types::{GLsizeiptr, GLuint};
+use glam::{Mat4, Vec3};
 use glsl_layout::{boolean, int, Std140, Uniform};
 use std::cell::RefCell;
 use std::mem;
 use std::rc::Weak;
 
+use cluster::{as_bytes, ClusterData, LightClusters, StorageBuffer, POINT_LIGHTS_BINDING, SPOT_LIGHTS_BINDING};
+use environment::Environment;
 use directional::{DirectionalLight, DirectionalLightData};
 use point::{PointLight, PointLightData};
+use shadow::{ShadowMaps, MAX_POINT_SHADOWS, MAX_SPOT_SHADOWS};
 use spot::{SpotLight, SpotLightData};
+use crate::engine::drawable::shader::Shader;
+use crate::engine::scene::camera::Camera;
+use crate::engine::scene::gameobject::GameObject;
 
+pub mod cluster;
 pub mod directional;
+pub mod environment;
 pub mod point;
+pub mod shadow;
 pub mod spot;
 
-const MAX_POINT_LIGHTS: usize = 5;
-const MAX_SPOT_LIGHTS: usize = 5;
+// contribution below which a light is considered out of range
+const LIGHT_CUTOFF: f32 = 1.0 / 256.0;
+
+// Distance at which an attenuated light stops contributing, used to cull it from the clusters.
+pub(crate) fn attenuation_range(intensity: f32, color: Vec3, constant: f32, linear: f32, quadratic: f32) -> f32 {
+    let peak = intensity * color.max_element() / LIGHT_CUTOFF;
+    if quadratic > 0.0 {
+        let discriminant = (linear * linear - 4.0 * quadratic * (constant - peak)).max(0.0);
+        (-linear + discriminant.sqrt()) / (2.0 * quadratic)
+    } else if linear > 0.0 {
+        ((peak - constant) / linear).max(0.0)
+    } else {
+        f32::MAX
+    }
+}
+
+// The point and spot lights live in storage buffers, only their counts are in the uniform block.
 #[derive(Debug, Copy,Default, Clone, Uniform)]
 pub struct LightsData {
     pub is_directional: boolean,
     pub directional: DirectionalLightData,
     pub point_count: int,
-    pub point: [PointLightData; MAX_POINT_LIGHTS],
     pub spot_count: int,
-    pub spot: [SpotLightData; MAX_SPOT_LIGHTS],
+    pub clusters: ClusterData,
 }
 
 pub struct Lights {
@@ -29,55 +53,117 @@ pub struct Lights {
     pub point: Vec<PointLight>,
     pub spot: Vec<SpotLight>,
     pub ssbo: GLuint,
+    pub point_buffer: Option<StorageBuffer>,
+    pub spot_buffer: Option<StorageBuffer>,
+    pub clusters: Option<LightClusters>,
+    pub shadow_maps: Option<ShadowMaps>,
+    pub environment: Option<Environment>,
 }
 
 impl Lights {
+    // drops the lights whose game object is gone
+    fn prune(&mut self) {
+        if self.directional.as_ref().is_some_and(|light| light.game_object.upgrade().is_none()) {
+            self.directional = None;
+        }
+        self.point.retain(|light| light.game_object.upgrade().is_some());
+        self.spot.retain(|light| light.game_object.upgrade().is_some());
+    }
+
     pub fn light_data(&mut self) -> LightsData {
-        let (directional, is_directional) = match &self.directional {
-            Some(light) => match light.light_data()
-            {
-                Some(light_data) => (light_data, true),
-                None => {
-                    self.directional = None;
-                    (DirectionalLightData::empty(), false) },
-            }
+        self.prune();
+        let (directional, is_directional) = match self.directional.as_ref().and_then(|light| light.light_data()) {
+            Some(light_data) => (light_data, true),
             None => (DirectionalLightData::empty(), false),
         };
+        LightsData {
+            is_directional: is_directional.into(),
+            directional,
+            point_count: self.point.len() as i32,
+            spot_count: self.spot.len() as i32,
+            clusters: ClusterData::from_config(),
+        }
+    }
+
+    pub fn point_data(&self) -> Vec<PointLightData> {
+        self.point.iter().map(|light| light.light_data().unwrap_or(PointLightData::empty())).collect()
+    }
+
+    pub fn spot_data(&self) -> Vec<SpotLightData> {
+        self.spot.iter().map(|light| light.light_data().unwrap_or(SpotLightData::empty())).collect()
+    }
+
+    pub fn init_shadow_maps(&mut self) {
+        self.shadow_maps = Some(ShadowMaps::new());
+    }
+
+    // Assigns the shadow map slots for this frame and renders the depth of the given objects
+    // from every shadow casting light. Lights past the slot limits are lit without shadows.
+    pub fn render_shadows(&mut self, objects: &Vec<GameObject>, camera: &Camera) {
+        self.prune();
+        if self.shadow_maps.is_none() {
+            return;
+        }
+        if let Some(light) = &mut self.directional {
+            light.cascades = match light.direction() {
+                Some(direction) if light.shadow.enabled => {
+                    Some(shadow::directional_cascades(direction, camera, &light.shadow))
+                }
+                _ => None,
+            };
+        }
 
-        let mut point = [PointLightData::empty(); MAX_POINT_LIGHTS];
-        let mut point_count = 0;
-        self.point.retain(|light|
-            {
-                if let Some(light_data) = light.light_data() {
-                    point[point_count] = light_data;
-                    point_count += 1;
-                    true
-                } else {
-                    false
+        let mut spot_index = 0;
+        for light in self.spot.iter_mut() {
+            light.shadow_slot = None;
+            if !light.shadow.enabled || spot_index >= MAX_SPOT_SHADOWS {
+                continue;
+            }
+            if let Some(light_space) = light.light_space() {
+                light.shadow_slot = Some((spot_index, light_space));
+                spot_index += 1;
+            }
+        }
+
+        let mut point_index = 0;
+        for light in self.point.iter_mut() {
+            light.shadow_index = None;
+            if light.shadow.enabled && point_index < MAX_POINT_SHADOWS {
+                light.shadow_index = Some(point_index);
+                point_index += 1;
+            }
+        }
+
+        let Some(shadow_maps) = &self.shadow_maps else {
+            return;
+        };
+        shadow_maps.with_shadow_pass(|maps, shader| {
+            if let Some(cascades) = self.directional.as_ref().and_then(|light| light.cascades) {
+                maps.render_directional(objects, shader, &cascades);
+            }
+            for light in &self.spot {
+                if let Some((index, light_space)) = &light.shadow_slot {
+                    maps.render_spot(objects, shader, *index, light_space);
                 }
             }
-        );
-
-        let mut spot = [SpotLightData::empty(); MAX_SPOT_LIGHTS];
-        let mut spot_count = 0;
-        self.spot.retain(|light|
-            {
-                if let Some(light_data) = light.light_data() {
-                    spot[spot_count] = light_data;
-                    spot_count += 1;
-                    true
-                } else {
-                    false
+            for light in &self.point {
+                if let (Some(index), Some(position)) = (light.shadow_index, light.position()) {
+                    maps.render_point(objects, shader, index, &shadow::point_light_spaces(position, &light.shadow));
                 }
             }
-        );
-        LightsData {
-            is_directional: is_directional.into(),
-            directional,
-            point_count: point_count as i32,
-            point ,
-            spot_count: spot_count as i32,
-            spot,
+        });
+    }
+
+    pub fn bind_shadow_maps(&self, shader: &Shader) {
+        if let Some(shadow_maps) = &self.shadow_maps {
+            shadow_maps.bind(shader);
+        }
+    }
+
+    pub fn bind_environment(&self, shader: &Shader) {
+        match &self.environment {
+            Some(environment) => environment.bind(shader),
+            None => Environment::bind_empty(shader),
         }
     }
 
@@ -88,15 +174,19 @@ impl Lights {
             gl::BindBuffer(gl::UNIFORM_BUFFER, self.ssbo);
             gl::BufferData(
                 gl::UNIFORM_BUFFER,
-                mem::size_of::<LightsData>() as GLsizeiptr,
+                mem::size_of::<<LightsData as Uniform>::Std140>() as GLsizeiptr,
                 empty.std140().as_raw().as_ptr() as *const _,
                 gl::DYNAMIC_DRAW,
             );
             gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
         }
+        self.point_buffer = Some(StorageBuffer::new(0));
+        self.spot_buffer = Some(StorageBuffer::new(0));
+        self.clusters = Some(LightClusters::new());
     }
 
-    pub fn update_ssbo(&mut self) {
+    // Uploads the lights and sorts them into the clusters of the given view.
+    pub fn update_ssbo(&mut self, viewmat: &Mat4) {
         let data = self.light_data();
         unsafe {
             gl::BindBuffer(gl::UNIFORM_BUFFER, self.ssbo);
@@ -104,23 +194,48 @@ impl Lights {
             gl::BufferSubData(
                 gl::UNIFORM_BUFFER,
                 0,
-                mem::size_of::<LightsData>() as GLsizeiptr,
+                mem::size_of::<<LightsData as Uniform>::Std140>() as GLsizeiptr,
                 data.std140().as_raw().as_ptr() as *const _,
             );
 
             gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
         }
+        if let Some(buffer) = &mut self.point_buffer {
+            let point: Vec<_> = self.point_data().iter().map(|light| light.std140()).collect();
+            buffer.upload(as_bytes(&point));
+        }
+        if let Some(buffer) = &mut self.spot_buffer {
+            let spot: Vec<_> = self.spot_data().iter().map(|light| light.std140()).collect();
+            buffer.upload(as_bytes(&spot));
+        }
+        if let Some(clusters) = &self.clusters {
+            self.bind(5);
+            clusters.cull(viewmat);
+            Self::unbind(5);
+        }
     }
     pub fn bind(&self, binding: u32) {
         unsafe {
             gl::BindBufferBase(gl::UNIFORM_BUFFER, binding, self.ssbo);
         }
+        if let Some(buffer) = &self.point_buffer {
+            buffer.bind(POINT_LIGHTS_BINDING);
+        }
+        if let Some(buffer) = &self.spot_buffer {
+            buffer.bind(SPOT_LIGHTS_BINDING);
+        }
+        if let Some(clusters) = &self.clusters {
+            clusters.bind();
+        }
     }
 
     pub fn unbind(binding: u32) {
         unsafe {
             gl::BindBufferBase(gl::UNIFORM_BUFFER, binding, 0);
         }
+        StorageBuffer::unbind(POINT_LIGHTS_BINDING);
+        StorageBuffer::unbind(SPOT_LIGHTS_BINDING);
+        LightClusters::unbind();
     }
 }
 
@@ -141,6 +256,11 @@ impl Default for Lights {
             point: Vec::new(),
             spot: Vec::new(),
             ssbo: 0,
+            point_buffer: None,
+            spot_buffer: None,
+            clusters: None,
+            shadow_maps: None,
+            environment: None,
         }
     }
 }
@@ -2,11 +2,13 @@ use std::cell::RefCell;
 use std::rc::{Rc, Weak};
 
 use glam::Vec3;
-use glsl_layout::{float, vec3, Uniform};
+use glsl_layout::{float, int, vec3, Uniform};
 
 use crate::engine::scene::gameobject::components::ComponentMap;
 use crate::engine::scene::gameobject::{GameObject};
 use crate::engine::scene::gameobject::base::{BaseGameObject, GameObjectData};
+use crate::engine::scene::lights::attenuation_range;
+use crate::engine::scene::lights::shadow::ShadowSettings;
 use crate::engine::transform::Transform;
 
 #[derive(Debug, Copy, Default, Clone, Uniform)]
@@ -17,6 +19,13 @@ pub struct PointLightData {
     pub constant: float,
     pub linear: float,
     pub quadratic: float,
+    pub range: float,
+    pub shadow_index: int,
+    pub bias: float,
+    pub normal_bias: float,
+    pub pcf_radius: int,
+    pub near: float,
+    pub far: float,
 }
 
 impl PointLightData {
@@ -28,6 +37,13 @@ impl PointLightData {
             constant: 0.0,
             linear: 0.0,
             quadratic: 0.0,
+            range: 0.0,
+            shadow_index: -1,
+            bias: 0.0,
+            normal_bias: 0.0,
+            pcf_radius: 0,
+            near: 0.0,
+            far: 0.0,
         }
     }
 }
@@ -38,6 +54,8 @@ pub struct PointLight {
     pub constant: f32,
     pub linear: f32,
     pub quadratic: f32,
+    pub shadow: ShadowSettings,
+    pub(crate) shadow_index: Option<usize>,
 }
 
 impl PointLight {
@@ -60,6 +78,8 @@ impl PointLight {
             constant,
             linear,
             quadratic,
+            shadow: ShadowSettings::default(),
+            shadow_index: None,
         };
         if let Some(parent) = parent {
             parent.base
@@ -79,13 +99,24 @@ impl PointLight {
             constant,
             linear,
             quadratic,
+            shadow: ShadowSettings::default(),
+            shadow_index: None,
         };
         light
     }
 
-    pub fn light_data(&self) -> Option<PointLightData> {
+    pub fn with_shadow(mut self, shadow: ShadowSettings) -> Self {
+        self.shadow = shadow;
+        self
+    }
+
+    pub fn position(&self) -> Option<Vec3> {
         let transform:Transform = self.game_object.upgrade()?.borrow().global_mat().into();
-        let position = transform.position;
+        Some(transform.position)
+    }
+
+    pub fn light_data(&self) -> Option<PointLightData> {
+        let position = self.position()?;
         Some(PointLightData {
             intensity: self.intensity,
             color: vec3::from([self.color.x, self.color.y, self.color.z]),
@@ -93,6 +124,13 @@ impl PointLight {
             constant: self.constant,
             linear: self.linear,
             quadratic: self.quadratic,
+            range: attenuation_range(self.intensity, self.color, self.constant, self.linear, self.quadratic),
+            shadow_index: self.shadow_index.map_or(-1, |index| index as i32),
+            bias: self.shadow.bias,
+            normal_bias: self.shadow.normal_bias,
+            pcf_radius: self.shadow.pcf_radius,
+            near: self.shadow.near,
+            far: self.shadow.far,
         })
     }
 }
\ No newline at end of file
@@ -0,0 +1,284 @@
+use gl::types::{GLenum, GLint, GLuint};
+use glam::{vec3, Mat4, Vec3};
+
+use crate::engine::config::CONFIG;
+use crate::engine::drawable::manager::DRAWABLE_MANAGER;
+use crate::engine::drawable::shader::manager::IncludedShaderHandle;
+use crate::engine::drawable::shader::Shader;
+use crate::engine::drawable::Drawable;
+use crate::engine::scene::camera::Camera;
+use crate::engine::scene::gameobject::GameObject;
+
+pub const SHADOW_CASCADES: usize = 3;
+pub const MAX_SPOT_SHADOWS: usize = 4;
+pub const MAX_POINT_SHADOWS: usize = 4;
+
+const DIRECTIONAL_RESOLUTION: u32 = 2048;
+const SPOT_RESOLUTION: u32 = 1024;
+const POINT_RESOLUTION: u32 = 512;
+// how far behind a cascade shadow casters are still rendered
+const DIRECTIONAL_CASTER_MARGIN: f32 = 100.0;
+const CASCADE_SPLIT_LAMBDA: f32 = 0.75;
+
+#[derive(Debug, Copy, Clone)]
+pub struct ShadowSettings {
+    pub enabled: bool,
+    pub bias: f32,
+    pub normal_bias: f32,
+    pub pcf_radius: i32,
+    // depth range of spot and point shadow maps, for directional lights far is the shadow distance
+    pub near: f32,
+    pub far: f32,
+}
+
+impl ShadowSettings {
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            bias: 0.0005,
+            normal_bias: 0.02,
+            pcf_radius: 1,
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+}
+
+pub fn directional_cascades(direction: Vec3, camera: &Camera, settings: &ShadowSettings) -> [Mat4; SHADOW_CASCADES] {
+    let config = CONFIG.config();
+    let (near, far) = config.get_clip();
+    let far = far.min(settings.far);
+    let aspect = config.get_resolution_x() as f32 / config.get_resolution_y() as f32;
+    let view = camera.game_object.global_mat().inverse();
+    let direction = direction.normalize();
+    let up = if direction.y.abs() > 0.99 { Vec3::Z } else { Vec3::Y };
+
+    let split = |i: usize| {
+        let p = i as f32 / SHADOW_CASCADES as f32;
+        let log = near * (far / near).powf(p);
+        let uniform = near + (far - near) * p;
+        CASCADE_SPLIT_LAMBDA * log + (1.0 - CASCADE_SPLIT_LAMBDA) * uniform
+    };
+
+    let mut cascades = [Mat4::IDENTITY; SHADOW_CASCADES];
+    for (i, cascade) in cascades.iter_mut().enumerate() {
+        let projection = Mat4::perspective_rh_gl(config.get_fov().to_radians(), aspect, split(i), split(i + 1));
+        let inverse = (projection * view).inverse();
+        let mut corners = [Vec3::ZERO; 8];
+        for (j, corner) in corners.iter_mut().enumerate() {
+            let ndc = vec3(
+                if j & 1 == 0 { -1.0 } else { 1.0 },
+                if j & 2 == 0 { -1.0 } else { 1.0 },
+                if j & 4 == 0 { -1.0 } else { 1.0 },
+            );
+            *corner = inverse.project_point3(ndc);
+        }
+        let center = corners.iter().sum::<Vec3>() / 8.0;
+        let radius = corners
+            .iter()
+            .map(|corner| corner.distance(center))
+            .fold(0.0_f32, f32::max)
+            .ceil();
+
+        // snap the cascade to whole texels so the shadow edges do not shimmer when the camera moves
+        let texel = 2.0 * radius / DIRECTIONAL_RESOLUTION as f32;
+        let rotation = Mat4::look_at_rh(Vec3::ZERO, direction, up);
+        let mut snapped = rotation.transform_point3(center);
+        snapped.x = (snapped.x / texel).floor() * texel;
+        snapped.y = (snapped.y / texel).floor() * texel;
+        let center = rotation.inverse().transform_point3(snapped);
+
+        let eye = center - direction * (radius + DIRECTIONAL_CASTER_MARGIN);
+        let light_view = Mat4::look_at_rh(eye, center, up);
+        let light_projection = Mat4::orthographic_rh_gl(
+            -radius,
+            radius,
+            -radius,
+            radius,
+            0.0,
+            2.0 * radius + DIRECTIONAL_CASTER_MARGIN,
+        );
+        *cascade = light_projection * light_view;
+    }
+    cascades
+}
+
+pub fn spot_light_space(position: Vec3, direction: Vec3, outer_cut_off: f32, settings: &ShadowSettings) -> Mat4 {
+    let direction = direction.normalize();
+    let up = if direction.y.abs() > 0.99 { Vec3::Z } else { Vec3::Y };
+    let fov = (2.0 * outer_cut_off).to_radians().min(std::f32::consts::PI * 0.99);
+    let projection = Mat4::perspective_rh_gl(fov, 1.0, settings.near, settings.far);
+    projection * Mat4::look_at_rh(position, position + direction, up)
+}
+
+pub fn point_light_spaces(position: Vec3, settings: &ShadowSettings) -> [Mat4; 6] {
+    cube_face_spaces(position, settings.near, settings.far)
+}
+
+// View projections looking out of the position through each face, in the order of the GL cube map faces
+pub fn cube_face_spaces(position: Vec3, near: f32, far: f32) -> [Mat4; 6] {
+    let projection = Mat4::perspective_rh_gl(std::f32::consts::FRAC_PI_2, 1.0, near, far);
+    let faces = [
+        (Vec3::X, -Vec3::Y),
+        (-Vec3::X, -Vec3::Y),
+        (Vec3::Y, Vec3::Z),
+        (-Vec3::Y, -Vec3::Z),
+        (Vec3::Z, -Vec3::Y),
+        (-Vec3::Z, -Vec3::Y),
+    ];
+    faces.map(|(target, up)| projection * Mat4::look_at_rh(position, position + target, up))
+}
+
+fn depth_texture(target: GLenum, resolution: u32, layers: u32) -> GLuint {
+    let mut texture = 0;
+    unsafe {
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(target, texture);
+        match target {
+            gl::TEXTURE_2D_ARRAY => gl::TexStorage3D(
+                target,
+                1,
+                gl::DEPTH_COMPONENT32F,
+                resolution as i32,
+                resolution as i32,
+                layers as i32,
+            ),
+            _ => gl::TexStorage2D(target, 1, gl::DEPTH_COMPONENT32F, resolution as i32, resolution as i32),
+        }
+        gl::TexParameteri(target, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+        gl::TexParameteri(target, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+        gl::TexParameteri(target, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(target, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(target, gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE as i32);
+        gl::TexParameteri(target, gl::TEXTURE_COMPARE_MODE, gl::COMPARE_REF_TO_TEXTURE as i32);
+        gl::TexParameteri(target, gl::TEXTURE_COMPARE_FUNC, gl::LEQUAL as i32);
+        gl::BindTexture(target, 0);
+    }
+    texture
+}
+
+pub struct ShadowMaps {
+    fbo: GLuint,
+    pub directional: GLuint,
+    pub spot: GLuint,
+    pub point: [GLuint; MAX_POINT_SHADOWS],
+}
+
+impl ShadowMaps {
+    pub fn new() -> Self {
+        let mut fbo = 0;
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+        }
+        Self {
+            fbo,
+            directional: depth_texture(gl::TEXTURE_2D_ARRAY, DIRECTIONAL_RESOLUTION, SHADOW_CASCADES as u32),
+            spot: depth_texture(gl::TEXTURE_2D_ARRAY, SPOT_RESOLUTION, MAX_SPOT_SHADOWS as u32),
+            point: [(); MAX_POINT_SHADOWS].map(|_| depth_texture(gl::TEXTURE_CUBE_MAP, POINT_RESOLUTION, 1)),
+        }
+    }
+
+    // Draws the shadow casters of the scene from the light's point of view into the given layer.
+    fn render_layer(&self, objects: &Vec<GameObject>, shader: &Shader, light_space: &Mat4, resolution: u32, attach: impl Fn()) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            attach();
+            gl::Viewport(0, 0, resolution as i32, resolution as i32);
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+        }
+        shader.set_mat4("light_space_mat", light_space);
+        for object in objects {
+            object.base.borrow_mut().draw_shadow(&Mat4::IDENTITY, shader);
+        }
+    }
+
+    pub fn render_directional(&self, objects: &Vec<GameObject>, shader: &Shader, cascades: &[Mat4]) {
+        for (layer, light_space) in cascades.iter().enumerate() {
+            self.render_layer(objects, shader, light_space, DIRECTIONAL_RESOLUTION, || unsafe {
+                gl::FramebufferTextureLayer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, self.directional, 0, layer as i32);
+            });
+        }
+    }
+
+    pub fn render_spot(&self, objects: &Vec<GameObject>, shader: &Shader, index: usize, light_space: &Mat4) {
+        self.render_layer(objects, shader, light_space, SPOT_RESOLUTION, || unsafe {
+            gl::FramebufferTextureLayer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, self.spot, 0, index as i32);
+        });
+    }
+
+    pub fn render_point(&self, objects: &Vec<GameObject>, shader: &Shader, index: usize, faces: &[Mat4; 6]) {
+        for (face, light_space) in faces.iter().enumerate() {
+            self.render_layer(objects, shader, light_space, POINT_RESOLUTION, || unsafe {
+                gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    gl::DEPTH_ATTACHMENT,
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as u32,
+                    self.point[index],
+                    0,
+                );
+            });
+        }
+    }
+
+    // Binds the depth-only program and framebuffer, the previous framebuffer, viewport
+    // and culling are restored once the passes are done.
+    pub fn with_shadow_pass<F: FnOnce(&ShadowMaps, &Shader)>(&self, passes: F) {
+        let mut previous_fbo: GLint = 0;
+        let mut previous_viewport: [GLint; 4] = [0; 4];
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut previous_fbo);
+            gl::GetIntegerv(gl::VIEWPORT, previous_viewport.as_mut_ptr());
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            let draw_buffers = [gl::NONE];
+            gl::DrawBuffers(1, draw_buffers.as_ptr());
+            gl::ReadBuffer(gl::NONE);
+            // rendering back faces moves most of the acne to the unlit side of the casters
+            gl::CullFace(gl::FRONT);
+        }
+        DRAWABLE_MANAGER.with(|dm| {
+            let draw_manager = dm.borrow();
+            let shader = draw_manager.shader.get_included(&IncludedShaderHandle::ShadowDepth);
+            shader.use_program();
+            passes(self, shader);
+        });
+        unsafe {
+            gl::CullFace(gl::BACK);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, previous_fbo as GLuint);
+            gl::Viewport(
+                previous_viewport[0],
+                previous_viewport[1],
+                previous_viewport[2],
+                previous_viewport[3],
+            );
+        }
+        Shader::unbind();
+    }
+
+    pub fn bind(&self, shader: &Shader) {
+        shader.add_texture("directional_shadow_map", self.directional, gl::TEXTURE_2D_ARRAY);
+        shader.add_texture("spot_shadow_maps", self.spot, gl::TEXTURE_2D_ARRAY);
+        for (i, texture) in self.point.iter().enumerate() {
+            shader.add_texture(&format!("point_shadow_maps[{}]", i), *texture, gl::TEXTURE_CUBE_MAP);
+        }
+    }
+}
+
+impl Drop for ShadowMaps {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteTextures(1, &self.directional);
+            gl::DeleteTextures(1, &self.spot);
+            gl::DeleteTextures(MAX_POINT_SHADOWS as i32, self.point.as_ptr());
+        }
+    }
+}
@@ -1,11 +1,13 @@
-use glam::Vec3;
-use glsl_layout::{float, vec3, Uniform};
+use glam::{Mat4, Vec3};
+use glsl_layout::{float, int, mat4, vec3, Uniform};
 use std::cell::RefCell;
 use std::rc::{Rc, Weak};
 
 use crate::engine::scene::gameobject::components::ComponentMap;
 use crate::engine::scene::gameobject::{GameObject};
 use crate::engine::scene::gameobject::base::{BaseGameObject, GameObjectData};
+use crate::engine::scene::lights::attenuation_range;
+use crate::engine::scene::lights::shadow::{self, ShadowSettings};
 use crate::engine::transform::Transform;
 
 #[derive(Debug, Copy, Default, Clone, Uniform)]
@@ -19,6 +21,12 @@ pub struct SpotLightData {
     pub quadratic: float,
     pub cut_off: float,
     pub outer_cut_off: float,
+    pub range: float,
+    pub shadow_index: int,
+    pub bias: float,
+    pub normal_bias: float,
+    pub pcf_radius: int,
+    pub light_space: mat4,
 }
 
 impl SpotLightData {
@@ -33,6 +41,12 @@ impl SpotLightData {
             quadratic: 0.0,
             cut_off: 0.0,
             outer_cut_off: 0.0,
+            range: 0.0,
+            shadow_index: -1,
+            bias: 0.0,
+            normal_bias: 0.0,
+            pcf_radius: 0,
+            light_space: mat4::default(),
         }
     }
 }
@@ -46,6 +60,8 @@ pub struct SpotLight {
     pub quadratic: f32,
     pub cut_off: f32,
     pub outer_cut_off: f32,
+    pub shadow: ShadowSettings,
+    pub(crate) shadow_slot: Option<(usize, Mat4)>,
 }
 
 impl SpotLight {
@@ -72,6 +88,8 @@ impl SpotLight {
             quadratic,
             cut_off,
             outer_cut_off,
+            shadow: ShadowSettings::default(),
+            shadow_slot: None,
         };
         if let Some(parent) = parent {
             parent.base
@@ -93,10 +111,22 @@ impl SpotLight {
             quadratic,
             cut_off,
             outer_cut_off,
+            shadow: ShadowSettings::default(),
+            shadow_slot: None,
         };
         light
     }
 
+    pub fn with_shadow(mut self, shadow: ShadowSettings) -> Self {
+        self.shadow = shadow;
+        self
+    }
+
+    pub fn light_space(&self) -> Option<Mat4> {
+        let mat:Transform = self.game_object.upgrade()?.borrow().global_mat().into();
+        Some(shadow::spot_light_space(mat.position, mat.forward(), self.outer_cut_off, &self.shadow))
+    }
+
     pub fn light_data(&self) -> Option<SpotLightData> {
         let mat:Transform = self.game_object.upgrade()?.borrow().global_mat().into();
         let position = mat.position;
@@ -112,6 +142,12 @@ impl SpotLight {
             quadratic: self.quadratic,
             cut_off: self.cut_off,
             outer_cut_off: self.outer_cut_off,
+            range: attenuation_range(self.intensity, self.color, self.constant, self.linear, self.quadratic),
+            shadow_index: self.shadow_slot.map_or(-1, |(index, _)| index as i32),
+            bias: self.shadow.bias,
+            normal_bias: self.shadow.normal_bias,
+            pcf_radius: self.shadow.pcf_radius,
+            light_space: self.shadow_slot.map_or(Mat4::IDENTITY, |(_, light_space)| light_space).to_cols_array_2d().into(),
         })
     }
 }
\ No newline at end of file
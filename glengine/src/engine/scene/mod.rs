@@ -3,7 +3,11 @@ use std::rc::{Rc, Weak};
 
 use glam::{vec3, Mat4};
 use crate::engine::drawable::Drawable;
+use crate::engine::drawable::queue::RENDER_QUEUE;
+use crate::engine::fbo::{DeferredFbo, Fbo};
+use crate::engine::config::CONFIG;
 use crate::engine::scene::camera::Camera;
+use crate::engine::scene::culling::{CullingStats, Frustum};
 use crate::engine::scene::gameobject::{GameObject};
 use crate::engine::GameState;
 use crate::engine::scene::gameobject::components::collider::ColliderComponent;
@@ -11,6 +15,7 @@ use crate::engine::scene::gameobject::components::rigidbody::RigidBodyComponent;
 use crate::result::{EngineRenderResult, EngineStepResult};
 
 pub mod camera;
+pub mod culling;
 pub mod gameobject;
 pub mod lights;
 
@@ -18,6 +23,8 @@ pub struct Scene {
     pub objects: Vec<GameObject>,
     pub main_camera: Option<Camera>,
     pub lights: lights::Lights,
+    // objects drawn and culled by the last render, counted when frustum culling is enabled
+    pub culling: CullingStats,
     init_gl_cb: fn(&mut Scene) -> EngineRenderResult<()>
 }
 
@@ -27,10 +34,12 @@ impl Scene {
             objects:vec![],
             main_camera:None,
             lights:lights::Lights::default(),
+            culling:CullingStats::default(),
             init_gl_cb
         }
     }
     pub fn init_gl(&mut self) -> EngineRenderResult<()>{
+        self.lights.init_shadow_maps();
         (self.init_gl_cb)(self)
     }
     pub fn render(&mut self) {
@@ -38,16 +47,106 @@ impl Scene {
             let camera_mat = camera.game_object.global_mat();
             
             let viewmat: Mat4 = camera_mat.inverse();
-            self.lights.update_ssbo();
+            self.lights.render_shadows(&self.objects, camera);
+            self.lights.update_ssbo(&viewmat);
+            self.culling = CullingStats::default();
+            let frustum = Self::frustum(&self.objects, camera);
+            RENDER_QUEUE.with(|queue| queue.borrow_mut().begin());
             for object in &self.objects {
-                object.base.borrow_mut().draw(
+                match &frustum {
+                    Some(frustum) => object.base.borrow_mut().draw_culled(
+                        &Mat4::IDENTITY,
+                        &viewmat,
+                        frustum,
+                        &mut self.culling,
+                        &mut |drawable, modelmat| drawable.draw(modelmat, &viewmat, Some(&self.lights)),
+                    ),
+                    None => object.base.borrow_mut().draw(
                         &Mat4::from_translation(vec3(0.0, 0.0, 0.0)),
                         &viewmat,
                         Some(&self.lights),
-                    );
+                    ),
+                }
             }
+            RENDER_QUEUE.with(|queue| queue.borrow_mut().execute(&viewmat, Some(&self.lights)));
         }
     }
+
+    // The camera frustum with the bounds of the objects updated, None when culling is disabled.
+    fn frustum(objects: &[GameObject], camera: &Camera) -> Option<Frustum> {
+        if !CONFIG.config().get_frustum_culling() {
+            return None;
+        }
+        for object in objects {
+            object.base.borrow_mut().update_bounds(&Mat4::IDENTITY);
+        }
+        Some(Frustum::new(camera))
+    }
+
+    // Builds the G-buffer from the lit objects, lights it into the target and draws
+    // the remaining objects forward on top of it.
+    pub fn render_deferred(&mut self, deferred: &mut DeferredFbo, target: &Fbo) {
+        if let Some(camera) = &self.main_camera {
+            let camera_mat = camera.game_object.global_mat();
+
+            let viewmat: Mat4 = camera_mat.inverse();
+            self.lights.render_shadows(&self.objects, camera);
+            self.lights.update_ssbo(&viewmat);
+
+            deferred.gbuffer.bind();
+            deferred.gbuffer.clear();
+            unsafe {
+                // the coverage stored in the alpha must not be blended
+                gl::Disable(gl::BLEND);
+            }
+            self.culling = CullingStats::default();
+            let frustum = Self::frustum(&self.objects, camera);
+            RENDER_QUEUE.with(|queue| queue.borrow_mut().begin());
+            for object in &self.objects {
+                match &frustum {
+                    // counted once, by the forward pass
+                    Some(frustum) => object.base.borrow_mut().draw_culled(
+                        &Mat4::IDENTITY,
+                        &viewmat,
+                        frustum,
+                        &mut CullingStats::default(),
+                        &mut |drawable, modelmat| drawable.draw_geometry(modelmat, &viewmat),
+                    ),
+                    None => object.base.borrow_mut().draw_geometry(&Mat4::IDENTITY, &viewmat),
+                }
+            }
+            RENDER_QUEUE.with(|queue| queue.borrow_mut().execute(&viewmat, None));
+
+            target.bind();
+            unsafe {
+                gl::DepthFunc(gl::ALWAYS);
+            }
+            deferred.draw_data.draw(&Mat4::IDENTITY, &viewmat, Some(&self.lights));
+            unsafe {
+                gl::DepthFunc(gl::LESS);
+            }
+
+            RENDER_QUEUE.with(|queue| queue.borrow_mut().begin());
+            for object in &self.objects {
+                match &frustum {
+                    Some(frustum) => object.base.borrow_mut().draw_culled(
+                        &Mat4::IDENTITY,
+                        &viewmat,
+                        frustum,
+                        &mut self.culling,
+                        &mut |drawable, modelmat| drawable.draw(modelmat, &viewmat, Some(&self.lights)),
+                    ),
+                    None => object.base.borrow_mut().draw(
+                        &Mat4::IDENTITY,
+                        &viewmat,
+                        Some(&self.lights),
+                    ),
+                }
+            }
+            RENDER_QUEUE.with(|queue| queue.borrow_mut().execute(&viewmat, Some(&self.lights)));
+        }
+    }
+
     pub fn step_recursive(&mut self, state: &GameState) -> EngineStepResult<()> {
         for object in &mut self.objects {
             object
@@ -29,6 +29,10 @@ pub enum EngineRenderError {
     CreateShaderError(#[from] ShaderError),
     #[error("Could not join thread")]
     JoinThreadError,
+    #[error("Unsupported OpenGL context: {0}")]
+    UnsupportedContext(String),
+    #[error("Failed to load texture: {0}")]
+    TextureLoadError(String),
 }
 
 #[derive(Error, Debug, Clone)]